@@ -0,0 +1,68 @@
+use crate::control::Tuning;
+use crate::{backend, Config};
+use rand::{thread_rng, Rng};
+use separator::Separatable;
+use std::error::Error;
+use std::time::Instant;
+
+/// Run `dispatches` dispatches of the configured work size on the configured
+/// device, after one untimed warm-up, and print the hash rate, the latency of
+/// each dispatch and how many solutions an hour the thresholds (or minimum
+/// score) would yield. Nothing is written to the results file or submitted,
+/// so kernels, work sizes and devices can be compared without a real job.
+pub fn bench(config: &Config, dispatches: u32) -> Result<(), Box<dyn Error>> {
+    let device = config.gpu_device;
+    println!(
+        "benchmarking device {device}: {dispatches} dispatches of {:#x} work items",
+        config.work_size
+    );
+
+    let mut backend = backend(config, &Tuning::new(config), device, config.work_size)?;
+    let mut rng = thread_rng();
+    let message: [u8; 4] = rng.gen();
+    let mut nonce: u32 = rng.gen();
+
+    // the first dispatch includes any lazy setup in the driver
+    backend.dispatch(0, &message, nonce, config.work_size)?;
+
+    let mut latencies = Vec::with_capacity(dispatches as usize);
+    let mut solutions: u64 = 0;
+    let start = Instant::now();
+    for _ in 0..dispatches {
+        nonce = nonce.wrapping_add(1);
+        let dispatch_start = Instant::now();
+        let dispatch = backend.dispatch(0, &message, nonce, config.work_size)?;
+        latencies.push(dispatch_start.elapsed().as_secs_f64() * 1000.0);
+        solutions += dispatch.count as u64;
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+
+    let hashes = config.work_size as f64 * dispatches as f64;
+    let mean = latencies.iter().sum::<f64>() / dispatches as f64;
+    let min = latencies.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = latencies.iter().copied().fold(0.0, f64::max);
+    let per_hour = (solutions as f64 * 3600.0 / elapsed) as u64;
+    let filter = match config.scoring {
+        Some(scoring) if scoring.min_score != 0 => {
+            format!("a score of at least {}", scoring.min_score)
+        }
+        _ => format!(
+            "{} leading or {} total zeroes",
+            config.leading_zeroes_threshold, config.total_zeroes_threshold
+        ),
+    };
+
+    println!("elapsed: {elapsed:.2} s");
+    println!(
+        "rate: {:.2} million attempts per second",
+        hashes / 1_000_000.0 / elapsed
+    );
+    println!("latency per dispatch: min {min:.1} ms, mean {mean:.1} ms, max {max:.1} ms");
+    println!(
+        "solutions at {filter}: {} ({} per hour)",
+        solutions.separated_string(),
+        per_hour.separated_string()
+    );
+
+    Ok(())
+}
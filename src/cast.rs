@@ -0,0 +1,43 @@
+use crate::miner::Solution;
+use alloy_primitives::hex;
+
+/// The deployment function of a well-known factory, for which a `cast send`
+/// command is generated with every result (`--factory-abi`) so that the salt
+/// does not have to be put back together by hand.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum FactoryAbi {
+    /// `safeCreate2(bytes32,bytes)`, as on the ImmutableCreate2Factory.
+    ImmutableCreate2,
+    /// `deployCreate2(bytes32,bytes)`, as on CreateX.
+    Createx,
+    /// `deployCreate3(bytes32,bytes)`, as on CreateX.
+    CreatexCreate3,
+    /// `deploy(bytes32,bytes)`, as on most other CREATE2 and CREATE3
+    /// factories.
+    Deploy,
+}
+
+impl FactoryAbi {
+    pub fn signature(&self) -> &'static str {
+        match self {
+            Self::ImmutableCreate2 => "safeCreate2(bytes32,bytes)",
+            Self::Createx => "deployCreate2(bytes32,bytes)",
+            Self::CreatexCreate3 => "deployCreate3(bytes32,bytes)",
+            Self::Deploy => "deploy(bytes32,bytes)",
+        }
+    }
+
+    /// The `cast send` command that deploys a solution, with the init code if
+    /// it is known and a `$INIT_CODE` placeholder otherwise.
+    pub(crate) fn cast_command(&self, solution: &Solution, init_code: Option<&[u8]>) -> String {
+        let init_code = init_code
+            .map(hex::encode_prefixed)
+            .unwrap_or_else(|| "$INIT_CODE".to_string());
+        format!(
+            "cast send {} \"{}\" {} {init_code}",
+            solution.factory,
+            self.signature(),
+            solution.salt
+        )
+    }
+}
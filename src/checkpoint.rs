@@ -0,0 +1,238 @@
+use crate::Config;
+use alloy_primitives::{hex, keccak256};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// The stream walked by the CPU.
+pub(crate) const CPU_STREAM: &str = "cpu";
+
+/// How far one stream of a deterministic search has got: the number of the
+/// salt segment that it is walking, and how far into that segment it is (the
+/// index into the nonces of every target on the CPU, or the nonce on a GPU).
+/// Everything before it has been searched and recorded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Position {
+    pub(crate) segment: u64,
+    pub(crate) offset: u64,
+}
+
+/// The progress of a deterministic search (`--deterministic`), saved to disk
+/// every so often so that it can be resumed (`--resume`) after a crash or
+/// reboot without redoing work. The CPU walks a single stream of segments
+/// (`cpu`), while each device walks one per target (`<device>/<target>`).
+///
+/// A checkpoint is only valid for the same search: the same mode, factories,
+/// callers (or free salts), init code hash, salt prefix, nonce start and
+/// stride, share of the fleet, devices and work size, which are kept as a
+/// fingerprint.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct Checkpoint {
+    search: String,
+    positions: BTreeMap<String, Position>,
+    #[serde(skip)]
+    start: u64,
+}
+
+impl Checkpoint {
+    /// A checkpoint for a search that starts at the configured segment.
+    pub(crate) fn new(config: &Config) -> Self {
+        Self {
+            search: fingerprint(config),
+            positions: BTreeMap::new(),
+            start: config.start_segment,
+        }
+    }
+
+    /// The checkpoint to resume from if resuming and there is one, or else a
+    /// new one.
+    pub(crate) fn load(config: &Config) -> Result<Self, Box<dyn Error>> {
+        let path = &config.checkpoint;
+        if !config.resume || !path.exists() {
+            if config.resume {
+                info!(
+                    "no checkpoint at {}, starting from segment {}",
+                    path.display(),
+                    config.start_segment
+                );
+            }
+            return Ok(Self::new(config));
+        }
+
+        let checkpoint = Self::open(config)?;
+        info!("resuming from {}", path.display());
+        Ok(checkpoint)
+    }
+
+    /// The checkpoint saved for the search, which must be there.
+    pub(crate) fn open(config: &Config) -> Result<Self, Box<dyn Error>> {
+        let path = &config.checkpoint;
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        let mut checkpoint: Self =
+            serde_json::from_str(&text).map_err(|e| format!("{}: {e}", path.display()))?;
+        if checkpoint.search != fingerprint(config) {
+            return Err(format!("{} is for a different search", path.display()).into());
+        }
+        checkpoint.start = config.start_segment;
+        Ok(checkpoint)
+    }
+
+    /// Where the given stream is to carry on from.
+    pub(crate) fn position(&self, stream: &str) -> Position {
+        self.positions.get(stream).copied().unwrap_or(Position {
+            segment: self.start,
+            offset: 0,
+        })
+    }
+
+    pub(crate) fn advance(&mut self, stream: String, position: Position) {
+        self.positions.insert(stream, position);
+    }
+
+    /// Write the checkpoint to `path`, replacing the previous one in a single
+    /// step so that a crash never leaves a partial checkpoint behind.
+    pub(crate) fn save(&self, path: &Path) -> std::io::Result<()> {
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+
+        let mut file = File::create(&tmp)?;
+        writeln!(file, "{}", serde_json::to_string_pretty(self)?)?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    }
+}
+
+/// The stream walked by a device for a target.
+pub(crate) fn gpu_stream(device: u8, target: usize) -> String {
+    format!("{device}/{target}")
+}
+
+/// A hash of everything that decides which salts a deterministic search
+/// covers, other than where it starts.
+fn fingerprint(config: &Config) -> String {
+    let mut search = format!(
+        "{:?} {:?} {:?} {:?} {} {} {:?} {} {}/{} {}",
+        config.mode,
+        config.factory_addresses,
+        config.salt_mode,
+        config.calling_addresses,
+        hex::encode(config.init_code_hash),
+        hex::encode(&config.salt_prefix),
+        config.nonce_start,
+        config.nonce_stride,
+        config.worker_id,
+        config.worker_count,
+        config.gpu_device,
+    );
+    if config.gpu_device != 255 {
+        search += &format!(" {:?} {}", config.extra_gpu_devices, config.work_size);
+    }
+    hex::encode(keccak256(search))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "create2crunch-{}-checkpoint-{name}.json",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    // a resumed deterministic search on a GPU, changed as given
+    fn config(checkpoint: &Path, change: impl FnOnce(&mut Config)) -> Config {
+        let mut config = Config {
+            checkpoint: checkpoint.to_path_buf(),
+            resume: true,
+            deterministic: true,
+            gpu_device: 0,
+            ..Config::default()
+        };
+        change(&mut config);
+        config
+    }
+
+    #[test]
+    fn round_trip() {
+        let path = path("round-trip");
+        let search = config(&path, |_| ());
+        // nothing to resume from yet
+        let mut checkpoint = Checkpoint::load(&search).unwrap();
+        assert_eq!(
+            checkpoint.position(CPU_STREAM),
+            Position {
+                segment: search.start_segment,
+                offset: 0
+            }
+        );
+
+        let cpu = Position {
+            segment: 7,
+            offset: 1234,
+        };
+        let gpu = Position {
+            segment: 3,
+            offset: 99,
+        };
+        checkpoint.advance(CPU_STREAM.to_string(), cpu);
+        checkpoint.advance(gpu_stream(1, 2), gpu);
+        checkpoint.save(&path).unwrap();
+
+        let loaded = Checkpoint::load(&search).unwrap();
+        assert_eq!(loaded.position(CPU_STREAM), cpu);
+        assert_eq!(loaded.position(&gpu_stream(1, 2)), gpu);
+        // streams that had not started yet begin at the start segment
+        let later = config(&path, |c| c.start_segment = 5);
+        assert_eq!(
+            Checkpoint::load(&later)
+                .unwrap()
+                .position(&gpu_stream(0, 0)),
+            Position {
+                segment: 5,
+                offset: 0
+            }
+        );
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn rejects_other_searches() {
+        let path = path("other");
+        Checkpoint::new(&config(&path, |_| ())).save(&path).unwrap();
+        assert!(Checkpoint::load(&config(&path, |_| ())).is_ok());
+
+        let changes = [
+            (
+                "caller",
+                (|c| c.calling_addresses = vec![[1; 20]]) as fn(&mut Config),
+            ),
+            ("salt prefix", |c| c.salt_prefix = vec![0xab]),
+            ("worker id", |c| (c.worker_id, c.worker_count) = (1, 2)),
+            ("worker count", |c| c.worker_count = 2),
+            ("work size", |c| c.work_size *= 2),
+        ];
+        for (change, apply) in changes {
+            let Err(error) = Checkpoint::load(&config(&path, apply)) else {
+                panic!("loaded after changing the {change}");
+            };
+            assert!(
+                error.to_string().ends_with("is for a different search"),
+                "{change}: {error}"
+            );
+        }
+
+        // without resuming, the old checkpoint is ignored
+        let fresh = config(&path, |c| (c.resume, c.worker_count) = (false, 2));
+        assert!(Checkpoint::load(&fresh).is_ok());
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,1530 @@
+use crate::leaderboard;
+use crate::replay;
+use crate::results::{self, BestFormat};
+use crate::{
+    fetch_init_code, simple_account_init_code, BatteryAction, Config, FactoryAbi, JobFile,
+    LogFormat, Mode, Pattern, PayloadFormat, PayloadTemplate, Reserved, ReservedAction, SaltMode,
+    Scoring, ScoringMode, CHECKPOINT_FILE, DEFAULT_LEADERBOARD_SIZE, DEFAULT_SELFTEST_COUNT,
+    DEFAULT_SOLUTIONS_FRACTION, DEFAULT_VERIFY_SAMPLE, DEFAULT_WORK_SIZE, LEADERBOARD_FILE,
+    OUTPUT_FILE, SPOOL_FILE, WARM_START_SEEDS,
+};
+use alloy_primitives::{hex, keccak256, Address, Bytes, FixedBytes};
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+use reqwest::header::{HeaderName, HeaderValue};
+use std::error::Error;
+use std::io;
+use std::path::PathBuf;
+
+/// Command line interface for create2crunch. Running without a subcommand
+/// starts a search using the positional arguments or their named equivalents.
+#[derive(Debug, Parser)]
+#[command(
+    name = "create2crunch",
+    version,
+    about = "Find salts that create gas-efficient Ethereum addresses via CREATE2.",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[command(flatten)]
+    pub mine: MineArgs,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Print a shell completion script to stdout.
+    Completions {
+        /// The shell to generate completions for.
+        shell: Shell,
+    },
+    /// Print a roff man page to stdout.
+    Man,
+    /// Search a salt, or a stretch of salts, again as the search described by
+    /// the other arguments did, and print the addresses that qualify, either on
+    /// the host or by re-running the work items on an OpenCL device.
+    Replay {
+        /// The full 32-byte salt to check, or with --count the first of the
+        /// salts to search again. Its caller need not be given.
+        #[arg(long, value_parser = parse_hash, required_unless_present_any = ["segment", "stream"])]
+        salt: Option<[u8; 32]>,
+
+        /// Salt segment to search again from --nonce, in place of --salt: the
+        /// 6 bytes after the caller on the CPU, or 4 on a GPU.
+        #[arg(long, value_parser = parse_segment, conflicts_with_all = ["salt", "stream"], requires = "nonce")]
+        segment: Option<Bytes>,
+
+        /// Nonce to start from in the --segment: the 6-byte nonce on the CPU,
+        /// or on a GPU the dispatch nonce (upper 4 bytes) and the first work
+        /// item (lower 4 bytes), in decimal or 0x-prefixed hex.
+        #[arg(long, value_parser = parse_nonce, requires = "segment")]
+        nonce: Option<u64>,
+
+        /// Search again from where a stream of a deterministic search (`cpu`,
+        /// or `<device>/<target>`) has got to in its --checkpoint.
+        #[arg(long, conflicts_with_all = ["salt", "segment"])]
+        stream: Option<String>,
+
+        /// Number of salts to search again (by default one with --salt, and
+        /// otherwise a dispatch on a GPU, or as many as the CPU searches
+        /// between checkpoints).
+        #[arg(long)]
+        count: Option<u64>,
+
+        #[command(flatten)]
+        search: Box<MineArgs>,
+    },
+    /// Print the best result found so far, ready to paste into a deployment script.
+    Best {
+        /// Results files to read (which may also be JSON lines, as written
+        /// with --output-file).
+        #[arg(default_value = OUTPUT_FILE)]
+        inputs: Vec<PathBuf>,
+
+        /// Read the results from this SQLite database (as written with `--db`)
+        /// instead of results files.
+        #[arg(long, conflicts_with = "inputs")]
+        db: Option<PathBuf>,
+
+        /// Only take the results found for this job (as tagged with --job-id),
+        /// which plain results files do not record.
+        #[arg(long)]
+        job: Option<String>,
+
+        /// Also ask the status server of a running search (or a coordinator
+        /// serving its status in the same shape) for the best result of its
+        /// run, e.g. `http://10.0.0.5:8080/status`; may be given more than once.
+        #[arg(long)]
+        status: Vec<String>,
+
+        /// How to format the result.
+        #[arg(long, value_enum, default_value_t = BestFormat::Solidity)]
+        format: BestFormat,
+    },
+    /// Compare the OpenCL kernel on a device against the host implementation.
+    Selftest {
+        /// OpenCL device to test.
+        #[arg(long)]
+        device: u8,
+
+        /// OpenCL platform that the device belongs to.
+        #[arg(long)]
+        platform: Option<u8>,
+
+        /// Number of random salts to check per round.
+        #[arg(long, default_value_t = DEFAULT_SELFTEST_COUNT)]
+        count: u32,
+    },
+    /// Time dispatches of the kernel on a device against a random job, without
+    /// reporting anything, to compare kernels, work sizes and devices.
+    Bench {
+        /// Device to benchmark.
+        #[arg(long)]
+        device: u8,
+
+        /// OpenCL platform that the device belongs to.
+        #[arg(long)]
+        platform: Option<u8>,
+
+        /// Number of nonces per dispatch, in decimal or 0x-prefixed hex.
+        #[arg(long, default_value_t = DEFAULT_WORK_SIZE, value_parser = parse_work_size)]
+        work_size: u32,
+
+        /// Number of timed dispatches.
+        #[arg(long, default_value_t = 16, value_parser = clap::value_parser!(u32).range(1..))]
+        dispatches: u32,
+
+        /// Leading zeroes threshold to estimate the solution rate for.
+        #[arg(long, default_value_t = 3, value_parser = parse_leading_zeroes)]
+        leading: u8,
+
+        /// Total zeroes threshold to estimate the solution rate for.
+        #[arg(long, default_value_t = 5, value_parser = parse_total_zeroes)]
+        total: u8,
+
+        /// Nibble to score addresses by, as with the miner.
+        #[arg(long, value_parser = parse_nibble)]
+        score_nibble: Option<u8>,
+
+        /// Minimum score to estimate the solution rate for, in place of the
+        /// thresholds.
+        #[arg(long)]
+        min_score: Option<u32>,
+    },
+    /// Find the nonces at which an account would create a contract (with
+    /// CREATE) at the best addresses, to deploy at once its nonce gets there.
+    Nonces {
+        /// Address of the account that deploys the contract.
+        #[arg(long, value_parser = parse_address)]
+        deployer: [u8; 20],
+
+        /// First nonce to try, e.g. the current nonce of the deployer.
+        #[arg(long, default_value_t = 0)]
+        from: u64,
+
+        /// Number of nonces to try.
+        #[arg(long, default_value_t = 1_000_000)]
+        count: u64,
+
+        /// Number of nonces to show.
+        #[arg(short, default_value_t = 10)]
+        n: usize,
+
+        /// Nibble to score addresses by, as with the miner, in place of their
+        /// zero bytes.
+        #[arg(long, value_parser = parse_nibble)]
+        score_nibble: Option<u8>,
+
+        /// Minimum score for a nonce to be shown, scoring with the default
+        /// nibble if none is given.
+        #[arg(long)]
+        min_score: Option<u32>,
+    },
+    /// Re-derive the results in a JSON lines results file (as written with
+    /// --output-file) from their salts and score them again, e.g. after a
+    /// change to the scoring rules, dropping any that no longer hold up.
+    Rescore {
+        /// Results file to read.
+        #[arg(long)]
+        input: PathBuf,
+
+        /// File to write the rescored results to, best first (may be the
+        /// input).
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Keccak-256 hash of the init code that the results were mined for
+        /// (not needed with --mode create3).
+        #[arg(long, value_parser = parse_hash)]
+        init_code_hash: Option<[u8; 32]>,
+
+        /// How the addresses follow from the salts.
+        #[arg(long, value_enum, default_value_t = Mode::Create2)]
+        mode: Mode,
+
+        /// What to score the results by.
+        #[arg(long, value_enum)]
+        scoring: ScoringMode,
+
+        /// Nibble to score addresses by.
+        #[arg(long, value_parser = parse_nibble)]
+        score_nibble: Option<u8>,
+
+        /// Minimum score for a result to be kept.
+        #[arg(long)]
+        min_score: Option<u32>,
+
+        /// Score addresses whose first nibble after the leading zeroes is not
+        /// the target.
+        #[arg(long)]
+        score_any_first_nibble: bool,
+    },
+    /// Inspect and maintain results files.
+    Results {
+        #[command(subcommand)]
+        command: ResultsCommand,
+    },
+    /// Print the best results across runs, as kept with --leaderboard.
+    Leaderboard {
+        /// Leaderboard file to read.
+        #[arg(default_value = LEADERBOARD_FILE)]
+        path: PathBuf,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ResultsCommand {
+    /// Merge results files and SQLite databases, dropping duplicate salts and
+    /// sorting by reward, or merge databases into another database.
+    Compact {
+        /// Results files to merge.
+        #[arg(required_unless_present = "db")]
+        inputs: Vec<PathBuf>,
+
+        /// SQLite database (as written with `--db`) to merge as well; may be
+        /// given more than once.
+        #[arg(long)]
+        db: Vec<PathBuf>,
+
+        /// File to write the merged results to (may be one of the inputs).
+        #[arg(short, long, required_unless_present = "into_db")]
+        output: Option<PathBuf>,
+
+        /// Merge the databases into this SQLite database instead (created if
+        /// need be, and which may be one of them), keeping every column of
+        /// each result; results files cannot be merged into a database.
+        #[arg(
+            long,
+            requires = "db",
+            conflicts_with_all = ["inputs", "output"]
+        )]
+        into_db: Option<PathBuf>,
+    },
+    /// Print the best results across one or more results files (or JSON lines
+    /// written with --output-file), or in a database, with when each was found.
+    Top {
+        /// Results files to read.
+        #[arg(default_value = OUTPUT_FILE)]
+        inputs: Vec<PathBuf>,
+
+        /// Read the results from this SQLite database (as written with `--db`)
+        /// instead of results files.
+        #[arg(long, conflicts_with = "inputs")]
+        db: Option<PathBuf>,
+
+        /// Only show the results found for this job (as tagged with --job-id),
+        /// which plain results files do not record.
+        #[arg(long)]
+        job: Option<String>,
+
+        /// Number of results to show.
+        #[arg(short, long, visible_alias = "top", default_value_t = 100)]
+        n: usize,
+
+        /// Write the selected results to this file instead of printing them.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+/// Arguments describing a search. The main ones may be given positionally, in
+/// the order they were historically accepted, or as named flags that fall back
+/// to `CREATE2CRUNCH_*` environment variables.
+#[derive(Clone, Debug, Args)]
+pub struct MineArgs {
+    /// URL that found salts are POSTed to as JSON.
+    pub endpoint_url: Option<String>,
+
+    /// Address of the contract that will be calling CREATE2.
+    #[arg(value_parser = parse_address)]
+    pub factory_address: Option<[u8; 20]>,
+
+    /// Address of the caller of the factory, embedded in the salt to prevent
+    /// frontrunning (use the null address if not applicable).
+    #[arg(value_parser = parse_address)]
+    pub calling_address: Option<[u8; 20]>,
+
+    /// Keccak-256 hash of the initialization code of the contract to deploy.
+    /// Optional with --init-code-url or --account-owner, in which case the
+    /// derived hash must match it.
+    #[arg(value_parser = parse_hash)]
+    pub init_code_hash: Option<[u8; 32]>,
+
+    /// OpenCL device to search with (default 255, which uses the CPU).
+    pub gpu_device: Option<u8>,
+
+    /// Minimum number of leading zero bytes to report (default 3, GPU only).
+    #[arg(value_parser = parse_leading_zeroes)]
+    pub leading_zeroes_threshold: Option<u8>,
+
+    /// Minimum number of total zero bytes to report, or 255 to disable
+    /// (default 5, GPU only).
+    #[arg(value_parser = parse_total_zeroes)]
+    pub total_zeroes_threshold: Option<u8>,
+
+    /// URL that found salts are POSTed to, in place of the positional argument;
+    /// may be given more than once (or separated by commas) to post every salt
+    /// to each endpoint.
+    #[arg(long, env = "CREATE2CRUNCH_ENDPOINT", value_delimiter = ',')]
+    pub endpoint: Vec<String>,
+
+    /// WebSocket URL (ws:// or wss://) that found salts are streamed to over a
+    /// persistent connection, falling back to the HTTP endpoints while it is
+    /// disconnected.
+    #[arg(long, env = "CREATE2CRUNCH_WS_ENDPOINT")]
+    pub ws_endpoint: Option<String>,
+
+    /// Factory address, in place of the positional argument.
+    #[arg(long, env = "CREATE2CRUNCH_FACTORY", value_parser = parse_address)]
+    pub factory: Option<[u8; 20]>,
+
+    /// Calling address, in place of the positional argument.
+    #[arg(long, env = "CREATE2CRUNCH_CALLER", value_parser = parse_address)]
+    pub caller: Option<[u8; 20]>,
+
+    /// Init code hash, in place of the positional argument.
+    #[arg(long = "init-code-hash", env = "CREATE2CRUNCH_INIT_CODE_HASH", value_parser = parse_hash)]
+    pub named_init_code_hash: Option<[u8; 32]>,
+
+    /// Device, in place of the positional argument.
+    #[arg(long, env = "CREATE2CRUNCH_DEVICE")]
+    pub device: Option<u8>,
+
+    /// Leading zeroes threshold, in place of the positional argument.
+    #[arg(
+        long,
+        env = "CREATE2CRUNCH_LEADING",
+        value_parser = parse_leading_zeroes
+    )]
+    pub leading: Option<u8>,
+
+    /// Total zeroes threshold, in place of the positional argument.
+    #[arg(long, env = "CREATE2CRUNCH_TOTAL", value_parser = parse_total_zeroes)]
+    pub total: Option<u8>,
+
+    /// TOML (or, with a `.json` extension, JSON) file describing the job, using
+    /// the names of these flags; values given here or in the environment take
+    /// precedence over the file.
+    #[arg(long, env = "CREATE2CRUNCH_CONFIG")]
+    pub config: Option<PathBuf>,
+
+    /// Download the initialization code from this URL (https://, ipfs:// or
+    /// file://) and mine for its hash.
+    #[arg(long)]
+    pub init_code_url: Option<String>,
+
+    /// How the factory deploys the contract: with CREATE2, or with CREATE from
+    /// a CREATE2 proxy (CREATE3), in which case no init code hash is needed.
+    #[arg(long, value_enum, default_value_t = Mode::Create2)]
+    pub mode: Mode,
+
+    /// Whether salts start with the calling address, or are entirely random
+    /// (for factories that take any salt, in which case no caller is given).
+    #[arg(long, value_enum, default_value_t = SaltMode::Caller)]
+    pub salt_mode: SaltMode,
+
+    /// Bytes that every salt must carry after the caller (e.g. a campaign tag
+    /// required by the factory), up to 4 in GPU mode or 6 on the CPU; they
+    /// take the place of part of the random segment.
+    #[arg(long, value_parser = parse_salt_prefix)]
+    pub salt_prefix: Option<Bytes>,
+
+    /// Mine a vanity ERC-4337 account for this owner, as created by
+    /// `createAccount(owner, salt)` on a SimpleAccountFactory-style account
+    /// factory (given as the factory address); the whole salt is passed on.
+    #[arg(
+        long,
+        value_parser = parse_address,
+        requires_all = ["account_implementation", "account_proxy_code"],
+        conflicts_with = "init_code_url"
+    )]
+    pub account_owner: Option<[u8; 20]>,
+
+    /// Account implementation that the account factory's proxies point at.
+    #[arg(long, value_parser = parse_address)]
+    pub account_implementation: Option<[u8; 20]>,
+
+    /// URL of the ERC1967Proxy creation code that the account factory deploys
+    /// (https://, ipfs:// or file://).
+    #[arg(long)]
+    pub account_proxy_code: Option<String>,
+
+    /// File that results are kept in when they cannot be submitted to the
+    /// endpoint, to be retried in the background (and on later runs).
+    #[arg(long, default_value = SPOOL_FILE)]
+    pub spool: PathBuf,
+
+    /// Also append every result to this file, as JSON lines or, if the name
+    /// ends in `.csv`, comma-separated values.
+    #[arg(long)]
+    pub output_file: Option<PathBuf>,
+
+    /// Also record every result in this SQLite database, skipping salts that
+    /// are already in it, for `results top --db`.
+    #[arg(long)]
+    pub db: Option<PathBuf>,
+
+    /// Keep the best results across runs in this JSON file, best first, for
+    /// the `leaderboard` subcommand.
+    #[arg(long)]
+    pub leaderboard: Option<PathBuf>,
+
+    /// Number of results to keep on the leaderboard.
+    #[arg(
+        long,
+        requires = "leaderboard",
+        default_value_t = DEFAULT_LEADERBOARD_SIZE,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    pub top: usize,
+
+    /// Run as a worker that fetches its job (a JSON object with the keys of a
+    /// job file) from this URL, polling it for changes and switching to each
+    /// new job as it appears.
+    #[arg(long, env = "CREATE2CRUNCH_JOB_URL")]
+    pub job_url: Option<String>,
+
+    /// Seconds between polls of the job URL.
+    #[arg(long, default_value_t = 30, value_parser = clap::value_parser!(u64).range(1..))]
+    pub job_poll_interval: u64,
+
+    /// ID of the job, which submissions are tagged with (as `job_id`).
+    #[arg(long, env = "CREATE2CRUNCH_JOB_ID")]
+    pub job_id: Option<String>,
+
+    /// Index of this process in a fleet of --worker-count processes, each of
+    /// which searches a disjoint share of the random salt segments.
+    #[arg(long, env = "CREATE2CRUNCH_WORKER_ID", default_value_t = 0)]
+    pub worker_id: u32,
+
+    /// Number of processes in the fleet.
+    #[arg(
+        long,
+        env = "CREATE2CRUNCH_WORKER_COUNT",
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    pub worker_count: u32,
+
+    /// URL that a status report (worker ID, devices, hash rates and uptime)
+    /// is POSTed to periodically, so that a coordinator can spot dead workers.
+    #[arg(long, env = "CREATE2CRUNCH_HEARTBEAT_URL")]
+    pub heartbeat_url: Option<String>,
+
+    /// Seconds between status reports.
+    #[arg(long, default_value_t = 60, value_parser = clap::value_parser!(u64).range(1..))]
+    pub heartbeat_interval: u64,
+
+    /// Address (such as 0.0.0.0:8080) to serve `/healthz` and `/status` on,
+    /// for orchestrators to supervise the miner with, and `/control` to change
+    /// the thresholds, minimum score, `--only-improvements` and
+    /// `--stop-at-score` of a run without restarting it.
+    #[arg(long, env = "CREATE2CRUNCH_STATUS_ADDR")]
+    pub status_addr: Option<String>,
+
+    /// Only post results that score higher (by pattern score, or else by
+    /// reward) than the best one posted so far; the rest are still counted
+    /// and written to the local files.
+    #[arg(long)]
+    pub only_improvements: bool,
+
+    /// Score that a result must beat to be posted with --only-improvements,
+    /// e.g. the best one from an earlier run.
+    #[arg(long, requires = "only_improvements")]
+    pub current_best: Option<u128>,
+
+    /// Stop once an address scores at least this much (by pattern score, or
+    /// else by reward).
+    #[arg(long)]
+    pub stop_at_score: Option<u128>,
+
+    /// Deployment function of the factory (one of a few well-known ones), to
+    /// add a `cast send` command that deploys each result to the output file
+    /// and to what is posted. The init code is filled in if given with
+    /// --init-code-url.
+    #[arg(long, value_enum)]
+    pub factory_abi: Option<FactoryAbi>,
+
+    /// JSON-RPC URL of a node on the factory's chain, to check every result
+    /// against the factory before recording it. The run stops on the first
+    /// address that the factory does not agree with.
+    #[arg(long, env = "CREATE2CRUNCH_RPC_URL", hide_env_values = true)]
+    pub rpc_url: Option<String>,
+
+    /// View of the factory that returns the address for a salt, called with
+    /// the salt and (if it takes a second bytes32) the init code hash.
+    #[arg(long, default_value = crate::DEFAULT_RPC_VERIFY, requires = "rpc_url")]
+    pub rpc_verify: String,
+
+    /// Deploy the first address to reach --stop-at-score through the node at
+    /// --rpc-url, with the init code from --init-code-url, and wait for the
+    /// deployment to be mined.
+    #[cfg(feature = "deploy")]
+    #[arg(
+        long,
+        requires_all = ["stop_at_score", "rpc_url", "private_key", "init_code_url"]
+    )]
+    pub deploy: bool,
+
+    /// Private key of the account that sends the deployment (the caller, if
+    /// the factory checks that salts start with the sender).
+    #[cfg(feature = "deploy")]
+    #[arg(long, env = "CREATE2CRUNCH_PRIVATE_KEY", hide_env_values = true)]
+    pub private_key: Option<String>,
+
+    /// Function of the factory that deploys with a salt and init code
+    /// [default: that of --factory-abi, or safeCreate2(bytes32,bytes)].
+    #[cfg(feature = "deploy")]
+    #[arg(long, requires = "deploy")]
+    pub deploy_call: Option<String>,
+
+    /// Stop after running for this long, in seconds or with a unit of s, m, h
+    /// or d (e.g. 6h).
+    #[arg(long, value_parser = parse_duration)]
+    pub max_runtime: Option<u64>,
+
+    /// Stop after this many attempts.
+    #[arg(long)]
+    pub max_hashes: Option<u128>,
+
+    /// Post up to this many results to the endpoint in one request, as a JSON
+    /// array (by default each result is posted on its own, as an object).
+    #[arg(long, default_value_t = 1, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    pub batch_size: usize,
+
+    /// Seconds to wait for a batch to fill before posting what there is.
+    #[arg(long, default_value_t = 5)]
+    pub batch_interval: u64,
+
+    /// Token sent to the endpoint as `Authorization: Bearer <token>`.
+    #[arg(long, env = "CREATE2CRUNCH_AUTH_TOKEN", hide_env_values = true)]
+    pub auth_token: Option<String>,
+
+    /// Header sent to the endpoint with every result, as `Name: value` (may be
+    /// given more than once).
+    #[arg(
+        long,
+        env = "CREATE2CRUNCH_AUTH_HEADER",
+        hide_env_values = true,
+        value_parser = parse_header
+    )]
+    pub auth_header: Vec<(String, String)>,
+
+    /// Shape of the JSON that results are posted as: the default payload, the
+    /// salt, address and score of the original create2crunch (classic), or
+    /// the one given by --payload-template (custom, implied by a template).
+    #[arg(long, value_enum)]
+    pub payload_format: Option<PayloadFormat>,
+
+    /// JSON file that results are posted as, with `{{field}}` placeholders
+    /// for their fields (such as salt, address, salt_nonce or init_code_hash).
+    #[arg(long)]
+    pub payload_template: Option<PathBuf>,
+
+    /// OpenCL platform to take the devices from (by default, the first one
+    /// with a GPU).
+    #[arg(long, env = "CREATE2CRUNCH_PLATFORM")]
+    pub platform: Option<u8>,
+
+    /// OpenCL devices to search with concurrently, separated by commas
+    /// (overrides the device argument).
+    #[arg(long, value_delimiter = ',')]
+    pub gpu_devices: Vec<u8>,
+
+    /// Also search on every core of the CPU while the GPUs mine, taking a
+    /// share of the salts of its own.
+    #[arg(long)]
+    pub hybrid: bool,
+
+    /// File of reserved addresses (one per line) that must not be reported as
+    /// new finds.
+    #[arg(long)]
+    pub reserved: Option<PathBuf>,
+
+    /// What to do with found addresses that are in the reserved set.
+    #[arg(long, value_enum, default_value_t = ReservedAction::Skip)]
+    pub reserved_action: ReservedAction,
+
+    /// Niceness to run the host process at (0-19, higher yields more to other
+    /// processes).
+    #[arg(long, value_parser = clap::value_parser!(i32).range(0..=19))]
+    pub nice: Option<i32>,
+
+    /// In GPU mode, poll for kernel completion every this many milliseconds
+    /// instead of waiting for the driver to report it (0 disables), for
+    /// drivers whose completion callbacks are unreliable.
+    #[arg(long, default_value_t = 0)]
+    pub poll_interval_ms: u64,
+
+    /// Number of nonces each GPU dispatch searches, in decimal or 0x-prefixed
+    /// hex. Smaller sizes suit integrated GPUs; larger ones big cards.
+    #[arg(long, default_value_t = DEFAULT_WORK_SIZE, value_parser = parse_work_size)]
+    pub work_size: u32,
+
+    /// In GPU mode, show a full-screen dashboard with the hash rate, the best
+    /// address so far and the last submission, updated after each dispatch.
+    #[arg(long)]
+    pub tui: bool,
+
+    /// Benchmark work sizes around --work-size on each device at startup and
+    /// use the one with the best hash rate.
+    #[arg(long)]
+    pub auto_tune: bool,
+
+    /// Size of the GPU solutions buffer as a fraction of the work size.
+    #[arg(long, default_value_t = DEFAULT_SOLUTIONS_FRACTION, value_parser = parse_fraction)]
+    pub solutions_fraction: f64,
+
+    /// Re-derive one in this many GPU-reported addresses on the host to guard
+    /// against kernel bugs (1 checks all, 0 disables).
+    #[arg(long, default_value_t = DEFAULT_VERIFY_SAMPLE)]
+    pub verify_sample: u64,
+
+    /// Run the self-test on every OpenCL device before mining, and do not
+    /// start if the kernel derives different addresses than the host.
+    #[arg(long)]
+    pub selftest: bool,
+
+    /// First value of the nonce segment (the 6-byte CPU nonce, or the upper 4
+    /// bytes of the GPU nonce); random on the GPU if unset.
+    #[arg(long)]
+    pub nonce_start: Option<u64>,
+
+    /// Step between successive nonces, e.g. process k of N uses
+    /// `--nonce-start k --nonce-stride N`.
+    #[arg(long, default_value_t = 1)]
+    pub nonce_stride: u64,
+
+    /// In GPU mode, count candidates by leading zero nibbles on the device and
+    /// display the counts next to their expected values.
+    #[arg(long)]
+    pub histogram: bool,
+
+    /// Another factory to search salts for, e.g. the same deployer at a
+    /// different address on another chain (may be repeated). Results for each
+    /// factory are written to their own file.
+    #[arg(long = "extra-factory", value_parser = parse_address)]
+    pub extra_factories: Vec<[u8; 20]>,
+
+    /// Another caller to search salts for, taken in turn with the calling
+    /// address (may be repeated).
+    #[arg(long = "extra-caller", value_parser = parse_address)]
+    pub extra_callers: Vec<[u8; 20]>,
+
+    /// The init code hash of another contract deployed through the factory,
+    /// which every salt is also tried with (may be repeated). Results for each
+    /// hash are written to their own file.
+    #[arg(long = "extra-init-code-hash", value_parser = parse_hash)]
+    pub extra_init_code_hashes: Vec<[u8; 32]>,
+
+    /// Results file from an earlier run with the same factory and init code
+    /// hash; in GPU mode every other segment continues next to one of its best
+    /// salts.
+    #[arg(long)]
+    pub warm_start: Option<PathBuf>,
+
+    /// Walk the salt segments in order rather than at random, saving progress
+    /// to the checkpoint file so that no work is lost or repeated.
+    #[arg(long, conflicts_with_all = ["auto_tune", "warm_start"])]
+    pub deterministic: bool,
+
+    /// Segment to start a deterministic search from, e.g. to split the space
+    /// by hand.
+    #[arg(long, default_value_t = 0, requires = "deterministic")]
+    pub start_segment: u64,
+
+    /// File that the progress of a deterministic search is saved to.
+    #[arg(long, default_value = CHECKPOINT_FILE)]
+    pub checkpoint: PathBuf,
+
+    /// Seconds between saves of the checkpoint.
+    #[arg(long, default_value_t = 60, value_parser = clap::value_parser!(u64).range(1..))]
+    pub checkpoint_interval: u64,
+
+    /// Carry on from the checkpoint file (if there is one) instead of the
+    /// start segment.
+    #[arg(long, requires = "deterministic")]
+    pub resume: bool,
+
+    /// Format of the log lines written to stderr (the level is set through
+    /// RUST_LOG).
+    #[arg(long, env = "CREATE2CRUNCH_LOG_FORMAT", value_enum, default_value_t = LogFormat::Text)]
+    pub log_format: LogFormat,
+
+    /// What to do while running on battery power.
+    #[arg(long, value_enum, default_value_t = BatteryAction::Ignore)]
+    pub on_battery: BatteryAction,
+
+    /// Pause dispatches to a GPU while it is at or above this temperature (in
+    /// °C), until it has cooled down by 5°C. Read through NVML on NVIDIA
+    /// cards (with the nvml feature) and from the amdgpu driver on AMD cards.
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_temp: Option<u32>,
+
+    /// Score addresses by their nibbles (leading zeroes and runs of a target
+    /// nibble) on top of the thresholds, and report the scores; implied by any
+    /// of the --score-* options.
+    #[arg(long)]
+    pub score: bool,
+
+    /// Mine for zero bytes (by --leading and --total, valued by the reward
+    /// table) or for nibble patterns (as with --score). By default, nibble
+    /// scoring is on only if any of the scoring options are given.
+    #[arg(long, value_enum, conflicts_with = "score")]
+    pub scoring: Option<ScoringMode>,
+
+    /// Hex digit (1 to f) that the pattern bonuses look for [default: 4].
+    #[arg(long, value_parser = parse_nibble)]
+    pub score_nibble: Option<u8>,
+
+    /// Points for each leading zero nibble [default: 10].
+    #[arg(long)]
+    pub score_leading_weight: Option<u32>,
+
+    /// Points for four target nibbles straight after the leading zeroes
+    /// [default: 40].
+    #[arg(long)]
+    pub score_run_bonus: Option<u32>,
+
+    /// Further points if that run is followed by a different nibble
+    /// [default: 20].
+    #[arg(long)]
+    pub score_run_end_bonus: Option<u32>,
+
+    /// Points for four target nibbles at the end of the address [default: 20].
+    #[arg(long)]
+    pub score_tail_bonus: Option<u32>,
+
+    /// Points for each target nibble anywhere in the address [default: 1].
+    #[arg(long)]
+    pub score_nibble_weight: Option<u32>,
+
+    /// Score addresses even if the first nibble after the leading zeroes is
+    /// not the target (which the Uniswap v4 rules disqualify).
+    #[arg(long)]
+    pub score_any_first_nibble: bool,
+
+    /// Points for each uppercase letter in the checksummed address
+    /// [default: 0]. These only count towards the score on the host, as the
+    /// device filter leaves them out.
+    #[arg(long)]
+    pub score_uppercase_weight: Option<u32>,
+
+    /// Only report addresses whose EIP-55 checksummed form starts with these
+    /// hex digits, in the same case (e.g. AbCd). This is checked on the host,
+    /// after the thresholds or minimum score.
+    #[arg(long, value_parser = parse_checksum_prefix)]
+    pub checksum_prefix: Option<String>,
+
+    /// WebAssembly module (.wasm or .wat) that scores what the device reports
+    /// in place of the built-in rules. It exports its memory, address() ->
+    /// i32 (where each address is written) and score(i32) -> i32, and drops
+    /// addresses that it scores below zero.
+    #[cfg(feature = "wasm")]
+    #[arg(long)]
+    pub scorer: Option<PathBuf>,
+
+    /// Only report addresses that match this pattern of 40 hex nibbles and ?
+    /// wildcards, in which ... stands for the rest of the wildcards (e.g.
+    /// badc0de...face), in place of the zero byte thresholds or a minimum
+    /// score. The pattern is compiled into the kernel.
+    #[arg(long)]
+    pub pattern: Option<Pattern>,
+
+    /// Only report addresses with at least this score, in place of the zero
+    /// byte thresholds; the filter runs on the device, so low-scoring
+    /// addresses never reach the host.
+    #[arg(long)]
+    pub min_score: Option<u32>,
+
+    /// Upper bound in MiB on memory used by large structures such as the
+    /// reserved set, which falls back to a smaller bloom filter to fit, and
+    /// the queues of results waiting to be posted, which spill into the spool.
+    #[arg(long)]
+    pub max_memory: Option<usize>,
+}
+
+impl MineArgs {
+    /// Construct the Config struct from the parsed arguments.
+    pub fn into_config(mut self) -> Result<Config, Box<dyn Error>> {
+        // fill in anything not given on the command line from the job file
+        if let Some(path) = self.config.clone() {
+            let job = JobFile::load(&path)?;
+            self.apply_job(job)
+                .map_err(|e| format!("{}: {e}", path.display()))?;
+        }
+
+        // positional arguments take precedence over their named equivalents,
+        // which may come from the environment
+        let defaults = Config::default();
+        let headers = self.headers();
+        let endpoint_urls = match self.endpoint_url {
+            Some(url) => vec![url],
+            None => self.endpoint,
+        };
+        let factory_address = self
+            .factory_address
+            .or(self.factory)
+            .ok_or("missing factory address (give it positionally or with --factory)")?;
+        let calling_addresses = match self.salt_mode {
+            SaltMode::Caller => {
+                let calling_address = self
+                    .calling_address
+                    .or(self.caller)
+                    .ok_or("missing calling address (give it positionally or with --caller)")?;
+                [calling_address]
+                    .into_iter()
+                    .chain(self.extra_callers)
+                    .collect()
+            }
+            SaltMode::Free => {
+                if self.calling_address.or(self.caller).is_some() || !self.extra_callers.is_empty()
+                {
+                    return Err("free salts do not start with a calling address".into());
+                }
+                vec![]
+            }
+        };
+        let expected_hash = self.init_code_hash.or(self.named_init_code_hash);
+        let gpu_device = self
+            .gpu_device
+            .or(self.device)
+            .unwrap_or(defaults.gpu_device);
+
+        // the init code hash may instead be derived from downloaded code,
+        // which is kept to deploy with
+        let init_code = self
+            .init_code_url
+            .as_deref()
+            .map(fetch_init_code)
+            .transpose()?;
+        let derived = match (&init_code, self.account_owner) {
+            (Some(init_code), _) => Some((
+                format!("init code at {}", self.init_code_url.as_ref().unwrap()),
+                keccak256(init_code).0,
+            )),
+            (None, Some(owner)) => {
+                let (Some(implementation), Some(url)) =
+                    (self.account_implementation, &self.account_proxy_code)
+                else {
+                    return Err("missing account implementation or proxy code".into());
+                };
+                let proxy = fetch_init_code(url)?;
+                let init_code = simple_account_init_code(&proxy, implementation, owner);
+                Some(("account init code".to_string(), keccak256(init_code).0))
+            }
+            (None, None) => None,
+        };
+        let init_code_hash = match derived {
+            Some((source, hash)) => {
+                if expected_hash.is_some_and(|expected| expected != hash) {
+                    return Err(format!(
+                        "{source} has hash 0x{}, not the expected hash",
+                        hex::encode(hash)
+                    )
+                    .into());
+                }
+                Some(hash)
+            }
+            None => expected_hash,
+        };
+        let init_code_hash = match (init_code_hash, self.mode) {
+            (Some(hash), _) => hash,
+            (None, Mode::Create3) => [0; 32],
+            (None, Mode::Create2) => {
+                return Err("missing init code hash (give it positionally, with \
+                            --init-code-hash, --init-code-url or --account-owner)"
+                    .into())
+            }
+        };
+
+        // any scoring option turns on scoring, with defaults for the rest
+        let scoring_options = [
+            self.score_leading_weight,
+            self.score_run_bonus,
+            self.score_run_end_bonus,
+            self.score_tail_bonus,
+            self.score_nibble_weight,
+            self.score_uppercase_weight,
+            self.min_score,
+        ];
+        let nibbles = self.score
+            || self.score_any_first_nibble
+            || self.score_nibble.is_some()
+            || scoring_options.iter().any(Option::is_some);
+        if self.scoring == Some(ScoringMode::Zeros) && nibbles {
+            return Err("the scoring options only apply with --scoring nibbles".into());
+        }
+        let scoring = (nibbles || self.scoring == Some(ScoringMode::Nibbles)).then(|| {
+            let defaults = Scoring::default();
+            Scoring {
+                target_nibble: self.score_nibble.unwrap_or(defaults.target_nibble),
+                leading_weight: self.score_leading_weight.unwrap_or(defaults.leading_weight),
+                run_bonus: self.score_run_bonus.unwrap_or(defaults.run_bonus),
+                run_end_bonus: self.score_run_end_bonus.unwrap_or(defaults.run_end_bonus),
+                tail_bonus: self.score_tail_bonus.unwrap_or(defaults.tail_bonus),
+                nibble_weight: self.score_nibble_weight.unwrap_or(defaults.nibble_weight),
+                require_target_first: !self.score_any_first_nibble,
+                uppercase_weight: self
+                    .score_uppercase_weight
+                    .unwrap_or(defaults.uppercase_weight),
+                min_score: self.min_score.unwrap_or(defaults.min_score),
+            }
+        });
+
+        let max_memory = self.max_memory.map(|mib| mib.saturating_mul(1 << 20));
+        let reserved = self
+            .reserved
+            .as_deref()
+            .map(|path| Reserved::load(path, max_memory))
+            .transpose()?;
+
+        let warm_start = match &self.warm_start {
+            Some(path) => {
+                let (found, _) = results::read_all(std::slice::from_ref(path))?;
+                found
+                    .iter()
+                    .take(WARM_START_SEEDS)
+                    .map(|found| parse_hash(&found.salt))
+                    .collect::<Result<_, _>>()?
+            }
+            None => vec![],
+        };
+
+        Ok(Config {
+            factory_addresses: [factory_address]
+                .into_iter()
+                .chain(self.extra_factories)
+                .collect(),
+            calling_addresses,
+            init_code_hash,
+            init_code,
+            extra_init_code_hashes: self.extra_init_code_hashes,
+            mode: self.mode,
+            salt_mode: self.salt_mode,
+            salt_prefix: self.salt_prefix.map(Vec::from).unwrap_or_default(),
+            platform: self.platform,
+            gpu_device: self.gpu_devices.first().copied().unwrap_or(gpu_device),
+            extra_gpu_devices: self.gpu_devices.iter().skip(1).copied().collect(),
+            hybrid: self.hybrid,
+            leading_zeroes_threshold: self
+                .leading_zeroes_threshold
+                .or(self.leading)
+                .unwrap_or(defaults.leading_zeroes_threshold),
+            total_zeroes_threshold: self
+                .total_zeroes_threshold
+                .or(self.total)
+                .unwrap_or(defaults.total_zeroes_threshold),
+            endpoint_urls,
+            ws_endpoint: self.ws_endpoint,
+            spool: self.spool,
+            output_file: self.output_file,
+            db: self.db,
+            leaderboard: self.leaderboard,
+            leaderboard_size: self.top,
+            only_improvements: self.only_improvements,
+            current_best: self.current_best,
+            stop_at_score: self.stop_at_score,
+            max_runtime_secs: self.max_runtime,
+            max_hashes: self.max_hashes,
+            batch_size: self.batch_size,
+            batch_interval_secs: self.batch_interval,
+            headers,
+            payload_format: self.payload_format.unwrap_or(match self.payload_template {
+                Some(_) => PayloadFormat::Custom,
+                None => PayloadFormat::Default,
+            }),
+            payload_template: self
+                .payload_template
+                .as_deref()
+                .map(PayloadTemplate::load)
+                .transpose()?,
+            job_id: self.job_id,
+            worker_id: self.worker_id,
+            worker_count: self.worker_count,
+            heartbeat_url: self.heartbeat_url,
+            heartbeat_interval_secs: self.heartbeat_interval,
+            status_addr: self.status_addr,
+            reserved,
+            reserved_action: self.reserved_action,
+            nice: self.nice,
+            poll_interval_ms: self.poll_interval_ms,
+            work_size: self.work_size,
+            auto_tune: self.auto_tune,
+            deterministic: self.deterministic,
+            start_segment: self.start_segment,
+            checkpoint: self.checkpoint,
+            checkpoint_interval_secs: self.checkpoint_interval,
+            resume: self.resume,
+            rpc_url: self.rpc_url,
+            rpc_verify: self.rpc_verify,
+            factory_abi: self.factory_abi,
+            #[cfg(feature = "deploy")]
+            deploy: self
+                .deploy
+                .then(|| {
+                    Some(crate::Deployment {
+                        private_key: self.private_key?,
+                        call: self.deploy_call.unwrap_or_else(|| {
+                            self.factory_abi
+                                .map_or(crate::DEFAULT_DEPLOY_CALL, |abi| abi.signature())
+                                .to_string()
+                        }),
+                    })
+                })
+                .flatten(),
+            tui: self.tui,
+            solutions_fraction: self.solutions_fraction,
+            verify_sample: self.verify_sample,
+            selftest: self.selftest,
+            nonce_start: self.nonce_start,
+            nonce_stride: self.nonce_stride,
+            histogram: self.histogram,
+            max_memory,
+            warm_start,
+            on_battery: self.on_battery,
+            max_temp: self.max_temp,
+            scoring,
+            checksum_prefix: self.checksum_prefix,
+            pattern: self.pattern,
+            #[cfg(feature = "wasm")]
+            scorer: self
+                .scorer
+                .as_deref()
+                .map(crate::Scorer::load)
+                .transpose()?,
+        })
+    }
+}
+
+impl MineArgs {
+    /// The headers sent with every request to the endpoint (or job URL).
+    pub(crate) fn headers(&self) -> Vec<(String, String)> {
+        self.auth_token
+            .iter()
+            .map(|token| ("Authorization".to_string(), format!("Bearer {token}")))
+            .chain(self.auth_header.iter().cloned())
+            .collect()
+    }
+
+    /// Take values from a job file for the flags that were not given.
+    pub(crate) fn apply_job(&mut self, job: JobFile) -> Result<(), Box<dyn Error>> {
+        let addresses = |values: &[String]| {
+            values
+                .iter()
+                .map(|value| parse_address(value))
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        if self.endpoint.is_empty() {
+            self.endpoint = job.endpoint;
+        }
+        self.ws_endpoint = self.ws_endpoint.take().or(job.ws_endpoint);
+        if self.factory.is_none() {
+            self.factory = job.factory.as_deref().map(parse_address).transpose()?;
+        }
+        if self.caller.is_none() {
+            self.caller = job.caller.as_deref().map(parse_address).transpose()?;
+        }
+        if self.named_init_code_hash.is_none() {
+            self.named_init_code_hash =
+                job.init_code_hash.as_deref().map(parse_hash).transpose()?;
+        }
+        if self.account_owner.is_none() {
+            self.init_code_url = self.init_code_url.take().or(job.init_code_url);
+        }
+        if self.salt_prefix.is_none() {
+            self.salt_prefix = job
+                .salt_prefix
+                .as_deref()
+                .map(parse_salt_prefix)
+                .transpose()?;
+        }
+        self.device = self.device.or(job.device);
+        self.platform = self.platform.or(job.platform);
+        if self.gpu_devices.is_empty() {
+            self.gpu_devices = job.gpu_devices;
+        }
+        if job.leading.is_some_and(|n| n > 20) {
+            return Err("leading must be at most 20".into());
+        }
+        self.leading = self.leading.or(job.leading);
+        if job.total.is_some_and(|n| n > 20 && n != 255) {
+            return Err("total must be at most 20, or 255".into());
+        }
+        self.total = self.total.or(job.total);
+        if self.extra_factories.is_empty() {
+            self.extra_factories = addresses(&job.extra_factories)?;
+        }
+        if self.extra_callers.is_empty() {
+            self.extra_callers = addresses(&job.extra_callers)?;
+        }
+        if self.extra_init_code_hashes.is_empty() {
+            self.extra_init_code_hashes = job
+                .extra_init_code_hashes
+                .iter()
+                .map(|hash| parse_hash(hash))
+                .collect::<Result<_, _>>()?;
+        }
+        self.reserved = self.reserved.take().or(job.reserved);
+        if job.nice.is_some_and(|n| !(0..=19).contains(&n)) {
+            return Err("nice must be between 0 and 19".into());
+        }
+        self.nice = self.nice.or(job.nice);
+        self.nonce_start = self.nonce_start.or(job.nonce_start);
+        self.warm_start = self.warm_start.take().or(job.warm_start);
+        self.max_memory = self.max_memory.or(job.max_memory);
+        self.score |= job.score;
+        if self.score_nibble.is_none() {
+            self.score_nibble = job.score_nibble.as_deref().map(parse_nibble).transpose()?;
+        }
+        self.score_leading_weight = self.score_leading_weight.or(job.score_leading_weight);
+        self.score_run_bonus = self.score_run_bonus.or(job.score_run_bonus);
+        self.score_run_end_bonus = self.score_run_end_bonus.or(job.score_run_end_bonus);
+        self.score_tail_bonus = self.score_tail_bonus.or(job.score_tail_bonus);
+        self.score_nibble_weight = self.score_nibble_weight.or(job.score_nibble_weight);
+        self.min_score = self.min_score.or(job.min_score);
+        self.job_id = self.job_id.take().or(job.id);
+
+        Ok(())
+    }
+}
+
+impl Command {
+    /// Run a subcommand that does not involve searching for salts.
+    pub fn run(self) -> Result<(), Box<dyn Error>> {
+        let mut cmd = Cli::command();
+        match self {
+            Command::Completions { shell } => {
+                let name = cmd.get_name().to_string();
+                clap_complete::generate(shell, &mut cmd, name, &mut io::stdout());
+            }
+            Command::Man => clap_mangen::Man::new(cmd).render(&mut io::stdout())?,
+            Command::Replay {
+                salt,
+                segment,
+                nonce,
+                stream,
+                count,
+                mut search,
+            } => {
+                // a salt carries its caller
+                if let (Some(salt), SaltMode::Caller) = (salt, search.salt_mode) {
+                    let head: [u8; 20] = salt[..20].try_into().unwrap();
+                    search.caller = search.caller.or(Some(head));
+                }
+                let config = search.into_config()?;
+                let (first, count) = match (salt, segment, stream) {
+                    (Some(salt), ..) => (salt, count.unwrap_or(1)),
+                    (_, Some(segment), _) => (
+                        replay::segment_start(&config, &segment, nonce.unwrap_or(0))?,
+                        count.unwrap_or_else(|| replay::default_count(&config)),
+                    ),
+                    (.., Some(stream)) => (
+                        replay::stream_start(&config, &stream)?,
+                        count.unwrap_or_else(|| replay::default_count(&config)),
+                    ),
+                    _ => unreachable!("clap requires one of them"),
+                };
+                crate::replay(&config, first, count)?
+            }
+            Command::Best {
+                inputs,
+                db,
+                job,
+                status,
+                format,
+            } => results::best(&inputs, db.as_deref(), job.as_deref(), &status, format)?,
+            Command::Selftest {
+                device,
+                platform,
+                count,
+            } => crate::selftest(platform, device, count)?,
+            Command::Bench {
+                device,
+                platform,
+                work_size,
+                dispatches,
+                leading,
+                total,
+                score_nibble,
+                min_score,
+            } => {
+                if device == 255 {
+                    return Err("bench needs a GPU device, not the CPU (255)".into());
+                }
+                let scoring = (score_nibble.is_some() || min_score.is_some()).then(|| {
+                    let defaults = Scoring::default();
+                    Scoring {
+                        target_nibble: score_nibble.unwrap_or(defaults.target_nibble),
+                        min_score: min_score.unwrap_or(defaults.min_score),
+                        ..defaults
+                    }
+                });
+                let config = Config {
+                    factory_addresses: vec![FixedBytes::<20>::random().0],
+                    calling_addresses: vec![FixedBytes::<20>::random().0],
+                    init_code_hash: FixedBytes::<32>::random().0,
+                    platform,
+                    gpu_device: device,
+                    leading_zeroes_threshold: leading,
+                    total_zeroes_threshold: total,
+                    work_size,
+                    scoring,
+                    ..Default::default()
+                };
+                crate::bench(&config, dispatches)?
+            }
+            Command::Nonces {
+                deployer,
+                from,
+                count,
+                n,
+                score_nibble,
+                min_score,
+            } => {
+                let scoring = (score_nibble.is_some() || min_score.is_some()).then(|| {
+                    let defaults = Scoring::default();
+                    Scoring {
+                        target_nibble: score_nibble.unwrap_or(defaults.target_nibble),
+                        min_score: min_score.unwrap_or(defaults.min_score),
+                        ..defaults
+                    }
+                });
+                crate::nonces(deployer, from..from.saturating_add(count), scoring, n)?
+            }
+            Command::Rescore {
+                input,
+                output,
+                init_code_hash,
+                mode,
+                scoring,
+                score_nibble,
+                min_score,
+                score_any_first_nibble,
+            } => {
+                if mode == Mode::Create2 && init_code_hash.is_none() {
+                    return Err("rescoring CREATE2 results needs --init-code-hash".into());
+                }
+                let nibbles =
+                    score_nibble.is_some() || min_score.is_some() || score_any_first_nibble;
+                if scoring == ScoringMode::Zeros && nibbles {
+                    return Err("the scoring options only apply with --scoring nibbles".into());
+                }
+                let scoring = (scoring == ScoringMode::Nibbles).then(|| {
+                    let defaults = Scoring::default();
+                    Scoring {
+                        target_nibble: score_nibble.unwrap_or(defaults.target_nibble),
+                        require_target_first: !score_any_first_nibble,
+                        min_score: min_score.unwrap_or(defaults.min_score),
+                        ..defaults
+                    }
+                });
+                let config = Config {
+                    init_code_hash: init_code_hash.unwrap_or_default(),
+                    mode,
+                    scoring,
+                    ..Default::default()
+                };
+                results::rescore(&input, &output, &config)?
+            }
+            Command::Results { command } => match command {
+                ResultsCommand::Compact {
+                    inputs,
+                    db,
+                    output,
+                    into_db,
+                } => match (output, into_db) {
+                    (_, Some(into_db)) => results::merge_dbs(&db, &into_db)?,
+                    (Some(output), None) => results::compact(&inputs, &db, &output)?,
+                    (None, None) => unreachable!("clap requires an output"),
+                },
+                ResultsCommand::Top {
+                    inputs,
+                    db,
+                    job,
+                    n,
+                    output,
+                } => results::top(&inputs, db.as_deref(), job.as_deref(), n, output.as_deref())?,
+            },
+            Command::Leaderboard { path } => leaderboard::print(&path)?,
+        }
+        Ok(())
+    }
+}
+
+fn parse_address(s: &str) -> Result<[u8; 20], &'static str> {
+    let Ok(bytes) = hex::decode(s) else {
+        return Err("could not decode address argument");
+    };
+    let Ok(address) = <[u8; 20]>::try_from(bytes) else {
+        return Err("invalid length for address argument");
+    };
+
+    // mixed-case input is taken to be EIP-55 checksummed and must match
+    let digits = s.strip_prefix("0x").unwrap_or(s);
+    let mixed_case = digits.chars().any(|c| c.is_ascii_uppercase())
+        && digits.chars().any(|c| c.is_ascii_lowercase());
+    if mixed_case && Address::parse_checksummed(format!("0x{digits}"), None).is_err() {
+        return Err("address argument has an invalid EIP-55 checksum");
+    }
+
+    Ok(address)
+}
+
+fn parse_hash(s: &str) -> Result<[u8; 32], &'static str> {
+    let Ok(bytes) = hex::decode(s) else {
+        return Err("could not decode initialization code hash argument");
+    };
+    bytes
+        .try_into()
+        .map_err(|_| "invalid length for initialization code hash argument")
+}
+
+fn parse_salt_prefix(s: &str) -> Result<Bytes, &'static str> {
+    hex::decode(s)
+        .map(Bytes::from)
+        .map_err(|_| "could not decode salt prefix argument")
+}
+
+fn parse_segment(s: &str) -> Result<Bytes, &'static str> {
+    hex::decode(s)
+        .map(Bytes::from)
+        .map_err(|_| "could not decode salt segment argument")
+}
+
+fn parse_nonce(s: &str) -> Result<u64, &'static str> {
+    let nonce = match s.strip_prefix("0x") {
+        Some(digits) => u64::from_str_radix(digits, 16),
+        None => s.parse::<u64>(),
+    };
+    nonce.map_err(|_| "invalid nonce (valid: a decimal or 0x-prefixed hex u64)")
+}
+
+fn parse_checksum_prefix(s: &str) -> Result<String, &'static str> {
+    let digits = s.strip_prefix("0x").unwrap_or(s);
+    match digits.len() <= 40 && digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        true => Ok(digits.to_string()),
+        false => Err("invalid checksum prefix (valid: up to 40 hex digits)"),
+    }
+}
+
+pub(crate) fn parse_leading_zeroes(s: &str) -> Result<u8, &'static str> {
+    match s.parse::<u8>() {
+        Ok(n) if n <= 20 => Ok(n),
+        _ => Err("invalid value for leading zeroes threshold argument. (valid: 0..=20)"),
+    }
+}
+
+pub(crate) fn parse_total_zeroes(s: &str) -> Result<u8, &'static str> {
+    match s.parse::<u8>() {
+        Ok(n) if n <= 20 || n == 255 => Ok(n),
+        _ => Err("invalid value for total zeroes threshold argument. (valid: 0..=20 | 255)"),
+    }
+}
+
+fn parse_nibble(s: &str) -> Result<u8, &'static str> {
+    match u8::from_str_radix(s, 16) {
+        Ok(n) if (1..=15).contains(&n) => Ok(n),
+        _ => Err("invalid nibble (valid: a hex digit from 1 to f)"),
+    }
+}
+
+fn parse_work_size(s: &str) -> Result<u32, &'static str> {
+    let work_size = match s.strip_prefix("0x") {
+        Some(digits) => u32::from_str_radix(digits, 16),
+        None => s.parse::<u32>(),
+    };
+    work_size.map_err(|_| "invalid work size (valid: a decimal or 0x-prefixed hex u32)")
+}
+
+fn parse_header(s: &str) -> Result<(String, String), &'static str> {
+    let Some((name, value)) = s.split_once(':') else {
+        return Err("invalid header (valid: `Name: value`)");
+    };
+    let (name, value) = (name.trim(), value.trim());
+    if HeaderName::from_bytes(name.as_bytes()).is_err() {
+        return Err("invalid header name");
+    }
+    if HeaderValue::from_str(value).is_err() {
+        return Err("invalid header value");
+    }
+    Ok((name.to_string(), value.to_string()))
+}
+
+fn parse_duration(s: &str) -> Result<u64, &'static str> {
+    let (digits, unit) = match s.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => s.split_at(i),
+        None => (s, "s"),
+    };
+    let scale = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err("invalid duration unit (valid: s, m, h or d)"),
+    };
+    match digits.parse::<u64>() {
+        Ok(n) if n != 0 => n.checked_mul(scale).ok_or("duration is too long"),
+        _ => Err("invalid duration (valid: a positive number of seconds, or e.g. 30m or 6h)"),
+    }
+}
+
+fn parse_fraction(s: &str) -> Result<f64, &'static str> {
+    match s.parse::<f64>() {
+        Ok(f) if f > 0.0 && f <= 1.0 => Ok(f),
+        _ => Err("invalid fraction (valid: greater than 0, at most 1)"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // from the examples of EIP-55
+    const CHECKSUMMED: &str = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+
+    #[test]
+    fn addresses() {
+        let expected = hex!("5aaeb6053f3e94c9b9a09f33669435e7ef1beaed");
+        for (input, parsed) in [
+            (CHECKSUMMED, Ok(expected)),
+            (&CHECKSUMMED[2..], Ok(expected)),
+            ("0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed", Ok(expected)),
+            ("0x5AAEB6053F3E94C9B9A09F33669435E7EF1BEAED", Ok(expected)),
+            (
+                "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD",
+                Err("address argument has an invalid EIP-55 checksum"),
+            ),
+            (
+                "0x5aaeb6053f3e94c9b9a09f33669435e7ef1bea",
+                Err("invalid length for address argument"),
+            ),
+            (
+                "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed00",
+                Err("invalid length for address argument"),
+            ),
+            ("", Err("invalid length for address argument")),
+            (
+                "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaeg",
+                Err("could not decode address argument"),
+            ),
+            (
+                "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beae",
+                Err("could not decode address argument"),
+            ),
+        ] {
+            assert_eq!(parse_address(input), parsed, "{input}");
+        }
+    }
+
+    #[test]
+    fn hashes() {
+        let hash = [0xab; 32];
+        let digits = hex::encode(hash);
+        for (input, parsed) in [
+            (format!("0x{digits}"), Ok(hash)),
+            (digits.to_uppercase(), Ok(hash)),
+            (
+                format!("0x{}", &digits[2..]),
+                Err("invalid length for initialization code hash argument"),
+            ),
+            (
+                format!("0x{digits}00"),
+                Err("invalid length for initialization code hash argument"),
+            ),
+            (
+                format!("0x{}", &digits[1..]),
+                Err("could not decode initialization code hash argument"),
+            ),
+            (
+                "0xnot a hash".to_string(),
+                Err("could not decode initialization code hash argument"),
+            ),
+        ] {
+            assert_eq!(parse_hash(&input), parsed, "{input}");
+        }
+    }
+
+    #[test]
+    fn thresholds() {
+        for (input, leading, total) in [
+            ("0", Some(0), Some(0)),
+            ("4", Some(4), Some(4)),
+            ("20", Some(20), Some(20)),
+            ("21", None, None),
+            ("254", None, None),
+            ("255", None, Some(255)),
+            ("256", None, None),
+            ("-1", None, None),
+            ("", None, None),
+            ("0x4", None, None),
+        ] {
+            assert_eq!(parse_leading_zeroes(input).ok(), leading, "{input:?}");
+            assert_eq!(parse_total_zeroes(input).ok(), total, "{input:?}");
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use serde::Deserialize;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+/// A mining job read from a file, using the names of the equivalent command
+/// line flags. Every value is optional, and values given on the command line
+/// (or through the environment) take precedence.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct JobFile {
+    pub id: Option<String>,
+    #[serde(deserialize_with = "one_or_many")]
+    pub endpoint: Vec<String>,
+    pub ws_endpoint: Option<String>,
+    pub factory: Option<String>,
+    pub caller: Option<String>,
+    pub init_code_hash: Option<String>,
+    pub init_code_url: Option<String>,
+    pub salt_prefix: Option<String>,
+    pub platform: Option<u8>,
+    pub device: Option<u8>,
+    pub gpu_devices: Vec<u8>,
+    pub leading: Option<u8>,
+    pub total: Option<u8>,
+    pub extra_factories: Vec<String>,
+    pub extra_callers: Vec<String>,
+    pub extra_init_code_hashes: Vec<String>,
+    pub reserved: Option<PathBuf>,
+    pub nice: Option<i32>,
+    pub nonce_start: Option<u64>,
+    pub warm_start: Option<PathBuf>,
+    pub max_memory: Option<usize>,
+    pub score: bool,
+    pub score_nibble: Option<String>,
+    pub score_leading_weight: Option<u32>,
+    pub score_run_bonus: Option<u32>,
+    pub score_run_end_bonus: Option<u32>,
+    pub score_tail_bonus: Option<u32>,
+    pub score_nibble_weight: Option<u32>,
+    pub min_score: Option<u32>,
+}
+
+impl JobFile {
+    /// Read a job from a TOML file, or a JSON file if the name ends in
+    /// `.json`. Relative paths in the job are taken relative to the file.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        let mut job: Self = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::from_str(&text).map_err(|e| format!("{}: {e}", path.display()))?
+        } else {
+            toml::from_str(&text).map_err(|e| format!("{}: {e}", path.display()))?
+        };
+
+        let dir = path.parent().unwrap_or(Path::new(""));
+        for file in [&mut job.reserved, &mut job.warm_start]
+            .into_iter()
+            .flatten()
+        {
+            *file = dir.join(&*file);
+        }
+
+        Ok(job)
+    }
+}
+
+/// Accept either a single string or a list of them.
+fn one_or_many<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(value) => vec![value],
+        OneOrMany::Many(values) => values,
+    })
+}
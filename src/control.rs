@@ -0,0 +1,221 @@
+use crate::{cli, Config};
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+use tracing::info;
+
+/// The parameters of a run that can be changed while it runs, through
+/// `POST /control` on the status server: the zero byte thresholds, the
+/// minimum score (when scoring), `only_improvements` and `stop_at_score`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Tuning {
+    pub(crate) leading_zeroes_threshold: u8,
+    pub(crate) total_zeroes_threshold: u8,
+    pub(crate) min_score: u32,
+    pub(crate) only_improvements: bool,
+    pub(crate) stop_at_score: Option<u128>,
+}
+
+impl Tuning {
+    /// The parameters that a run starts with.
+    pub(crate) fn new(config: &Config) -> Self {
+        Self {
+            leading_zeroes_threshold: config.leading_zeroes_threshold,
+            total_zeroes_threshold: config.total_zeroes_threshold,
+            min_score: config.scoring.map_or(0, |s| s.min_score),
+            only_improvements: config.only_improvements,
+            stop_at_score: config.stop_at_score,
+        }
+    }
+
+    /// Whether a device has to be asked for something else to search for
+    /// `other` rather than these parameters (the rest only apply on the host).
+    pub(crate) fn changes_kernel(&self, other: &Self) -> bool {
+        self.leading_zeroes_threshold != other.leading_zeroes_threshold
+            || self.total_zeroes_threshold != other.total_zeroes_threshold
+            || self.min_score != other.min_score
+    }
+
+    pub(crate) fn report(&self) -> Value {
+        json!({
+            "leading_zeroes_threshold": self.leading_zeroes_threshold,
+            "total_zeroes_threshold": self.total_zeroes_threshold,
+            "min_score": self.min_score,
+            "only_improvements": self.only_improvements,
+            "stop_at_score": self.stop_at_score.map(|score| score.to_string()),
+        })
+    }
+}
+
+/// The current parameters of a run, shared between the status server that
+/// changes them and the workers, which pick up a change at their next
+/// dispatch and rebuild their kernels only if it changes what the device
+/// reports.
+pub(crate) struct Control {
+    tuning: RwLock<Tuning>,
+    // bumped on every change, so that a worker can tell cheaply that there is one
+    generation: AtomicU64,
+    scoring: bool,
+}
+
+impl Control {
+    pub(crate) fn new(config: &Config) -> Self {
+        Self {
+            tuning: RwLock::new(Tuning::new(config)),
+            generation: AtomicU64::new(0),
+            scoring: config.scoring.is_some(),
+        }
+    }
+
+    pub(crate) fn tuning(&self) -> Tuning {
+        *self.tuning.read().unwrap()
+    }
+
+    /// How many times the parameters have been changed.
+    pub(crate) fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Relaxed)
+    }
+
+    /// Apply the changes in a JSON object such as `{"min_score": 60,
+    /// "only_improvements": true}`, returning the new parameters, or why they
+    /// cannot be applied (in which case none of them are).
+    pub(crate) fn apply(&self, changes: &Value) -> Result<Tuning, String> {
+        let changes = changes
+            .as_object()
+            .ok_or("expected a JSON object of the parameters to change")?;
+        let mut tuning = self.tuning.write().unwrap();
+        let mut next = *tuning;
+        for (key, value) in changes {
+            let invalid = || format!("invalid {key}: {value}");
+            match key.as_str() {
+                // checked as on the command line, where 255 disables the
+                // total zeroes threshold
+                "leading_zeroes_threshold" => {
+                    next.leading_zeroes_threshold =
+                        threshold(value, cli::parse_leading_zeroes).ok_or_else(invalid)?;
+                }
+                "total_zeroes_threshold" => {
+                    next.total_zeroes_threshold =
+                        threshold(value, cli::parse_total_zeroes).ok_or_else(invalid)?;
+                }
+                "min_score" if !self.scoring => {
+                    return Err("the minimum score only applies with scoring rules".to_string())
+                }
+                "min_score" => {
+                    next.min_score = value
+                        .as_u64()
+                        .and_then(|score| score.try_into().ok())
+                        .ok_or_else(invalid)?;
+                }
+                "only_improvements" => {
+                    next.only_improvements = value.as_bool().ok_or_else(invalid)?;
+                }
+                // scores can be too large for a JSON number, so they can also
+                // be given as strings
+                "stop_at_score" => {
+                    next.stop_at_score = match value {
+                        Value::Null => None,
+                        Value::String(score) => Some(score.parse().map_err(|_| invalid())?),
+                        _ => Some(value.as_u64().ok_or_else(invalid)?.into()),
+                    };
+                }
+                _ => return Err(format!("{key} cannot be changed while running")),
+            }
+        }
+
+        if next != *tuning {
+            info!("parameters changed to {}", next.report());
+            *tuning = next;
+            self.generation.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(next)
+    }
+}
+
+// a threshold given as a JSON number, if the command line would take it
+fn threshold(value: &Value, parse: fn(&str) -> Result<u8, &'static str>) -> Option<u8> {
+    value.as_u64().and_then(|n| parse(&n.to_string()).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Scoring;
+
+    fn control(scoring: Option<Scoring>) -> Control {
+        Control::new(&Config {
+            scoring,
+            ..Config::default()
+        })
+    }
+
+    #[test]
+    fn applies_changes() {
+        let control = control(Some(Scoring::default()));
+        let tuning = control
+            .apply(&json!({
+                "leading_zeroes_threshold": 4,
+                "total_zeroes_threshold": 255,
+                "min_score": 60,
+                "only_improvements": true,
+            }))
+            .unwrap();
+        assert_eq!(
+            tuning,
+            Tuning {
+                leading_zeroes_threshold: 4,
+                total_zeroes_threshold: 255,
+                min_score: 60,
+                only_improvements: true,
+                stop_at_score: None,
+            }
+        );
+        assert_eq!(control.tuning(), tuning);
+        assert_eq!(control.generation(), 1);
+
+        // applying the same parameters again is no change
+        control.apply(&json!({"min_score": 60})).unwrap();
+        assert_eq!(control.generation(), 1);
+    }
+
+    #[test]
+    fn rejects_changes_atomically() {
+        let control = control(None);
+        let before = control.tuning();
+        for changes in [
+            json!({"leading_zeroes_threshold": 4, "total_zeroes_threshold": 21}),
+            json!({"only_improvements": true, "leading_zeroes_threshold": 255}),
+            json!({"leading_zeroes_threshold": -1}),
+            json!({"only_improvements": "yes"}),
+            json!({"only_improvements": true, "work_size": 1024}),
+            json!([1, 2]),
+        ] {
+            assert!(control.apply(&changes).is_err(), "{changes}");
+        }
+        assert_eq!(control.tuning(), before);
+        assert_eq!(control.generation(), 0);
+    }
+
+    #[test]
+    fn min_score_needs_scoring() {
+        let error = control(None).apply(&json!({"min_score": 60})).unwrap_err();
+        assert_eq!(error, "the minimum score only applies with scoring rules");
+    }
+
+    #[test]
+    fn stop_at_score() {
+        let control = control(None);
+        let score = u128::MAX / 3;
+        let tuning = control
+            .apply(&json!({"stop_at_score": score.to_string()}))
+            .unwrap();
+        assert_eq!(tuning.stop_at_score, Some(score));
+        assert_eq!(tuning.report()["stop_at_score"], json!(score.to_string()));
+
+        let tuning = control.apply(&json!({"stop_at_score": 100})).unwrap();
+        assert_eq!(tuning.stop_at_score, Some(100));
+        let tuning = control.apply(&json!({"stop_at_score": null})).unwrap();
+        assert_eq!(tuning.stop_at_score, None);
+        assert!(control.apply(&json!({"stop_at_score": "lots"})).is_err());
+    }
+}
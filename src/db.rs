@@ -0,0 +1,242 @@
+use crate::results::{Found, Listed};
+use alloy_primitives::{Address, U256};
+use rusqlite::{params, Connection};
+use serde_json::Value;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// rewards can exceed an SQLite integer, so they are kept as decimal text and
+// ranked on the host
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS results (
+        salt TEXT PRIMARY KEY,
+        address TEXT NOT NULL,
+        factory TEXT NOT NULL,
+        caller TEXT NOT NULL,
+        reward TEXT NOT NULL,
+        pattern_score INTEGER,
+        reserved INTEGER NOT NULL,
+        device INTEGER,
+        found_at INTEGER NOT NULL,
+        job_id TEXT
+    );
+    CREATE INDEX IF NOT EXISTS results_pattern_score ON results (pattern_score);
+";
+
+// the columns of the results table, in order
+const COLUMNS: &[&str] = &[
+    "salt",
+    "address",
+    "factory",
+    "caller",
+    "reward",
+    "pattern_score",
+    "reserved",
+    "device",
+    "found_at",
+    "job_id",
+];
+
+/// An SQLite store of every result of a campaign (`--db`), kept across runs.
+/// Each solution is recorded with the device that found it (none for the
+/// CPU), the time it was found and the job it was found for; a salt that is
+/// found again is ignored.
+pub(crate) struct ResultDb {
+    connection: Mutex<Connection>,
+}
+
+impl ResultDb {
+    pub(crate) fn open(path: &Path) -> rusqlite::Result<Self> {
+        let connection = Connection::open(path)?;
+        // wait for other processes that write to the same store
+        connection.busy_timeout(std::time::Duration::from_secs(5))?;
+        connection.execute_batch(&format!("PRAGMA journal_mode = WAL; {SCHEMA}"))?;
+        // stores created before results were tagged with their job lack it
+        if !columns(&connection, "main")?.iter().any(|c| c == "job_id") {
+            connection.execute("ALTER TABLE results ADD COLUMN job_id TEXT", [])?;
+        }
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    /// Record a result, given as the payload that is posted to the endpoint.
+    /// A free salt has no caller, so its first 20 bytes are recorded in its
+    /// place. Returns whether the salt was new.
+    pub(crate) fn record(&self, payload: &Value, device: Option<u8>) -> rusqlite::Result<bool> {
+        let salt = payload["salt"].as_str();
+        let caller = match payload["caller"].as_str() {
+            Some(caller) => Some(caller.to_string()),
+            None => salt
+                .and_then(|salt| salt.get(..42)?.parse::<Address>().ok())
+                .map(|head| head.to_checksum(None)),
+        };
+        let found_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        let inserted = self.connection.lock().unwrap().execute(
+            &format!(
+                "INSERT OR IGNORE INTO results ({})
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                COLUMNS.join(", ")
+            ),
+            params![
+                salt,
+                payload["address"].as_str(),
+                payload["factory"].as_str(),
+                caller,
+                payload["score"].as_str(),
+                payload["pattern_score"].as_u64(),
+                payload["reserved"].as_bool().unwrap_or(false),
+                device,
+                found_at,
+                payload["job_id"].as_str(),
+            ],
+        )?;
+        Ok(inserted != 0)
+    }
+}
+
+/// Merge every result of the stores at `inputs` into the store at `output`,
+/// which is created if need be. As when recording, a salt that is already in
+/// the store keeps its row, and columns that an older store lacks are left
+/// empty. Returns how many results were read and how many of them were new.
+pub(crate) fn merge(inputs: &[PathBuf], output: &Path) -> Result<(usize, usize), Box<dyn Error>> {
+    let db = ResultDb::open(output).map_err(|e| format!("{}: {e}", output.display()))?;
+    let connection = db.connection.lock().unwrap();
+    let (mut read, mut added) = (0, 0);
+    for input in inputs {
+        let path = existing(input)?
+            .to_str()
+            .ok_or_else(|| format!("{}: not a UTF-8 path", input.display()))?;
+        connection.execute("ATTACH DATABASE ?1 AS input", [path])?;
+        let merged = (|| -> rusqlite::Result<()> {
+            let present = columns(&connection, "input")?;
+            let shared = COLUMNS
+                .iter()
+                .filter(|column| present.iter().any(|c| c == *column))
+                .copied()
+                .collect::<Vec<_>>()
+                .join(", ");
+            read += connection.query_row("SELECT COUNT(*) FROM input.results", [], |row| {
+                row.get::<_, usize>(0)
+            })?;
+            added += connection.execute(
+                &format!(
+                    "INSERT OR IGNORE INTO results ({shared}) SELECT {shared} FROM input.results"
+                ),
+                [],
+            )?;
+            Ok(())
+        })();
+        connection.execute("DETACH DATABASE input", [])?;
+        merged.map_err(|e| format!("{}: {e}", input.display()))?;
+    }
+    Ok((read, added))
+}
+
+/// Every result in the store at `path`, as a line of a results file.
+pub(crate) fn read(path: &Path) -> Result<Vec<Found>, Box<dyn Error>> {
+    let connection = Connection::open(existing(path)?)?;
+    let mut statement = connection.prepare("SELECT salt, address, reward FROM results")?;
+    let results = statement
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?
+        .map(|row| {
+            let (salt, address, reward) = row?;
+            Found::parse(&format!("{salt} => {address} => {reward}"))
+                .ok_or_else(|| format!("{}: malformed result {salt}", path.display()).into())
+        })
+        .collect();
+    results
+}
+
+// the columns of the results table in the given schema of a connection
+fn columns(connection: &Connection, schema: &str) -> rusqlite::Result<Vec<String>> {
+    connection
+        .prepare(&format!("PRAGMA {schema}.table_info(results)"))?
+        .query_map([], |row| row.get(1))?
+        .collect()
+}
+
+// a store that exists, so that reading a mistyped path does not create one
+fn existing(path: &Path) -> Result<&Path, Box<dyn Error>> {
+    match path.exists() {
+        true => Ok(path),
+        false => Err(format!("{}: no such database", path.display()).into()),
+    }
+}
+
+/// The results in the store at `path` that are not reserved, with their
+/// pattern score, when they were found and their job, only taking those of
+/// `job` if given.
+pub(crate) fn list(path: &Path, job: Option<&str>) -> Result<Vec<Listed>, Box<dyn Error>> {
+    let connection = Connection::open(existing(path)?)?;
+    // stores created before results were tagged with their job have none
+    let job_id = match columns(&connection, "main")?.iter().any(|c| c == "job_id") {
+        true => "job_id",
+        false => "NULL",
+    };
+    let mut statement = connection.prepare(&format!(
+        "SELECT salt, address, reward, pattern_score, found_at, {job_id} FROM results
+            WHERE reserved = 0 AND (?1 IS NULL OR {job_id} = ?1)"
+    ))?;
+    let listed = statement
+        .query_map([job], |row| {
+            Ok(Listed {
+                found: Found {
+                    salt: row.get(0)?,
+                    address: row.get(1)?,
+                    reward: row.get::<_, String>(2)?.parse().unwrap_or(U256::ZERO),
+                },
+                pattern_score: row.get(3)?,
+                found_at: row.get(4)?,
+                job_id: row.get(5)?,
+            })
+        })?
+        .collect::<Result<_, _>>()?;
+    Ok(listed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::miner::Solution;
+    use alloy_primitives::B256;
+
+    #[test]
+    fn records_free_salts() {
+        let db = ResultDb::open(Path::new(":memory:")).unwrap();
+        let solution = Solution {
+            salt: B256::repeat_byte(0xab),
+            address: Address::repeat_byte(0x01),
+            factory: Address::repeat_byte(0x02),
+            caller: None,
+            init_code_hash: None,
+            reward: U256::from(7),
+            score: None,
+            reserved: false,
+            device: None,
+        };
+
+        let payload = solution.payload(None);
+        assert!(db.record(&payload, None).unwrap());
+        assert!(!db.record(&payload, None).unwrap());
+
+        let caller: String = db
+            .connection
+            .lock()
+            .unwrap()
+            .query_row("SELECT caller FROM results", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(caller, Address::repeat_byte(0xab).to_checksum(None));
+    }
+}
@@ -0,0 +1,119 @@
+use crate::miner::Solution;
+use alloy::network::TransactionBuilder;
+use alloy::primitives::{Address, Bytes, U256};
+use alloy::providers::{DynProvider, Provider, ProviderBuilder};
+use alloy::rpc::types::TransactionRequest;
+use alloy::signers::local::PrivateKeySigner;
+use alloy_primitives::keccak256;
+use std::error::Error;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::runtime::Runtime;
+use tracing::info;
+
+/// The function of the factory that deploys a contract by default, as on the
+/// ImmutableCreate2Factory.
+pub const DEFAULT_DEPLOY_CALL: &str = "safeCreate2(bytes32,bytes)";
+
+/// What it takes to deploy a contract once an address reaches the target
+/// score (`--deploy`), besides its init code: the key of the account that
+/// sends the deployment (which has to be the caller that salts start with, if
+/// the factory checks it), and the function of the factory to call with the
+/// salt and the init code.
+pub struct Deployment {
+    pub private_key: String,
+    pub call: String,
+}
+
+/// Sends the deployment of a found salt to the factory and waits for it to be
+/// mined, over JSON-RPC at the same node that results are checked with.
+pub(crate) struct Deployer {
+    runtime: Runtime,
+    provider: DynProvider,
+    from: Address,
+    selector: [u8; 4],
+    init_code: Vec<u8>,
+    sent: AtomicBool,
+}
+
+impl Deployer {
+    pub(crate) fn new(
+        url: &str,
+        deployment: &Deployment,
+        init_code: &[u8],
+    ) -> Result<Self, Box<dyn Error>> {
+        let signer: PrivateKeySigner = deployment
+            .private_key
+            .parse()
+            .map_err(|_| "the deployment key is not a private key")?;
+        let (name, arguments) = deployment
+            .call
+            .split_once('(')
+            .ok_or("the deployment call is not a function signature")?;
+        if name.is_empty() || arguments != "bytes32,bytes)" {
+            return Err(format!(
+                "{} does not take a salt and init code as (bytes32,bytes)",
+                deployment.call
+            )
+            .into());
+        }
+
+        let from = signer.address();
+        let provider = ProviderBuilder::new()
+            .wallet(signer)
+            .connect_http(url.parse()?)
+            .erased();
+        Ok(Self {
+            runtime: tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()?,
+            provider,
+            from,
+            selector: keccak256(&deployment.call)[..4].try_into().unwrap(),
+            init_code: init_code.to_vec(),
+            sent: AtomicBool::new(false),
+        })
+    }
+
+    /// Deploy the contract with the salt of a solution, and make sure that it
+    /// ended up at the address of the solution. Only the first solution is
+    /// deployed, as the contract can only be deployed once.
+    pub(crate) fn deploy(&self, solution: &Solution) -> Result<(), Box<dyn Error>> {
+        if self.sent.swap(true, Ordering::Relaxed) {
+            return Ok(());
+        }
+        let factory = Address::from(solution.factory.0 .0);
+        let address = Address::from(solution.address.0 .0);
+        info!("deploying {address} from {} through {factory}", self.from);
+
+        // (bytes32 salt, bytes initCode): the salt, the offset of the code,
+        // then its length and the code itself padded to a whole word
+        let mut data = self.selector.to_vec();
+        data.extend_from_slice(solution.salt.as_slice());
+        data.extend_from_slice(&U256::from(64).to_be_bytes::<32>());
+        data.extend_from_slice(&U256::from(self.init_code.len()).to_be_bytes::<32>());
+        data.extend_from_slice(&self.init_code);
+        data.resize(4 + 96 + self.init_code.len().next_multiple_of(32), 0);
+
+        self.runtime.block_on(async {
+            let transaction = TransactionRequest::default()
+                .with_from(self.from)
+                .with_to(factory)
+                .with_input(Bytes::from(data));
+            let receipt = self
+                .provider
+                .send_transaction(transaction)
+                .await?
+                .get_receipt()
+                .await?;
+            let hash = receipt.transaction_hash;
+            if !receipt.status() {
+                return Err(format!("the deployment {hash} reverted").into());
+            }
+            if self.provider.get_code_at(address).await?.is_empty() {
+                return Err(format!("the deployment {hash} left no code at {address}").into());
+            }
+            info!("deployed {address} in {hash}");
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,40 @@
+use alloy_primitives::keccak256;
+
+/// Build the init code that a SimpleAccountFactory-style ERC-4337 account
+/// factory deploys for `createAccount(owner, salt)`: an ERC1967 proxy created
+/// with `new ERC1967Proxy{salt: bytes32(salt)}(implementation,
+/// abi.encodeCall(SimpleAccount.initialize, (owner)))`.
+///
+/// The owner is part of the init code while the salt is passed to CREATE2 as
+/// is, so mining over the salt with the account factory as the deployer yields
+/// the counterfactual address of the account.
+pub fn simple_account_init_code(
+    proxy_creation_code: &[u8],
+    implementation: [u8; 20],
+    owner: [u8; 20],
+) -> Vec<u8> {
+    // initialize(address owner)
+    let selector = &keccak256("initialize(address)")[..4];
+    let mut initialize = selector.to_vec();
+    initialize.extend_from_slice(&word(&owner));
+
+    // abi.encode(address implementation, bytes data)
+    let mut init_code = proxy_creation_code.to_vec();
+    init_code.extend_from_slice(&word(&implementation));
+    init_code.extend_from_slice(&word(&[0x40]));
+    init_code.extend_from_slice(&word(&(initialize.len() as u32).to_be_bytes()));
+    init_code.extend_from_slice(&initialize);
+    init_code.resize(
+        init_code.len() + initialize.len().next_multiple_of(32) - initialize.len(),
+        0,
+    );
+
+    init_code
+}
+
+/// Left-pad a value to a 32-byte ABI word.
+fn word(value: &[u8]) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[32 - value.len()..].copy_from_slice(value);
+    word
+}
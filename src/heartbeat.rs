@@ -0,0 +1,126 @@
+use crate::{submit, Config};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+// reports waiting to be sent, beyond which newer ones are dropped (as the
+// next report supersedes them anyway)
+const QUEUE_LEN: usize = 4;
+
+/// Periodic status reports (`--heartbeat-url`), so that a coordinator can see
+/// what each worker of a fleet is doing and notice when one has died. Reports
+/// are posted from a thread of their own and are not retried.
+pub(crate) struct Heartbeat {
+    interval: Duration,
+    last: Mutex<Option<Instant>>,
+    // the attempts made on each device as of the previous report
+    reported: Mutex<BTreeMap<u8, u128>>,
+    reports: mpsc::SyncSender<Value>,
+}
+
+impl Heartbeat {
+    pub(crate) fn new(
+        url: String,
+        interval: Duration,
+        headers: &[(String, String)],
+    ) -> Result<Self, Box<dyn Error>> {
+        let client = submit::client(headers)?;
+        let (reports, received) = mpsc::sync_channel::<Value>(QUEUE_LEN);
+        thread::spawn(move || {
+            for report in received {
+                let sent = client
+                    .post(&url)
+                    .json(&report)
+                    .send()
+                    .and_then(|response| response.error_for_status());
+                if let Err(error) = sent {
+                    debug!("could not send heartbeat: {error}");
+                }
+            }
+        });
+
+        Ok(Self {
+            interval,
+            last: Mutex::new(None),
+            reported: Mutex::new(BTreeMap::new()),
+            reports,
+        })
+    }
+
+    /// Whether the next report is due, in which case it is taken to be sent.
+    pub(crate) fn due(&self) -> bool {
+        let mut last = self.last.lock().unwrap();
+        if last.is_some_and(|last| last.elapsed() < self.interval) {
+            return false;
+        }
+        *last = Some(Instant::now());
+        true
+    }
+
+    /// Send a report on the given devices, adding the attempts made on each
+    /// (and in total) since the previous report.
+    pub(crate) fn send(&self, mut report: Value, devices: &[(u8, u128)]) {
+        let mut reported = self.reported.lock().unwrap();
+        let mut since_last = 0;
+        for (entry, &(device, attempts)) in report["devices"]
+            .as_array_mut()
+            .unwrap()
+            .iter_mut()
+            .zip(devices)
+        {
+            let previous = reported.insert(device, attempts).unwrap_or(0);
+            let attempts = attempts.saturating_sub(previous);
+            entry["attempts_since_last"] = json!(attempts.to_string());
+            since_last += attempts;
+        }
+        report["attempts_since_last"] = json!(since_last.to_string());
+
+        match self.reports.try_send(report) {
+            Ok(()) => {}
+            Err(mpsc::TrySendError::Full(_)) => {
+                debug!("heartbeat endpoint is behind, dropping a report")
+            }
+            // the thread only stops once the sender is dropped
+            Err(mpsc::TrySendError::Disconnected(_)) => unreachable!(),
+        }
+    }
+}
+
+/// A status report after `uptime` seconds, with the attempts made so far on
+/// each device (255 for the CPU), the number of results found and the score
+/// of the best one (its pattern score if scoring, or else its reward).
+pub(crate) fn report(
+    config: &Config,
+    uptime: f64,
+    devices: &[(u8, u128)],
+    found: u64,
+    best: Option<u128>,
+) -> Value {
+    let rate = |attempts: u128| attempts as f64 / uptime.max(1.0);
+    let attempts: u128 = devices.iter().map(|(_, attempts)| attempts).sum();
+    let mut report = json!({
+        "worker_id": config.worker_id,
+        "worker_count": config.worker_count,
+        "uptime": uptime as u64,
+        "attempts": attempts.to_string(),
+        "hashrate": rate(attempts),
+        "found": found,
+        "best_score": best.map(|rank| rank.to_string()),
+        "devices": devices
+            .iter()
+            .map(|&(device, attempts)| json!({
+                "device": device,
+                "attempts": attempts.to_string(),
+                "hashrate": rate(attempts),
+            }))
+            .collect::<Vec<_>>(),
+    });
+    if let Some(id) = &config.job_id {
+        report["job_id"] = json!(id);
+    }
+    report
+}
@@ -0,0 +1,43 @@
+use alloy_primitives::{hex, keccak256};
+use std::error::Error;
+
+// gateway used to resolve ipfs:// links
+const IPFS_GATEWAY: &str = "https://ipfs.io/ipfs/";
+
+/// Download the creation bytecode of a contract and return its keccak-256
+/// hash (see [`fetch_init_code`]).
+pub fn fetch_init_code_hash(url: &str) -> Result<[u8; 32], Box<dyn Error>> {
+    Ok(keccak256(fetch_init_code(url)?).0)
+}
+
+/// Download the creation bytecode of a contract. `https://` and `http://`
+/// URLs are fetched directly, `ipfs://` links through a public gateway, and
+/// `file://` URLs are read from disk. The body may be raw bytecode or hex text
+/// (with or without a `0x` prefix).
+pub fn fetch_init_code(url: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    let body = if let Some(path) = url.strip_prefix("file://") {
+        std::fs::read(path).map_err(|e| format!("{path}: {e}"))?
+    } else {
+        let url = match url.strip_prefix("ipfs://") {
+            Some(cid) => format!("{IPFS_GATEWAY}{cid}"),
+            None => url.to_string(),
+        };
+        let response = reqwest::blocking::get(&url)?.error_for_status()?;
+        response.bytes()?.to_vec()
+    };
+
+    let init_code = decode(&body);
+    if init_code.is_empty() {
+        return Err(format!("no init code found at {url}").into());
+    }
+
+    Ok(init_code)
+}
+
+/// Decode a hex-encoded body, or take it as raw bytecode if it is not hex.
+fn decode(body: &[u8]) -> Vec<u8> {
+    std::str::from_utf8(body)
+        .ok()
+        .and_then(|text| hex::decode(text.trim()).ok())
+        .unwrap_or_else(|| body.to_vec())
+}
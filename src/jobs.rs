@@ -0,0 +1,119 @@
+use crate::cli::MineArgs;
+use crate::shutdown::Shutdown;
+use crate::{cpu_until, gpu_until, submit, JobFile};
+use reqwest::blocking::Client;
+use std::error::Error;
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+/// Run as a long-lived worker (`--job-url`): fetch a job from the URL, mine it
+/// until the URL serves a different one, then switch to that, until stopped by
+/// SIGINT or SIGTERM. A job is a JSON object with the keys of a job file (see
+/// [`JobFile`]), such as `factory`, `caller`, `init-code-hash` and
+/// `min-score`, along with an `id` that each submission is tagged with. Flags
+/// given on the command line take precedence over the job.
+///
+/// A job that stops by itself (e.g. on reaching `--stop-at-score`) or fails is
+/// not restarted; the worker waits for the next one instead.
+pub fn work_jobs(args: MineArgs) -> Result<(), Box<dyn Error>> {
+    let url = args.job_url.clone().ok_or("missing job URL")?;
+    let poll = Duration::from_secs(args.job_poll_interval);
+    let client = submit::client(&args.headers())?;
+
+    let Some(mut job) = next_job(&client, &url, None, poll, &Shutdown::install()?) else {
+        return Ok(());
+    };
+    loop {
+        // watch for a new job while this one is mined, stopping it if there is
+        let run = Shutdown::install()?;
+        let watcher = {
+            let (client, url, current, run) =
+                (client.clone(), url.clone(), job.clone(), run.clone());
+            thread::spawn(move || {
+                let next = next_job(&client, &url, Some(&current), poll, &run);
+                if next.is_some() {
+                    run.request_because("the job has changed");
+                }
+                next
+            })
+        };
+
+        if let Err(e) = mine(&args, &job, run.clone()) {
+            error!("job failed: {e}");
+        }
+
+        // stop the watcher if the job stopped by itself
+        run.request();
+        let next = watcher.join().map_err(|_| "job watcher panicked")?;
+        if Shutdown::signalled() {
+            return Ok(());
+        }
+
+        job = match next {
+            Some(next) => next,
+            None => {
+                info!("waiting for a new job");
+                match next_job(&client, &url, Some(&job), poll, &Shutdown::install()?) {
+                    Some(next) => next,
+                    None => return Ok(()),
+                }
+            }
+        };
+    }
+}
+
+/// Mine a job until it is done or `shutdown` is requested.
+fn mine(args: &MineArgs, job: &str, shutdown: Shutdown) -> Result<(), Box<dyn Error>> {
+    let job: JobFile = serde_json::from_str(job).map_err(|e| format!("invalid job: {e}"))?;
+    info!(id = job.id, "starting job");
+
+    let mut args = args.clone();
+    args.apply_job(job)?;
+    let config = args.into_config()?;
+    for warning in config.validate()? {
+        warn!("{warning}");
+    }
+
+    if config.gpu_device == 255 {
+        cpu_until(config, shutdown)
+    } else {
+        Ok(gpu_until(config, shutdown).map_err(|e| e.to_string())?)
+    }
+}
+
+/// Poll the job URL until it serves a job other than `current`, returning
+/// `None` if `shutdown` is requested first. The first poll is immediate if
+/// there is no current job, and otherwise waits for the poll interval.
+fn next_job(
+    client: &Client,
+    url: &str,
+    current: Option<&str>,
+    poll: Duration,
+    shutdown: &Shutdown,
+) -> Option<String> {
+    let mut wait = current.is_some();
+    loop {
+        if wait {
+            let deadline = Instant::now() + poll;
+            while Instant::now() < deadline {
+                if shutdown.requested() {
+                    return None;
+                }
+                thread::sleep(Duration::from_millis(100));
+            }
+        }
+        wait = true;
+
+        match fetch(client, url) {
+            Ok(job) if current != Some(job.as_str()) => return Some(job),
+            Ok(_) => {}
+            Err(e) => warn!("could not fetch a job from {url}: {e}"),
+        }
+    }
+}
+
+fn fetch(client: &Client, url: &str) -> Result<String, reqwest::Error> {
+    let job = client.get(url).send()?.error_for_status()?.text()?;
+    Ok(job.trim().to_string())
+}
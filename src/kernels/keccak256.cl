@@ -128,12 +128,35 @@ a[n + 4] = b[4] ^ ((~b[0]) & b[1]);
 
 #define iteration(x) theta(); rhoPi(); chi(); iota(x);
 
-static inline void keccakf(ulong *a)
+// theta for the first round, starting from column parities computed on the
+// host for each init code hash: only lanes 5 and 6 (the message and the
+// nonce) vary between work items, so THETA_C0 and THETA_C1 exclude them,
+// THETA_C2 to THETA_C4 are the whole parities, and the column 3 term THETA_D3
+// is constant as well
+#define thetaFirst(hash) \
+b[0] = THETA_C0(hash) ^ a[5]; \
+b[1] = THETA_C1(hash) ^ a[6]; \
+b[2] = THETA_C2(hash); \
+b[3] = THETA_C3(hash); \
+b[4] = THETA_C4(hash); \
+theta_(4, 1, 0); \
+theta_(0, 2, 1); \
+theta_(1, 3, 2); \
+t = THETA_D3(hash); \
+a[3] ^= t; \
+a[8] ^= t; \
+a[13] ^= t; \
+a[18] ^= t; \
+a[23] ^= t; \
+theta_(3, 0, 4);
+
+// rounds 2 to 24 of keccakf, the last of which only goes as far as the bytes
+// of the address
+static inline void keccakfRounds(ulong *a)
 {
   ulong b[5];
   ulong t;
 
-  iteration(0x0000000000000001); // iteration 1
   iteration(0x0000000000008082); // iteration 2
   iteration(0x800000000000808a); // iteration 3
   iteration(0x8000000080008000); // iteration 4
@@ -188,6 +211,29 @@ static inline void keccakf(ulong *a)
 #undef o
 }
 
+// keccakf over the CREATE2 preimage with the given init code hash, taking the
+// first round's theta from the host
+static inline void keccakf(ulong *a, uint const hash)
+{
+  ulong b[5];
+  ulong t;
+
+  thetaFirst(hash); rhoPi(); chi(); iota(0x0000000000000001); // iteration 1
+  keccakfRounds(a);
+}
+
+#if CREATE3
+// keccakf over any state
+static inline void keccakfAny(ulong *a)
+{
+  ulong b[5];
+  ulong t;
+
+  iteration(0x0000000000000001); // iteration 1
+  keccakfRounds(a);
+}
+#endif
+
 #define hasTotal(d) ( \
   (!(d[0])) + (!(d[1])) + (!(d[2])) + (!(d[3])) + \
   (!(d[4])) + (!(d[5])) + (!(d[6])) + (!(d[7])) + \
@@ -223,145 +269,227 @@ static inline bool hasLeading(uchar const *d)
 }
 #endif
 
+// nibble `i` of an address, most significant first
+#define nibble(d, i) (((d)[(i) >> 1] >> (((i) & 1u) ? 0u : 4u)) & 0xfu)
+
+#if SCORING
+// score an address by its nibbles under the SCORE_ rules (mirrored by
+// `score_address` on the host)
+static inline uint scoreAddress(uchar const *d)
+{
+  uint leading = 0;
+  while (leading < 40 && !nibble(d, leading))
+    ++leading;
+  uint score = leading * SCORE_LEADING_WEIGHT;
+
+#if SCORE_REQUIRE_TARGET
+  // nothing scores unless the target comes straight after the zeroes
+  if (leading < 40 && nibble(d, leading) != SCORE_NIBBLE)
+    return 0;
+#endif
+
+  // a run of four target nibbles straight after the leading zeroes
+  if (leading + 4 <= 40) {
+    uint run = 0;
+    while (run < 4 && nibble(d, leading + run) == SCORE_NIBBLE)
+      ++run;
+    if (run == 4) {
+      score += SCORE_RUN_BONUS;
+      if (leading + 4 < 40 && nibble(d, leading + 4) != SCORE_NIBBLE)
+        score += SCORE_RUN_END_BONUS;
+    }
+  }
+
+  // four target nibbles at the end, and target nibbles anywhere
+  uint tail = 1;
+  uint count = 0;
+#pragma unroll
+  for (uint i = 0; i < 40; ++i) {
+    uint isTarget = nibble(d, i) == SCORE_NIBBLE;
+    count += isTarget;
+    if (i >= 36)
+      tail &= isTarget;
+  }
+  if (tail)
+    score += SCORE_TAIL_BONUS;
+
+  return score + count * SCORE_NIBBLE_WEIGHT;
+}
+#endif
+
+#if PATTERN
+// whether an address matches the pattern, a 32-bit word at a time; words that
+// are all wildcards fold away
+#define matchesPattern(d) ( \
+  (((uint*)d)[0] & PATTERN_MASK_0) == PATTERN_VALUE_0 && \
+  (((uint*)d)[1] & PATTERN_MASK_1) == PATTERN_VALUE_1 && \
+  (((uint*)d)[2] & PATTERN_MASK_2) == PATTERN_VALUE_2 && \
+  (((uint*)d)[3] & PATTERN_MASK_3) == PATTERN_VALUE_3 && \
+  (((uint*)d)[4] & PATTERN_MASK_4) == PATTERN_VALUE_4)
+#endif
+
+#define sponge ((uchar *) spongeBuffer)
+#define digest (sponge + 12)
+
+// pack eight preimage bytes into a little-endian lane; with compile-time
+// arguments the whole lane folds into a constant
+#define lane(b0, b1, b2, b3, b4, b5, b6, b7) ( \
+  ((ulong)(b0)) | ((ulong)(b1) << 8) | ((ulong)(b2) << 16) | ((ulong)(b3) << 24) | \
+  ((ulong)(b4) << 32) | ((ulong)(b5) << 40) | ((ulong)(b6) << 48) | ((ulong)(b7) << 56))
+
+// build the 85-byte CREATE2 preimage for the given nonce and init code hash
+// (by its index) and apply keccakf. The state is written a lane at a time
+// rather than a byte at a time so that it can live in registers: only lanes 5
+// and 6 (which hold the message and the nonce) vary between work items, the
+// rest are constants or padding. The fixed bytes are folded into immediates
+// rather than placed in __constant memory, and the effect on the hashrate has
+// not been benchmarked against the byte-wise version; `selftest` only checks
+// that the output is unchanged.
+static inline void hashPreimage(
+  ulong *spongeBuffer,
+  __constant uchar const *d_message,
+  nonce_t const nonce,
+  uint const hash
+) {
+  // the message, which starts with the salt prefix if one is pinned
+  ulong const message = (lane(
+    d_message[0], d_message[1], d_message[2], d_message[3], 0, 0, 0, 0
+  ) & ~SALT_PREFIX_MASK) | SALT_PREFIX;
+
+  // 0xff ++ factory ++ caller
+  spongeBuffer[0] = lane(0xffu, S_1, S_2, S_3, S_4, S_5, S_6, S_7);
+  spongeBuffer[1] = lane(S_8, S_9, S_10, S_11, S_12, S_13, S_14, S_15);
+  spongeBuffer[2] = lane(S_16, S_17, S_18, S_19, S_20, S_21, S_22, S_23);
+  spongeBuffer[3] = lane(S_24, S_25, S_26, S_27, S_28, S_29, S_30, S_31);
+  spongeBuffer[4] = lane(S_32, S_33, S_34, S_35, S_36, S_37, S_38, S_39);
+
+  // last caller byte ++ message ++ nonce ++ start of the init code hash
+  spongeBuffer[5] = lane(S_40, 0, 0, 0, 0, 0, 0, 0) | (message << 8) |
+                    (nonce.uint64_t << 40);
+  spongeBuffer[6] = (nonce.uint64_t >> 24) | HASH_LANE_6(hash);
+
+  // rest of the init code hash, then the start of the padding
+  spongeBuffer[7] = HASH_LANE_7(hash);
+  spongeBuffer[8] = HASH_LANE_8(hash);
+  spongeBuffer[9] = HASH_LANE_9(hash);
+  spongeBuffer[10] = HASH_LANE_10(hash);
+
+  // zero padding, ending with 0x80 in the last byte of the rate, and zeroes
+  // for the capacity
+#pragma unroll
+  for (int i = 11; i < 25; ++i)
+    spongeBuffer[i] = 0;
+  spongeBuffer[16] = 0x8000000000000000UL;
+
+  // Apply keccakf
+  keccakf(spongeBuffer, hash);
+
+#if CREATE3
+  // that is the address of the proxy (bytes 12 to 31 of the state), which
+  // deploys the contract with CREATE from its nonce of 1: hash rlp([proxy, 1]),
+  // which is 0xd6 0x94 ++ proxy ++ 0x01, followed by the padding
+  ulong const proxyLow = (spongeBuffer[1] >> 32) | (spongeBuffer[2] << 32);
+  ulong const proxyHigh = (spongeBuffer[2] >> 32) | (spongeBuffer[3] << 32);
+  ulong const proxyEnd = spongeBuffer[3] >> 32;
+  spongeBuffer[0] = lane(0xd6u, 0x94u, 0, 0, 0, 0, 0, 0) | (proxyLow << 16);
+  spongeBuffer[1] = (proxyLow >> 48) | (proxyHigh << 16);
+  spongeBuffer[2] = (proxyHigh >> 48) | (proxyEnd << 16) | lane(0, 0, 0, 0, 0, 0, 0x01u, 0x01u);
+#pragma unroll
+  for (int i = 3; i < 25; ++i)
+    spongeBuffer[i] = 0;
+  spongeBuffer[16] = 0x8000000000000000UL;
+
+  keccakfAny(spongeBuffer);
+#endif
+}
+
 __kernel void hashMessage(
   __constant uchar const *d_message,
-  __constant uint const *d_nonce,
-  __global volatile ulong *restrict solutions
+  uint const d_nonce,
+  __global volatile ulong *restrict solutions,
+  __global volatile uint *restrict solutionCount,
+  __global uchar *restrict solutionAddresses,
+  __global uint *restrict solutionHashes,
+  __global volatile uint *restrict histogram
 ) {
 
   ulong spongeBuffer[25];
 
-#define sponge ((uchar *) spongeBuffer)
-#define digest (sponge + 12)
-
   nonce_t nonce;
 
-  // write the control character
-  sponge[0] = 0xffu;
-
-  sponge[1] = S_1;
-  sponge[2] = S_2;
-  sponge[3] = S_3;
-  sponge[4] = S_4;
-  sponge[5] = S_5;
-  sponge[6] = S_6;
-  sponge[7] = S_7;
-  sponge[8] = S_8;
-  sponge[9] = S_9;
-  sponge[10] = S_10;
-  sponge[11] = S_11;
-  sponge[12] = S_12;
-  sponge[13] = S_13;
-  sponge[14] = S_14;
-  sponge[15] = S_15;
-  sponge[16] = S_16;
-  sponge[17] = S_17;
-  sponge[18] = S_18;
-  sponge[19] = S_19;
-  sponge[20] = S_20;
-  sponge[21] = S_21;
-  sponge[22] = S_22;
-  sponge[23] = S_23;
-  sponge[24] = S_24;
-  sponge[25] = S_25;
-  sponge[26] = S_26;
-  sponge[27] = S_27;
-  sponge[28] = S_28;
-  sponge[29] = S_29;
-  sponge[30] = S_30;
-  sponge[31] = S_31;
-  sponge[32] = S_32;
-  sponge[33] = S_33;
-  sponge[34] = S_34;
-  sponge[35] = S_35;
-  sponge[36] = S_36;
-  sponge[37] = S_37;
-  sponge[38] = S_38;
-  sponge[39] = S_39;
-  sponge[40] = S_40;
-
-  sponge[41] = d_message[0];
-  sponge[42] = d_message[1];
-  sponge[43] = d_message[2];
-  sponge[44] = d_message[3];
-
   // populate the nonce
   nonce.uint32_t[0] = get_global_id(0);
-  nonce.uint32_t[1] = d_nonce[0];
-
-  // populate the body of the message with the nonce
-  sponge[45] = nonce.uint8_t[0];
-  sponge[46] = nonce.uint8_t[1];
-  sponge[47] = nonce.uint8_t[2];
-  sponge[48] = nonce.uint8_t[3];
-  sponge[49] = nonce.uint8_t[4];
-  sponge[50] = nonce.uint8_t[5];
-  sponge[51] = nonce.uint8_t[6];
-  sponge[52] = nonce.uint8_t[7];
-
-  sponge[53] = S_53;
-  sponge[54] = S_54;
-  sponge[55] = S_55;
-  sponge[56] = S_56;
-  sponge[57] = S_57;
-  sponge[58] = S_58;
-  sponge[59] = S_59;
-  sponge[60] = S_60;
-  sponge[61] = S_61;
-  sponge[62] = S_62;
-  sponge[63] = S_63;
-  sponge[64] = S_64;
-  sponge[65] = S_65;
-  sponge[66] = S_66;
-  sponge[67] = S_67;
-  sponge[68] = S_68;
-  sponge[69] = S_69;
-  sponge[70] = S_70;
-  sponge[71] = S_71;
-  sponge[72] = S_72;
-  sponge[73] = S_73;
-  sponge[74] = S_74;
-  sponge[75] = S_75;
-  sponge[76] = S_76;
-  sponge[77] = S_77;
-  sponge[78] = S_78;
-  sponge[79] = S_79;
-  sponge[80] = S_80;
-  sponge[81] = S_81;
-  sponge[82] = S_82;
-  sponge[83] = S_83;
-  sponge[84] = S_84;
-
-  // begin padding based on message length
-  sponge[85] = 0x01u;
-
-  // fill padding
-#pragma unroll
-  for (int i = 86; i < 135; ++i)
-    sponge[i] = 0;
-
-  // end padding
-  sponge[135] = 0x80u;
+  nonce.uint32_t[1] = d_nonce;
 
-  // fill remaining sponge state with zeroes
+  // try the nonce with each init code hash in turn; the loop is unrolled, so
+  // that the constants of each hash fold into the code
 #pragma unroll
-  for (int i = 136; i < 200; ++i)
-    sponge[i] = 0;
-
-  // Apply keccakf
-  keccakf(spongeBuffer);
+  for (uint hash = 0; hash < INIT_CODE_HASHES; ++hash) {
+    hashPreimage(spongeBuffer, d_message, nonce, hash);
+
+#if HISTOGRAM
+    // count candidates by their number of leading zero nibbles; depths below
+    // HISTOGRAM_MIN_NIBBLES are skipped as they would need an atomic on nearly
+    // every work item
+    if (!(digest[0] | digest[1])) {
+      uint nibbles = HISTOGRAM_MIN_NIBBLES;
+      while (nibbles < 40 && !nibble(digest, nibbles))
+        ++nibbles;
+      atomic_inc(&histogram[nibbles]);
+    }
+#endif
 
-  // determine if the address meets the constraints
-  if (
-    hasLeading(digest) 
+    // determine if the address meets the constraints: a pattern or else a
+    // minimum score, if given, takes the place of the zero byte thresholds
+#if PATTERN
+    if (matchesPattern(digest)) {
+#elif SCORING && MIN_SCORE
+    if (scoreAddress(digest) >= MIN_SCORE) {
+#else
+    if (
+      hasLeading(digest)
 #if TOTAL_ZEROES <= 20
-    || hasTotal(digest)
+      || hasTotal(digest)
+#endif
+    ) {
 #endif
-  ) {
-    // To be honest, if we are using OpenCL, 
-    // we just need to write one solution for all practical purposes,
-    // since the chance of multiple solutions appearing
-    // in a single workset is extremely low.
-    solutions[0] = nonce.uint64_t;
+      // claim the next free slot; the count keeps increasing past the end of
+      // the buffer so that the host can tell how many solutions were dropped
+      uint slot = atomic_inc(solutionCount);
+      if (slot < MAX_SOLUTIONS) {
+        solutions[slot] = nonce.uint64_t;
+        solutionHashes[slot] = hash;
+
+        // return the derived address so the host doesn't need to hash again
+        __global uchar *address = solutionAddresses + slot * 20;
+#pragma unroll
+        for (int i = 0; i < 20; ++i)
+          address[i] = digest[i];
+      }
+    }
   }
 }
+
+// write the derived address of every work item (with the first init code hash)
+// to `addresses` (20 bytes each) so that the host can check the kernel against a reference implementation
+__kernel void hashDigests(
+  __constant uchar const *d_message,
+  uint const d_nonce,
+  __global uchar *restrict addresses
+) {
+
+  ulong spongeBuffer[25];
+
+  nonce_t nonce;
+
+  // populate the nonce
+  nonce.uint32_t[0] = get_global_id(0);
+  nonce.uint32_t[1] = d_nonce;
+
+  hashPreimage(spongeBuffer, d_message, nonce, 0);
+
+  __global uchar *address = addresses + (get_global_id(0) - get_global_offset(0)) * 20;
+#pragma unroll
+  for (int i = 0; i < 20; ++i)
+    address[i] = digest[i];
+}
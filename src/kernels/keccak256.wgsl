@@ -0,0 +1,257 @@
+// Keccak-f[1600] (for finding efficient Ethereum addresses), ported from
+// keccak256.cl for wgpu. WGSL has no 64-bit integers, so each lane is held as
+// a pair of 32-bit words: `x` for the low half and `y` for the high half.
+//
+// The host prepends HISTOGRAM_BUCKETS, HISTOGRAM_MIN_NIBBLES, WORKGROUP_SIZE,
+// CREATE3, PATTERN and the PATTERN_MASK_ and PATTERN_VALUE_ words as
+// constants.
+
+struct Params {
+  // the upper half of the nonce; the lower half is the work item ID
+  nonce: u32,
+  leading_zeroes: u32,
+  total_zeroes: u32,
+  max_solutions: u32,
+  histogram: u32,
+  // nibble scoring rules, as in keccak256.cl; `scoring` is 0 when disabled
+  scoring: u32,
+  score_nibble: u32,
+  score_leading_weight: u32,
+  score_run_bonus: u32,
+  score_run_end_bonus: u32,
+  score_tail_bonus: u32,
+  score_nibble_weight: u32,
+  min_score: u32,
+  score_require_target: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+
+// the padded 85-byte CREATE2 preimage with the nonce left as zeroes
+@group(0) @binding(1) var<storage, read> preimage: array<vec2<u32>, 25>;
+
+@group(0) @binding(2) var<storage, read_write> solutions: array<vec2<u32>>;
+@group(0) @binding(3) var<storage, read_write> solutionCount: atomic<u32>;
+@group(0) @binding(4) var<storage, read_write> solutionAddresses: array<u32>;
+@group(0) @binding(5) var<storage, read_write> histogram: array<atomic<u32>, HISTOGRAM_BUCKETS>;
+
+var<private> roundConstants: array<vec2<u32>, 24> = array<vec2<u32>, 24>(
+  vec2<u32>(0x00000001u, 0x00000000u), vec2<u32>(0x00008082u, 0x00000000u),
+  vec2<u32>(0x0000808au, 0x80000000u), vec2<u32>(0x80008000u, 0x80000000u),
+  vec2<u32>(0x0000808bu, 0x00000000u), vec2<u32>(0x80000001u, 0x00000000u),
+  vec2<u32>(0x80008081u, 0x80000000u), vec2<u32>(0x00008009u, 0x80000000u),
+  vec2<u32>(0x0000008au, 0x00000000u), vec2<u32>(0x00000088u, 0x00000000u),
+  vec2<u32>(0x80008009u, 0x00000000u), vec2<u32>(0x8000000au, 0x00000000u),
+  vec2<u32>(0x8000808bu, 0x00000000u), vec2<u32>(0x0000008bu, 0x80000000u),
+  vec2<u32>(0x00008089u, 0x80000000u), vec2<u32>(0x00008003u, 0x80000000u),
+  vec2<u32>(0x00008002u, 0x80000000u), vec2<u32>(0x00000080u, 0x80000000u),
+  vec2<u32>(0x0000800au, 0x00000000u), vec2<u32>(0x8000000au, 0x80000000u),
+  vec2<u32>(0x80008081u, 0x80000000u), vec2<u32>(0x00008080u, 0x80000000u),
+  vec2<u32>(0x80000001u, 0x00000000u), vec2<u32>(0x80008008u, 0x80000000u),
+);
+
+// rotation offsets and destination lanes for the combined rho and pi steps
+var<private> rotations: array<u32, 24> = array<u32, 24>(
+  1u, 3u, 6u, 10u, 15u, 21u, 28u, 36u, 45u, 55u, 2u, 14u,
+  27u, 41u, 56u, 8u, 25u, 43u, 62u, 18u, 39u, 61u, 20u, 44u,
+);
+var<private> piLanes: array<u32, 24> = array<u32, 24>(
+  10u, 7u, 11u, 17u, 18u, 3u, 5u, 16u, 8u, 21u, 24u, 4u,
+  15u, 23u, 19u, 13u, 12u, 2u, 20u, 14u, 22u, 9u, 6u, 1u,
+);
+
+// rotate a lane left by 1 to 63 bits
+fn rol(x: vec2<u32>, n: u32) -> vec2<u32> {
+  var v = x;
+  var s = n;
+  if (s >= 32u) {
+    v = x.yx;
+    s = s - 32u;
+  }
+  if (s == 0u) {
+    return v;
+  }
+  return vec2<u32>((v.x << s) | (v.y >> (32u - s)), (v.y << s) | (v.x >> (32u - s)));
+}
+
+fn keccakf(a: ptr<function, array<vec2<u32>, 25>>) {
+  var b: array<vec2<u32>, 5>;
+
+  for (var round = 0u; round < 24u; round++) {
+    // theta
+    for (var x = 0u; x < 5u; x++) {
+      b[x] = (*a)[x] ^ (*a)[x + 5u] ^ (*a)[x + 10u] ^ (*a)[x + 15u] ^ (*a)[x + 20u];
+    }
+    for (var x = 0u; x < 5u; x++) {
+      let t = b[(x + 4u) % 5u] ^ rol(b[(x + 1u) % 5u], 1u);
+      for (var y = 0u; y < 25u; y += 5u) {
+        (*a)[y + x] ^= t;
+      }
+    }
+
+    // rho pi
+    var t = (*a)[1];
+    for (var i = 0u; i < 24u; i++) {
+      let lane = piLanes[i];
+      let next = (*a)[lane];
+      (*a)[lane] = rol(t, rotations[i]);
+      t = next;
+    }
+
+    // chi
+    for (var y = 0u; y < 25u; y += 5u) {
+      for (var x = 0u; x < 5u; x++) {
+        b[x] = (*a)[y + x];
+      }
+      for (var x = 0u; x < 5u; x++) {
+        (*a)[y + x] = b[x] ^ (~b[(x + 1u) % 5u] & b[(x + 2u) % 5u]);
+      }
+    }
+
+    // iota
+    (*a)[0] ^= roundConstants[round];
+  }
+}
+
+// byte `i` of the 20-byte address held in five little-endian words
+fn digestByte(digest: array<u32, 5>, i: u32) -> u32 {
+  return (digest[i >> 2u] >> ((i & 3u) * 8u)) & 0xffu;
+}
+
+// nibble `i` of the address, most significant first
+fn digestNibble(digest: array<u32, 5>, i: u32) -> u32 {
+  return (digestByte(digest, i >> 1u) >> select(4u, 0u, (i & 1u) != 0u)) & 0xfu;
+}
+
+// score the address by its nibbles (mirrored by `score_address` on the host)
+fn scoreAddress(digest: array<u32, 5>) -> u32 {
+  var leading = 0u;
+  while (leading < 40u && digestNibble(digest, leading) == 0u) {
+    leading++;
+  }
+  var score = leading * params.score_leading_weight;
+
+  // nothing scores unless the target comes straight after the zeroes
+  if (params.score_require_target != 0u && leading < 40u &&
+      digestNibble(digest, leading) != params.score_nibble) {
+    return 0u;
+  }
+
+  // a run of four target nibbles straight after the leading zeroes
+  if (leading + 4u <= 40u) {
+    var run = 0u;
+    while (run < 4u && digestNibble(digest, leading + run) == params.score_nibble) {
+      run++;
+    }
+    if (run == 4u) {
+      score += params.score_run_bonus;
+      if (leading + 4u < 40u && digestNibble(digest, leading + 4u) != params.score_nibble) {
+        score += params.score_run_end_bonus;
+      }
+    }
+  }
+
+  // four target nibbles at the end, and target nibbles anywhere
+  var tail = true;
+  var count = 0u;
+  for (var i = 0u; i < 40u; i++) {
+    let isTarget = digestNibble(digest, i) == params.score_nibble;
+    count += u32(isTarget);
+    if (i >= 36u) {
+      tail = tail && isTarget;
+    }
+  }
+  if (tail) {
+    score += params.score_tail_bonus;
+  }
+
+  return score + count * params.score_nibble_weight;
+}
+
+// whether an address matches the pattern, a word at a time
+fn matchesPattern(digest: array<u32, 5>) -> bool {
+  return (digest[0] & PATTERN_MASK_0) == PATTERN_VALUE_0 &&
+    (digest[1] & PATTERN_MASK_1) == PATTERN_VALUE_1 &&
+    (digest[2] & PATTERN_MASK_2) == PATTERN_VALUE_2 &&
+    (digest[3] & PATTERN_MASK_3) == PATTERN_VALUE_3 &&
+    (digest[4] & PATTERN_MASK_4) == PATTERN_VALUE_4;
+}
+
+@compute @workgroup_size(WORKGROUP_SIZE)
+fn hashMessage(
+  @builtin(global_invocation_id) globalId: vec3<u32>,
+  @builtin(num_workgroups) workgroups: vec3<u32>
+) {
+  let id = globalId.x + globalId.y * workgroups.x * WORKGROUP_SIZE;
+
+  // place the 8-byte nonce (the work item ID, then the upper half) at bytes
+  // 45 to 52 of the preimage, which straddle lanes 5 and 6
+  var a = preimage;
+  a[5].y |= id << 8u;
+  a[6].x |= (id >> 24u) | (params.nonce << 8u);
+  a[6].y |= params.nonce >> 24u;
+
+  keccakf(&a);
+
+  // with CREATE3 that is the address of the proxy, which deploys the contract
+  // with CREATE from its nonce of 1: hash rlp([proxy, 1]), which is
+  // 0xd6 0x94 ++ proxy ++ 0x01, followed by the padding
+  if (CREATE3) {
+    let proxy = array<u32, 5>(a[1].y, a[2].x, a[2].y, a[3].x, a[3].y);
+    var c: array<vec2<u32>, 25>;
+    c[0] = vec2<u32>(0x94d6u | (proxy[0] << 16u), (proxy[0] >> 16u) | (proxy[1] << 16u));
+    c[1] = vec2<u32>((proxy[1] >> 16u) | (proxy[2] << 16u), (proxy[2] >> 16u) | (proxy[3] << 16u));
+    c[2] = vec2<u32>((proxy[3] >> 16u) | (proxy[4] << 16u), (proxy[4] >> 16u) | 0x01010000u);
+    c[16].y = 0x80000000u;
+    a = c;
+    keccakf(&a);
+  }
+
+  // the address is the last 20 bytes of the hash
+  let digest = array<u32, 5>(a[1].y, a[2].x, a[2].y, a[3].x, a[3].y);
+
+  // count candidates by their number of leading zero nibbles; depths below
+  // HISTOGRAM_MIN_NIBBLES are skipped as in the OpenCL kernel
+  if (params.histogram != 0u && (digest[0] & 0xffffu) == 0u) {
+    var nibbles = HISTOGRAM_MIN_NIBBLES;
+    while (nibbles < 40u && digestNibble(digest, nibbles) == 0u) {
+      nibbles++;
+    }
+    atomicAdd(&histogram[nibbles], 1u);
+  }
+
+  // determine if the address meets the constraints
+  var leading = 0u;
+  while (leading < 20u && digestByte(digest, leading) == 0u) {
+    leading++;
+  }
+  var total = 0u;
+  for (var i = 0u; i < 20u; i++) {
+    total += u32(digestByte(digest, i) == 0u);
+  }
+  if (PATTERN) {
+    // a pattern takes the place of the other constraints
+    if (!matchesPattern(digest)) {
+      return;
+    }
+  } else if (params.scoring != 0u && params.min_score != 0u) {
+    // a minimum score takes the place of the zero byte thresholds
+    if (scoreAddress(digest) < params.min_score) {
+      return;
+    }
+  } else if (leading < params.leading_zeroes && total < params.total_zeroes) {
+    return;
+  }
+
+  // claim the next free slot; the count keeps increasing past the end of the
+  // buffer so that the host can tell how many solutions were dropped
+  let slot = atomicAdd(&solutionCount, 1u);
+  if (slot < params.max_solutions) {
+    solutions[slot] = vec2<u32>(id, params.nonce);
+
+    // return the derived address so the host doesn't need to hash again
+    for (var i = 0u; i < 5u; i++) {
+      solutionAddresses[slot * 5u + i] = digest[i];
+    }
+  }
+}
@@ -0,0 +1,241 @@
+use crate::miner::Solution;
+use alloy_primitives::hex;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A result on the leaderboard. The score is what solutions are ranked by (the
+/// pattern score if scoring, or else the reward), kept as decimal text as it
+/// can exceed a JSON number, and the timestamp is when it was found, in Unix
+/// seconds.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Entry {
+    salt: String,
+    address: String,
+    factory: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    init_code_hash: Option<String>,
+    score: String,
+    timestamp: u64,
+}
+
+impl Entry {
+    fn rank(&self) -> u128 {
+        self.score.parse().unwrap_or(0)
+    }
+}
+
+/// The best `size` results found across runs (`--leaderboard`), kept on disk
+/// as a JSON array sorted best first. The file is rewritten in a single step
+/// whenever a result makes it onto the board, so that a crash never leaves a
+/// partial one behind. Reserved addresses are left out, and so is a salt that
+/// is already on the board.
+pub(crate) struct Leaderboard {
+    path: PathBuf,
+    size: usize,
+    entries: Mutex<Vec<Entry>>,
+}
+
+impl Leaderboard {
+    /// Carry on with the board at `path`, or start one if there is none.
+    pub(crate) fn open(path: &Path, size: usize) -> Result<Self, Box<dyn Error>> {
+        let mut entries = read(path).or_else(|e| match e.kind() {
+            io::ErrorKind::NotFound => Ok(vec![]),
+            _ => Err(format!("{}: {e}", path.display())),
+        })?;
+        sort(&mut entries);
+        entries.truncate(size);
+        Ok(Self {
+            path: path.to_path_buf(),
+            size,
+            entries: Mutex::new(entries),
+        })
+    }
+
+    /// Put a solution on the board if it is good enough, writing the board
+    /// out if it is. Returns whether it was.
+    pub(crate) fn offer(&self, solution: &Solution) -> io::Result<bool> {
+        if solution.reserved {
+            return Ok(false);
+        }
+        let salt = hex::encode_prefixed(solution.salt);
+        let rank = solution.rank();
+
+        let mut entries = self.entries.lock().unwrap();
+        let full = entries.len() == self.size;
+        let worse = full && entries.last().is_some_and(|last| rank <= last.rank());
+        if worse || entries.iter().any(|entry| entry.salt == salt) {
+            return Ok(false);
+        }
+
+        entries.push(Entry {
+            salt,
+            address: solution.address.to_checksum(None),
+            factory: solution.factory.to_checksum(None),
+            init_code_hash: solution.init_code_hash.map(hex::encode_prefixed),
+            score: rank.to_string(),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+        });
+        sort(&mut entries);
+        entries.truncate(self.size);
+        write(&self.path, &entries)?;
+        Ok(true)
+    }
+}
+
+/// Print the board at `path` as a ranked table, with the time each result was
+/// found (in UTC).
+pub(crate) fn print(path: &Path) -> Result<(), Box<dyn Error>> {
+    let entries = read(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    if entries.is_empty() {
+        return Err(format!("{} is empty", path.display()).into());
+    }
+
+    let width = entries.iter().map(|e| e.score.len()).max().unwrap_or(1);
+    for (i, entry) in entries.iter().enumerate() {
+        println!(
+            "{:>4}. {:>width$}  {}  {}  {}",
+            i + 1,
+            entry.score,
+            entry.address,
+            entry.salt,
+            utc(entry.timestamp)
+        );
+    }
+
+    Ok(())
+}
+
+// best first, with ties going to the result found first
+fn sort(entries: &mut [Entry]) {
+    entries.sort_by(|a, b| {
+        b.rank()
+            .cmp(&a.rank())
+            .then_with(|| a.timestamp.cmp(&b.timestamp))
+    });
+}
+
+fn read(path: &Path) -> io::Result<Vec<Entry>> {
+    let text = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&text)?)
+}
+
+// write the board via a temporary file that is moved into place
+fn write(path: &Path, entries: &[Entry]) -> io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+
+    let mut file = File::create(&tmp)?;
+    writeln!(file, "{}", serde_json::to_string_pretty(entries)?)?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)
+}
+
+// a Unix timestamp as a UTC date and time, such as `2024-05-01 13:37:00`
+pub(crate) fn utc(timestamp: u64) -> String {
+    let (days, secs) = (timestamp / 86400, timestamp % 86400);
+
+    // the civil date of a day count, shifted to start each year in March
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+
+    format!(
+        "{year}-{month:02}-{day:02} {:02}:{:02}:{:02}",
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{Address, B256, U256};
+
+    #[test]
+    fn utc_dates() {
+        for (timestamp, expected) in [
+            (0, "1970-01-01 00:00:00"),
+            (951_782_400, "2000-02-29 00:00:00"),
+            (1_709_210_096, "2024-02-29 12:34:56"),
+            (1_714_570_620, "2024-05-01 13:37:00"),
+            // 2100 is not a leap year
+            (4_107_542_399, "2100-02-28 23:59:59"),
+            (4_107_542_400, "2100-03-01 00:00:00"),
+        ] {
+            assert_eq!(utc(timestamp), expected, "{timestamp}");
+        }
+    }
+
+    fn solution(byte: u8, reward: u64) -> Solution {
+        Solution {
+            salt: B256::repeat_byte(byte),
+            address: Address::repeat_byte(byte),
+            factory: Address::ZERO,
+            caller: None,
+            init_code_hash: None,
+            reward: U256::from(reward),
+            score: None,
+            reserved: false,
+            device: None,
+        }
+    }
+
+    #[test]
+    fn offers() {
+        let path = std::env::temp_dir().join(format!(
+            "create2crunch-{}-leaderboard.json",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        let board = Leaderboard::open(&path, 2).unwrap();
+
+        assert!(board.offer(&solution(1, 5)).unwrap());
+        // the same salt, even if ranked higher
+        assert!(!board.offer(&solution(1, 9)).unwrap());
+        assert!(board.offer(&solution(2, 3)).unwrap());
+        // no better than the last of a full board
+        assert!(!board.offer(&solution(3, 3)).unwrap());
+        assert!(!board.offer(&solution(4, 1)).unwrap());
+        assert!(!board
+            .offer(&Solution {
+                reserved: true,
+                ..solution(5, 9)
+            })
+            .unwrap());
+        assert!(board.offer(&solution(6, 4)).unwrap());
+
+        // kept on disk, best first
+        let board = Leaderboard::open(&path, 2).unwrap();
+        let entries = board.entries.lock().unwrap();
+        let ranks: Vec<_> = entries.iter().map(|e| (e.salt.clone(), e.rank())).collect();
+        assert_eq!(
+            ranks,
+            [
+                (hex::encode_prefixed([1; 32]), 5),
+                (hex::encode_prefixed([6; 32]), 4)
+            ]
+        );
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -4,63 +4,431 @@
 use alloy_primitives::{hex, Address, FixedBytes};
 use byteorder::{BigEndian, ByteOrder, LittleEndian};
 use console::Term;
-use fs4::FileExt;
-use ocl::{Buffer, Context, Device, MemFlags, Platform, ProQue, Program, Queue};
+use ocl::enums::Status;
+use ocl::flags::DeviceType;
+use ocl::{Buffer, Context, Device, Event, Kernel, MemFlags, Platform, ProQue, Program, Queue};
 use rand::{thread_rng, Rng};
 use rayon::prelude::*;
-use reqwest::blocking::Client;
 use separator::Separatable;
 use serde_json::json;
+use std::collections::{BTreeSet, HashSet, VecDeque};
 use std::error::Error;
 use std::fmt::Write as _;
 use std::fs::{File, OpenOptions};
-use std::io::prelude::*;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use terminal_size::{terminal_size, Height};
 use tiny_keccak::{Hasher, Keccak};
-
+use tracing::{debug, debug_span, info, warn};
+
+mod bench;
+mod cast;
+mod checkpoint;
+pub mod cli;
+mod config;
+mod control;
+mod db;
+#[cfg(feature = "deploy")]
+mod deploy;
+mod erc4337;
+mod heartbeat;
+mod init_code;
+mod jobs;
+mod leaderboard;
+mod logging;
+pub mod miner;
+mod mode;
+mod monitor;
+mod nonces;
+mod output;
+mod pattern;
+mod payload;
+mod power;
+mod priority;
+mod probability;
+mod record;
+mod replay;
+pub mod reserved;
+pub mod results;
 mod reward;
+mod rpc;
+mod salt;
+mod score;
+#[cfg(feature = "wasm")]
+mod scorer;
+mod selftest;
+mod shutdown;
+mod status;
+mod submit;
+mod tui;
+#[cfg(feature = "wgpu")]
+mod webgpu;
+mod websocket;
+pub use bench::bench;
+pub use cast::FactoryAbi;
+pub use config::JobFile;
+use control::{Control, Tuning};
+#[cfg(feature = "deploy")]
+pub use deploy::{Deployment, DEFAULT_DEPLOY_CALL};
+pub use erc4337::simple_account_init_code;
+pub use init_code::{fetch_init_code, fetch_init_code_hash};
+pub use jobs::work_jobs;
+pub use logging::{init_logging, LogFormat};
+pub use miner::{Miner, Mining, Solution};
+pub use mode::{Mode, CREATE3_PROXY_INIT_CODE_HASH};
+pub use nonces::nonces;
+pub use pattern::Pattern;
+pub use payload::{PayloadFormat, PayloadTemplate};
+pub use power::{on_battery, BatteryAction};
+pub use priority::set_niceness;
+pub use probability::{reward_probability, score_probability};
+pub use replay::replay;
+pub use reserved::{Reserved, ReservedAction};
 pub use reward::Reward;
+pub use rpc::DEFAULT_RPC_VERIFY;
+pub use salt::SaltMode;
+use score::matches_checksum;
+pub use score::{score_address, score_address_detailed, ScoreBreakdown, Scoring, ScoringMode};
+#[cfg(feature = "wasm")]
+pub use scorer::Scorer;
+pub use selftest::{selftest, DEFAULT_SELFTEST_COUNT};
+
+/// Default global work size of a GPU dispatch (tweak with `--work-size`).
+pub const DEFAULT_WORK_SIZE: u32 = 0x4000000;
+
+// the work size is halved on resource errors, but never below this
+const MIN_WORK_SIZE: u32 = 0x10000;
+
+// the largest work size that auto-tuning will try (abs. max 0xffffffff)
+const MAX_WORK_SIZE: u32 = 0x15400000;
 
-// workset size (tweak this!)
-const WORK_SIZE: u32 = 0x4000000; // max. 0x15400000 to abs. max 0xffffffff
+// dispatches timed for each work size tried by auto-tuning, after a warm-up
+const AUTO_TUNE_DISPATCHES: u32 = 3;
 
-const WORK_FACTOR: u128 = (WORK_SIZE as u128) / 1_000_000;
+// auto-tuning tries no larger work sizes once a dispatch takes this long
+const AUTO_TUNE_MAX_MILLIS: u128 = 2000;
 const CONTROL_CHARACTER: u8 = 0xff;
 const MAX_INCREMENTER: u64 = 0xffffffffffff;
 
+/// Number of the best earlier salts that a warm start is seeded from.
+pub const WARM_START_SEEDS: usize = 16;
+
+/// Default size of the solutions buffer relative to the work size.
+pub const DEFAULT_SOLUTIONS_FRACTION: f64 = 1.0 / 65536.0;
+
+// number of recently found solutions kept for display, more than any terminal
+// is tall; everything else lives in the results file
+const RECENT_FOUND: usize = 256;
+
+// near-miss histogram buckets, one per possible count of leading zero nibbles,
+// of which only the deeper (rarer) ones are counted on the device
+const HISTOGRAM_BUCKETS: usize = 41;
+const HISTOGRAM_MIN_NIBBLES: usize = 4;
+
+/// By default, one in this many GPU solutions is re-derived on the host.
+pub const DEFAULT_VERIFY_SAMPLE: u64 = 64;
+
+// keccak-256 of empty bytes, i.e. the code hash of an account without code
+const EMPTY_CODE_HASH: [u8; 32] =
+    hex!("c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470");
+
+/// File that found salts are appended to.
+pub const OUTPUT_FILE: &str = "efficient_addresses.txt";
+
+/// File that submissions are spooled to while the endpoint cannot take them.
+pub const SPOOL_FILE: &str = "failed_submissions.jsonl";
+
+/// File that the progress of a deterministic search is saved to.
+pub const CHECKPOINT_FILE: &str = "checkpoint.json";
+
+/// File that the best results across runs are kept in, for `leaderboard`.
+pub const LEADERBOARD_FILE: &str = "leaderboard.json";
+
+/// By default, the leaderboard keeps this many results.
+pub const DEFAULT_LEADERBOARD_SIZE: usize = 50;
+
+// attempts made by a deterministic search on the CPU between updates of its
+// position, which is as much as a shutdown can cost it
+const CHECKPOINT_CHUNK: u64 = 1 << 22;
+
 static KERNEL_SRC: &str = include_str!("./kernels/keccak256.cl");
 
-/// Requires three hex-encoded arguments: the address of the contract that will
-/// be calling CREATE2, the address of the caller of said contract *(assuming
-/// the contract calling CREATE2 has frontrunning protection in place - if not
-/// applicable to your use-case you can set it to the null address)*, and the
-/// keccak-256 hash of the bytecode that is provided by the contract calling
-/// CREATE2 that will be used to initialize the new contract. An additional set
-/// of three optional values may be provided: a device to target for OpenCL GPU
-/// search, a threshold for leading zeroes to search for, and a threshold for
-/// total zeroes to search for.
+/// What to mine for and what to do with the results, as parsed from the
+/// command line by [`cli::Cli`]. Every combination of the factory and calling
+/// addresses is searched in turn for salts that give efficient addresses,
+/// which are posted to the endpoints and recorded as configured below.
 pub struct Config {
-    pub factory_address: [u8; 20],
-    pub calling_address: [u8; 20],
+    /// The addresses of the contracts that will be calling CREATE2. Results
+    /// for each factory are kept apart.
+    pub factory_addresses: Vec<[u8; 20]>,
+    /// The addresses of the callers of said contracts, which salts start with
+    /// *(assuming the contract calling CREATE2 has frontrunning protection in
+    /// place - if not applicable to your use-case you can set it to the null
+    /// address)*.
+    pub calling_addresses: Vec<[u8; 20]>,
+    /// The keccak-256 hash of the bytecode that is provided by the contract
+    /// calling CREATE2 that will be used to initialize the new contract.
     pub init_code_hash: [u8; 32],
+    /// The init code itself, which the `deploy` feature deploys.
+    pub init_code: Option<Vec<u8>>,
+    /// The init code hashes of other contracts deployed through the same
+    /// factory, with each of which every salt is also tried. Each result is
+    /// tagged with the hash it is for.
+    pub extra_init_code_hashes: Vec<[u8; 32]>,
+    /// How addresses are derived. In [`Mode::Create3`], they are those of
+    /// contracts deployed through a CREATE3 proxy, which do not depend on the
+    /// init code hash.
+    pub mode: Mode,
+    /// How salts start. With [`SaltMode::Free`], salts start with random
+    /// bytes rather than a caller (and no calling addresses are given).
+    pub salt_mode: SaltMode,
+    /// Bytes that salts must carry after the caller, which take the place of
+    /// as many bytes of the random segment.
+    pub salt_prefix: Vec<u8>,
+    /// The OpenCL platform that devices are numbered within, which is
+    /// otherwise picked automatically.
+    pub platform: Option<u8>,
+    /// The OpenCL device to search on, or 255 for the CPU.
     pub gpu_device: u8,
+    /// Further OpenCL devices that are searched alongside `gpu_device` from
+    /// the same process.
+    pub extra_gpu_devices: Vec<u8>,
+    /// Whether the CPU searches a share of its own alongside the GPUs.
+    pub hybrid: bool,
+    /// The number of leading zero bytes an address needs to be reported.
     pub leading_zeroes_threshold: u8,
+    /// The number of zero bytes an address needs to be reported.
     pub total_zeroes_threshold: u8,
-    pub endpoint_url: Option<String>,
+    /// The endpoints that results are posted to, from a separate thread.
+    pub endpoint_urls: Vec<String>,
+    /// A WebSocket that takes the results in place of the endpoints while
+    /// connected.
+    pub ws_endpoint: Option<String>,
+    /// The file that results are spooled to while the endpoints cannot take
+    /// them.
+    pub spool: PathBuf,
+    /// The most results posted in one request.
+    pub batch_size: usize,
+    /// How long to wait at most for a batch to fill.
+    pub batch_interval_secs: u64,
+    /// Headers (such as an `Authorization` token) attached to each request.
+    pub headers: Vec<(String, String)>,
+    /// The shape that results are posted in.
+    pub payload_format: PayloadFormat,
+    /// The template that describes a custom `payload_format`.
+    pub payload_template: Option<PayloadTemplate>,
+    /// A tag for each result and report of this run.
+    pub job_id: Option<String>,
+    /// Which of the `worker_count` processes of a fleet this is. Each takes a
+    /// disjoint share of the random salt segments.
+    pub worker_id: u32,
+    /// The number of processes in the fleet.
+    pub worker_count: u32,
+    /// Where to post a status report every `heartbeat_interval_secs`.
+    pub heartbeat_url: Option<String>,
+    /// How often a status report is posted, in seconds.
+    pub heartbeat_interval_secs: u64,
+    /// The address to serve the status over HTTP on.
+    pub status_addr: Option<String>,
+    /// A file that every result is appended to, as JSON lines or CSV.
+    pub output_file: Option<PathBuf>,
+    /// An SQLite store that every result is recorded in.
+    pub db: Option<PathBuf>,
+    /// A file that keeps the best `leaderboard_size` results across runs.
+    pub leaderboard: Option<PathBuf>,
+    /// How many results the leaderboard keeps.
+    pub leaderboard_size: usize,
+    /// Whether a result is only posted if it scores higher than every one
+    /// posted before it (and than `current_best`).
+    pub only_improvements: bool,
+    /// The best score known from earlier runs, which a result must beat to
+    /// be posted with `only_improvements`.
+    pub current_best: Option<u128>,
+    /// A score that stops the run once an address reaches it.
+    pub stop_at_score: Option<u128>,
+    /// How long the run lasts at most.
+    pub max_runtime_secs: Option<u64>,
+    /// How many hashes the run tries at most.
+    pub max_hashes: Option<u128>,
+    /// Addresses that are skipped or flagged when found, as `reserved_action`
+    /// says.
+    pub reserved: Option<Reserved>,
+    /// Whether reserved addresses are skipped or reported with a flag.
+    pub reserved_action: ReservedAction,
+    /// The niceness to run the host at, so that it yields to other work.
+    pub nice: Option<i32>,
+    /// In GPU mode, how often to poll for a dispatch to complete, rather than
+    /// sleeping until the driver reports it (0).
+    pub poll_interval_ms: u64,
+    /// The size of the GPU solutions buffer, as a fraction of the work size.
+    pub solutions_fraction: f64,
+    /// One in every this many addresses that a device returns is re-derived
+    /// on the host.
+    pub verify_sample: u64,
+    /// Whether each OpenCL device is checked against the host as by
+    /// [`selftest`] before the search starts, which does not start if any of
+    /// them diverges.
+    pub selftest: bool,
+    /// Where the nonce segment starts, which with `nonce_stride` lets several
+    /// processes interleave rather than overlap.
+    pub nonce_start: Option<u64>,
+    /// The step between the nonces tried, 1 to try every one.
+    pub nonce_stride: u64,
+    /// In GPU mode, whether the device keeps a histogram of candidates by
+    /// leading zero nibbles, so that observed rates can be compared with
+    /// theory.
+    pub histogram: bool,
+    /// A bound in bytes on the larger structures, such as the reserved set
+    /// and the queues of results waiting to be posted. Other state that could
+    /// grow over a long run is capped.
+    pub max_memory: Option<usize>,
+    /// Salts of an earlier run, next to which every other salt segment is
+    /// seeded in GPU mode, so that the space around good results is explored
+    /// further.
+    pub warm_start: Vec<[u8; 32]>,
+    /// Whether mining is throttled or paused while a laptop runs on battery
+    /// power.
+    pub on_battery: BatteryAction,
+    /// The temperature in °C at or over which dispatches to a GPU are paused.
+    pub max_temp: Option<u32>,
+    /// Rules for scoring addresses by their nibbles, which are compiled into
+    /// the kernel to mine for patterns other than zero bytes. A minimum score
+    /// then replaces the thresholds in deciding what the device reports.
+    pub scoring: Option<Scoring>,
+    /// Digits that the EIP-55 checksummed form of an address must start with
+    /// (matching case), which is checked on the host.
+    pub checksum_prefix: Option<String>,
+    /// A pattern that exactly the reported addresses match, in place of the
+    /// thresholds or the minimum score.
+    pub pattern: Option<Pattern>,
+    /// A WebAssembly module that scores what the device reports on the host,
+    /// instead of the built-in rules.
+    #[cfg(feature = "wasm")]
+    pub scorer: Option<Scorer>,
+    /// The number of nonces each GPU dispatch covers.
+    pub work_size: u32,
+    /// Whether to use whichever work size around `work_size` gives the best
+    /// hash rate on each device.
+    pub auto_tune: bool,
+    /// Whether to walk the salt segments of this share in order from
+    /// `start_segment` rather than at random.
+    pub deterministic: bool,
+    /// The salt segment a deterministic search starts at.
+    pub start_segment: u64,
+    /// Where the command line tool saves the progress of a deterministic
+    /// search every `checkpoint_interval_secs`.
+    pub checkpoint: PathBuf,
+    /// How often the checkpoint is saved, in seconds.
+    pub checkpoint_interval_secs: u64,
+    /// Whether to carry on from the `checkpoint`.
+    pub resume: bool,
+    /// A JSON-RPC node that the command line tool checks every result against
+    /// before recording it, by calling the `rpc_verify` view of the factory
+    /// with the salt (and the init code hash).
+    pub rpc_url: Option<String>,
+    /// The signature of the view that results are checked with.
+    pub rpc_verify: String,
+    /// The factory function that each result carries a `cast send` command
+    /// for.
+    pub factory_abi: Option<FactoryAbi>,
+    /// How to deploy the first address to reach `stop_at_score` through the
+    /// `rpc_url` node.
+    #[cfg(feature = "deploy")]
+    pub deploy: Option<Deployment>,
+    /// In GPU mode, whether to show progress on a full-screen dashboard.
+    pub tui: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            factory_addresses: vec![[0; 20]],
+            calling_addresses: vec![[0; 20]],
+            init_code_hash: [0; 32],
+            init_code: None,
+            extra_init_code_hashes: vec![],
+            mode: Mode::default(),
+            salt_mode: SaltMode::default(),
+            salt_prefix: vec![],
+            platform: None,
+            gpu_device: 255,
+            extra_gpu_devices: vec![],
+            hybrid: false,
+            leading_zeroes_threshold: 3,
+            total_zeroes_threshold: 5,
+            endpoint_urls: vec![],
+            ws_endpoint: None,
+            spool: PathBuf::from(SPOOL_FILE),
+            batch_size: 1,
+            batch_interval_secs: 5,
+            headers: vec![],
+            payload_format: PayloadFormat::default(),
+            payload_template: None,
+            job_id: None,
+            worker_id: 0,
+            worker_count: 1,
+            heartbeat_url: None,
+            heartbeat_interval_secs: 60,
+            status_addr: None,
+            output_file: None,
+            db: None,
+            leaderboard: None,
+            leaderboard_size: DEFAULT_LEADERBOARD_SIZE,
+            only_improvements: false,
+            current_best: None,
+            stop_at_score: None,
+            max_runtime_secs: None,
+            max_hashes: None,
+            reserved: None,
+            reserved_action: ReservedAction::default(),
+            nice: None,
+            poll_interval_ms: 0,
+            solutions_fraction: DEFAULT_SOLUTIONS_FRACTION,
+            verify_sample: DEFAULT_VERIFY_SAMPLE,
+            selftest: false,
+            nonce_start: None,
+            nonce_stride: 1,
+            histogram: false,
+            max_memory: None,
+            warm_start: vec![],
+            on_battery: BatteryAction::default(),
+            max_temp: None,
+            scoring: None,
+            checksum_prefix: None,
+            pattern: None,
+            #[cfg(feature = "wasm")]
+            scorer: None,
+            work_size: DEFAULT_WORK_SIZE,
+            auto_tune: false,
+            deterministic: false,
+            start_segment: 0,
+            checkpoint: PathBuf::from(CHECKPOINT_FILE),
+            checkpoint_interval_secs: 60,
+            resume: false,
+            rpc_url: None,
+            rpc_verify: DEFAULT_RPC_VERIFY.to_string(),
+            factory_abi: None,
+            #[cfg(feature = "deploy")]
+            deploy: None,
+            tui: false,
+        }
+    }
 }
 
-/// Validate the provided arguments and construct the Config struct.
 impl Config {
+    /// Validate the provided arguments and construct the Config struct, from
+    /// the positional arguments alone (no flags are understood).
+    #[deprecated(note = "parse the arguments with `cli::Cli` and use `MineArgs::into_config`")]
     pub fn new(mut args: std::env::Args) -> Result<Self, &'static str> {
         // get args, skipping first arg (program name)
         args.next();
 
         // Get the required endpoint URL
-        let Some(endpoint_url_string) = args.next() else {
+        let Some(endpoint_url) = args.next() else {
             return Err("didn't get an endpoint_url argument");
         };
-        let endpoint_url = Some(endpoint_url_string);
 
         let Some(factory_address_string) = args.next() else {
             return Err("didn't get a factory_address argument");
@@ -126,15 +494,236 @@ impl Config {
         }
 
         Ok(Self {
-            factory_address,
-            calling_address,
+            factory_addresses: vec![factory_address],
+            calling_addresses: vec![calling_address],
             init_code_hash,
             gpu_device,
             leading_zeroes_threshold,
             total_zeroes_threshold,
-            endpoint_url,
+            endpoint_urls: vec![endpoint_url],
+            ..Self::default()
         })
     }
+
+    /// Sanity check the search parameters before any work is done. Values that
+    /// cannot possibly be intended are rejected outright, while values that are
+    /// merely suspicious are returned as warnings for the caller to surface.
+    pub fn validate(&self) -> Result<Vec<&'static str>, &'static str> {
+        if self.factory_addresses.is_empty() {
+            return Err("no factory address given");
+        }
+        if self.factory_addresses.contains(&[0; 20]) {
+            return Err("factory address is the zero address");
+        }
+        if self.salt_mode == SaltMode::Caller && self.calling_addresses.is_empty() {
+            return Err("no calling address given");
+        }
+        if self.salt_mode == SaltMode::Free && !self.calling_addresses.is_empty() {
+            return Err("free salts do not start with a calling address");
+        }
+        if self.salt_mode == SaltMode::Free && !self.warm_start.is_empty() {
+            return Err("free salts cannot be warm started");
+        }
+        if self.mode == Mode::Create2
+            && (self.init_code_hash == EMPTY_CODE_HASH
+                || self.extra_init_code_hashes.contains(&EMPTY_CODE_HASH))
+        {
+            return Err("initialization code hash is the hash of empty bytes");
+        }
+        if self.mode == Mode::Create3 && !self.extra_init_code_hashes.is_empty() {
+            return Err("CREATE3 addresses do not depend on the initialization code hash");
+        }
+        if !self.extra_gpu_devices.is_empty()
+            && (self.gpu_device == 255 || self.extra_gpu_devices.contains(&255))
+        {
+            return Err("the CPU (device 255) cannot be combined with other devices");
+        }
+        if has_duplicates(
+            &[self.gpu_device]
+                .into_iter()
+                .chain(self.extra_gpu_devices.iter().copied())
+                .collect::<Vec<_>>(),
+        ) {
+            return Err("the same device is given more than once");
+        }
+        if self
+            .scoring
+            .is_some_and(|s| !(1..=15).contains(&s.target_nibble))
+        {
+            return Err("scoring target nibble must be between 1 and 15");
+        }
+        if self.checksum_prefix.as_ref().is_some_and(|prefix| {
+            prefix.len() > 40 || !prefix.chars().all(|c| c.is_ascii_hexdigit())
+        }) {
+            return Err("checksum prefix must be at most 40 hex digits");
+        }
+        if self.pattern.is_some_and(|pattern| pattern.fixed() == 0) {
+            return Err("pattern does not fix any nibble");
+        }
+        if self.work_size < MIN_WORK_SIZE {
+            return Err("work size must be at least 0x10000");
+        }
+        if self.nonce_stride == 0 {
+            return Err("nonce stride must be at least one");
+        }
+        if self.ws_endpoint.is_some() && self.endpoint_urls.is_empty() {
+            return Err("a WebSocket endpoint needs an HTTP endpoint to fall back on");
+        }
+        if self.payload_format == PayloadFormat::Custom && self.payload_template.is_none() {
+            return Err("a custom payload format needs a payload template");
+        }
+        if self.worker_id >= self.worker_count {
+            return Err("worker ID must be less than the worker count");
+        }
+        if self.deterministic && self.auto_tune {
+            return Err("a deterministic search cannot be auto-tuned");
+        }
+        if self.deterministic && !self.warm_start.is_empty() {
+            return Err("a deterministic search cannot be warm started");
+        }
+        if self.resume && !self.deterministic {
+            return Err("only a deterministic search can be resumed");
+        }
+        if self.rpc_url.is_some() && !matches!(rpc::arguments(&self.rpc_verify), Some(1 | 2)) {
+            return Err(
+                "the verification view must take the salt (and the init code hash) as bytes32",
+            );
+        }
+        #[cfg(feature = "deploy")]
+        if self.deploy.is_some()
+            && (self.rpc_url.is_none() || self.stop_at_score.is_none() || self.init_code.is_none())
+        {
+            return Err(
+                "deploying needs a node to send to, a target score to stop at and the init code",
+            );
+        }
+        #[cfg(feature = "deploy")]
+        if self.deploy.is_some() && !self.extra_init_code_hashes.is_empty() {
+            return Err("deploying only applies to a single initialization code hash");
+        }
+        let max_nonce = if self.gpu_device == 255 {
+            MAX_INCREMENTER
+        } else {
+            u32::MAX as u64
+        };
+        if self.nonce_start.unwrap_or(0) > max_nonce || self.nonce_stride > max_nonce {
+            return Err("nonce start and stride must fit in the nonce segment");
+        }
+        let (segment_len, shares) = if self.gpu_device == 255 {
+            (6, self.worker_count as u64)
+        } else {
+            (4, self.worker_count as u64 * local_workers(self) as u64)
+        };
+        if self.hybrid && self.deterministic {
+            return Err("a deterministic search cannot also be hybrid");
+        }
+        if self.salt_prefix.len() > segment_len {
+            return Err("salt prefix must be at most 4 bytes in GPU mode, or 6 on the CPU");
+        }
+        if (1u64 << (8 * (segment_len - self.salt_prefix.len()))) < shares {
+            return Err("salt prefix leaves too few segments to share between the workers");
+        }
+
+        let mut warnings = vec![];
+        if self.mode == Mode::Create2 && self.init_code_hash == [0; 32] {
+            warnings.push("initialization code hash is all zeroes");
+        }
+        if self.mode == Mode::Create3 && self.init_code_hash != [0; 32] {
+            warnings.push("the initialization code hash does not affect CREATE3 addresses");
+        }
+        if self.calling_addresses.contains(&[0; 20]) {
+            warnings
+                .push("caller is the zero address; salts will not be protected from frontrunning");
+        }
+        if self
+            .factory_addresses
+            .iter()
+            .any(|factory| self.calling_addresses.contains(factory))
+        {
+            warnings.push("caller is the same address as the factory");
+        }
+        if has_duplicates(&self.calling_addresses) {
+            warnings.push("the same caller is given more than once");
+        }
+        if has_duplicates(&self.factory_addresses) {
+            warnings.push("the same factory is given more than once");
+        }
+        if has_duplicates(&mode::create2_hashes(self)) {
+            warnings.push("the same initialization code hash is given more than once");
+        }
+        if self.pattern.is_some() && self.scoring.is_some_and(|s| s.min_score != 0) {
+            warnings.push("the minimum score does not apply with a pattern");
+        }
+        if self.gpu_device == 255 && self.hybrid {
+            warnings.push("hybrid mining only applies in GPU mode");
+        }
+        if self.gpu_device == 255 && !self.warm_start.is_empty() {
+            warnings.push("warm start only applies in GPU mode");
+        }
+        if self.gpu_device == 255 && self.platform.is_some() {
+            warnings.push("the OpenCL platform only applies in GPU mode");
+        }
+        if self.gpu_device == 255 && self.tui {
+            warnings.push("the dashboard only applies in GPU mode");
+        }
+        if self.gpu_device == 255 && self.auto_tune {
+            warnings.push("auto-tuning only applies in GPU mode");
+        }
+        if self.gpu_device == 255 && self.selftest {
+            warnings.push("the self-test only applies in GPU mode");
+        }
+        if self.gpu_device == 255 && self.max_temp.is_some() {
+            warnings.push("the maximum temperature only applies in GPU mode");
+        }
+        if self.payload_template.is_some() && self.payload_format != PayloadFormat::Custom {
+            warnings.push("the payload template only applies with the custom payload format");
+        }
+        if self.payload_format != PayloadFormat::Default && self.endpoint_urls.is_empty() {
+            warnings.push("the payload format has no effect without an endpoint");
+        }
+        if self.only_improvements && self.endpoint_urls.is_empty() {
+            warnings.push("only submitting improvements has no effect without an endpoint");
+        }
+        if !self.warm_start.is_empty()
+            && !self
+                .warm_start
+                .iter()
+                .any(|salt| self.calling_addresses.iter().any(|c| salt[..20] == c[..]))
+        {
+            warnings.push("no warm start salt is bound to one of the callers");
+        }
+        let min_score = self.scoring.map_or(0, |s| s.min_score);
+        if self.gpu_device != 255
+            && min_score == 0
+            && self.leading_zeroes_threshold < 2
+            && self.total_zeroes_threshold < 4
+        {
+            warnings.push("thresholds are low enough to flood the output with results");
+        }
+        if self
+            .scoring
+            .is_some_and(|s| min_score != 0 && min_score <= 2 * s.leading_weight)
+        {
+            warnings.push("minimum score is low enough to flood the output with results");
+        }
+
+        Ok(warnings)
+    }
+
+    /// Why the run should stop after `runtime` seconds and `hashes` attempts,
+    /// if either exceeds its budget.
+    fn budget_spent(&self, runtime: f64, hashes: u128) -> Option<&'static str> {
+        if self
+            .max_runtime_secs
+            .is_some_and(|max| runtime >= max as f64)
+        {
+            return Some("reached the maximum runtime");
+        }
+        if self.max_hashes.is_some_and(|max| hashes >= max) {
+            return Some("reached the maximum number of attempts");
+        }
+        None
+    }
 }
 
 /// Given a Config object with a factory address, a caller address, and a
@@ -143,129 +732,351 @@ impl Config {
 /// address via CREATE2.
 ///
 /// The 32-byte salt is constructed as follows:
-///   - the 20-byte calling address (to prevent frontrunning)
+///   - the 20-byte calling address (to prevent frontrunning), alternating
+///     between the configured callers from one nonce to the next
 ///   - a random 6-byte segment (to prevent collisions with other runs)
-///   - a 6-byte nonce segment (incrementally stepped through during the run)
+///   - a 6-byte nonce segment (incrementally stepped through during the run,
+///     from the configured nonce start by the configured stride)
 ///
 /// When a salt that will result in the creation of a gas-efficient contract
 /// address is found, it will be appended to `efficient_addresses.txt` along
 /// with the resultant address and the "value" (i.e. approximate rarity) of the
 /// resultant address.
+///
+/// On SIGINT or SIGTERM, the hashing threads stop, and a summary of the run is
+/// printed once any address being recorded has been written.
 pub fn cpu(config: Config) -> Result<(), Box<dyn Error>> {
-    // (create if necessary) and open a file where found salts will be written
-    let file = output_file();
+    cpu_until(config, shutdown::Shutdown::install()?)
+}
+
+/// Search on the CPU as [`cpu`] does until `shutdown` is requested.
+fn cpu_until(config: Config, shutdown: shutdown::Shutdown) -> Result<(), Box<dyn Error>> {
+    // lower the priority of the process before any worker threads are spawned
+    if let Some(nice) = config.nice {
+        set_niceness(nice)?;
+    }
+
+    // record, post and report on solutions as they are found
+    let recorder = record::Recorder::new(&config, shutdown.clone())?;
+
+    let start_time = Instant::now();
+    let hashes = AtomicU64::new(0);
+    // the first result that could not be recorded, which stops the run
+    let failed = Mutex::new(None);
+    cpu_search(
+        &config,
+        &recorder.control(),
+        &shutdown,
+        &hashes,
+        recorder.checkpoint().position(checkpoint::CPU_STREAM),
+        || {
+            let total = hashes.load(Ordering::Relaxed) as u128;
+            recorder.progress(start_time.elapsed().as_secs_f64(), &[(255, total)]);
+        },
+        |solution| {
+            let mut failed = failed.lock().unwrap();
+            if failed.is_none() {
+                if let Err(error) = recorder.record(&solution) {
+                    shutdown.request_because("a result could not be recorded");
+                    *failed = Some(error);
+                }
+            }
+        },
+        |position| recorder.advance(checkpoint::CPU_STREAM.to_string(), position),
+    );
+
+    recorder.finish(
+        start_time.elapsed().as_secs_f64(),
+        hashes.into_inner() as u128,
+    )?;
+    match failed.into_inner().unwrap() {
+        Some(error) => Err(format!("could not record a result: {error}").into()),
+        None => Ok(()),
+    }
+}
+
+/// Search on the CPU until `shutdown` is requested, counting attempts in
+/// `hashes` and passing each solution to `found`. `progress` is called every
+/// so often as the count is updated. A deterministic search carries on from
+/// `start`, and passes its position to `advance` whenever everything before it
+/// has been searched. A change to the minimum score through `control` applies
+/// from the next address on.
+#[allow(clippy::too_many_arguments)]
+fn cpu_search(
+    config: &Config,
+    control: &Control,
+    shutdown: &shutdown::Shutdown,
+    hashes: &AtomicU64,
+    start: checkpoint::Position,
+    progress: impl Fn() + Sync,
+    found: impl Fn(Solution) + Sync,
+    advance: impl Fn(checkpoint::Position),
+) {
+    // hold off while on battery power if so configured
+    let power_gate = power::PowerGate::spawn(config.on_battery);
 
     // create object for computing rewards (relative rarity) for a given address
     let rewards = Reward::new();
 
-    // Create reqwest client if endpoint is provided
-    let client = config.endpoint_url.as_ref().map(|_| Client::new());
+    // the nonces to step through for each random segment
+    let nonce_start = config.nonce_start.unwrap_or(0);
+    let nonce_count = (MAX_INCREMENTER - nonce_start).div_ceil(config.nonce_stride);
+
+    // the first 20 bytes of the salts for each caller
+    let heads = salt::heads(config);
+
+    // the init code hashes that end the preimages
+    let create2_hashes = mode::create2_hashes(config);
+
+    // begin searching for addresses until asked to stop
+    let mut position = start;
+    while !shutdown.requested() {
+        // the segment is shared by every factory and caller, and is random
+        // unless the segments of this share are being walked in order
+        let segment = match config.hybrid {
+            // in hybrid mode, start with a message in the share of the CPU
+            // among the devices, so that no GPU ever searches the same salts
+            true => {
+                let workers = local_workers(config) as u64;
+                let share = config.worker_id as u64 * workers + workers - 1;
+                let shares = config.worker_count as u64 * workers;
+                let message = salt_segment::<4>(&config.salt_prefix, None, share, shares);
+                let mut segment = FixedBytes::<6>::random();
+                segment[..4].copy_from_slice(&message[..]);
+                segment
+            }
+            false => salt_segment::<6>(
+                &config.salt_prefix,
+                config.deterministic.then_some(position.segment),
+                config.worker_id as u64,
+                config.worker_count as u64,
+            ),
+        };
+        debug!(segment = %hex::encode(segment), "starting salt segment");
 
-    // begin searching for addresses
-    loop {
         // header: 0xff ++ factory ++ caller ++ salt_random_segment (47 bytes)
-        let mut header = [0; 47];
-        header[0] = CONTROL_CHARACTER;
-        header[1..21].copy_from_slice(&config.factory_address);
-        header[21..41].copy_from_slice(&config.calling_address);
-        header[41..].copy_from_slice(&FixedBytes::<6>::random()[..]);
-
-        // create new hash object
-        let mut hash_header = Keccak::v256();
-
-        // update hash with header
-        hash_header.update(&header);
-
-        // iterate over a 6-byte nonce and compute each address
-        (0..MAX_INCREMENTER)
-            .into_par_iter() // parallelization
-            .for_each(|salt| {
-                let salt = salt.to_le_bytes();
-                let salt_incremented_segment = &salt[..6];
-
-                // clone the partially-hashed object
-                let mut hash = hash_header.clone();
-
-                // update with body and footer (total: 38 bytes)
-                hash.update(salt_incremented_segment);
-                hash.update(&config.init_code_hash);
-
-                // hash the payload and get the result
-                let mut res: [u8; 32] = [0; 32];
-                hash.finalize(&mut res);
-
-                // get the address that results from the hash
-                let address = <&Address>::try_from(&res[12..]).unwrap();
-
-                // count total and leading zero bytes
-                let mut total = 0;
-                let mut leading = 21;
-                for (i, &b) in address.iter().enumerate() {
-                    if b == 0 {
-                        total += 1;
-                    } else if leading == 21 {
-                        // set leading on finding non-zero byte
-                        leading = i;
-                    }
-                }
+        let headers: Vec<([u8; 47], Keccak)> = config
+            .factory_addresses
+            .iter()
+            .flat_map(|factory| heads.iter().map(move |c| (factory, c)))
+            .map(|(factory, caller)| {
+                let mut header = [0; 47];
+                header[0] = CONTROL_CHARACTER;
+                header[1..21].copy_from_slice(factory);
+                header[21..41].copy_from_slice(caller);
+                header[41..].copy_from_slice(&segment[..]);
+
+                // create new hash object and update it with the header
+                let mut hash_header = Keccak::v256();
+                hash_header.update(&header);
+
+                (header, hash_header)
+            })
+            .collect();
+        let targets = headers.len() as u64;
+
+        // iterate over a 6-byte nonce for each factory and caller in turn and
+        // compute each address, a chunk at a time when checkpointing
+        let count = nonce_count * targets;
+        let chunk = if config.deterministic {
+            CHECKPOINT_CHUNK
+        } else {
+            count
+        };
+        let mut offset = position.offset;
+        while offset < count && !shutdown.requested() {
+            let end = (offset + chunk).min(count);
+            (offset..end)
+                .into_par_iter() // parallelization
+                .take_any_while(|_| !shutdown.requested())
+                .map(|i| {
+                    let (header, hash_header) = &headers[(i % targets) as usize];
+                    (
+                        header,
+                        hash_header,
+                        nonce_start + i / targets * config.nonce_stride,
+                    )
+                })
+                .for_each_init(
+                    || (Tally::new(hashes), control.generation(), control.tuning()),
+                    |(tally, generation, tuning), (header, hash_header, salt)| {
+                        power_gate.wait();
+                        if tally.add() {
+                            progress();
+                        }
+
+                        // pick up any change to the parameters of the run
+                        if control.generation() != *generation {
+                            *generation = control.generation();
+                            *tuning = control.tuning();
+                        }
+
+                        let factory = &header[1..21];
+                        let caller = &header[21..41];
+                        let salt = salt.to_le_bytes();
+                        let salt_incremented_segment = &salt[..6];
+
+                        // clone the partially-hashed object and update it
+                        // with the rest of the salt
+                        let mut salted = hash_header.clone();
+                        salted.update(salt_incremented_segment);
+
+                        // then try the salt with each init code hash (the
+                        // footer; total: 38 bytes)
+                        for create2_hash in &create2_hashes {
+                            let mut hash = salted.clone();
+                            hash.update(create2_hash);
+
+                            // hash the payload and get the result
+                            let mut res: [u8; 32] = [0; 32];
+                            hash.finalize(&mut res);
+
+                            // get the address that results from the hash (by way
+                            // of the proxy with CREATE3)
+                            let address = &mode::deployed(config, Address::from_slice(&res[12..]));
+
+                            // count total and leading zero bytes
+                            let (leading, total) = zero_bytes(address);
+
+                            // with a pattern, only proceed if the address matches it, or
+                            // else with a minimum score if the address reaches it
+                            let min_score = tuning.min_score;
+                            let pattern_score = config.scoring.map(|s| score_address(&s, address));
+                            match config.pattern {
+                                Some(pattern) if !pattern.matches(address) => continue,
+                                None if min_score != 0 && pattern_score.unwrap() < min_score => {
+                                    continue
+                                }
+                                _ => {}
+                            }
+
+                            // otherwise only proceed if there are at least three zero bytes
+                            let zeroes = config.pattern.is_none() && min_score == 0;
+                            if zeroes && total < 3 {
+                                continue;
+                            }
+
+                            // look up the reward amount
+                            let key = leading * 20 + total;
+                            let reward_amount = rewards.get(&key);
+
+                            // only proceed if an efficient address has been found
+                            if zeroes && reward_amount.is_none() {
+                                continue;
+                            }
+
+                            // skip addresses that do not start with the checksum
+                            // prefix
+                            if config
+                                .checksum_prefix
+                                .as_ref()
+                                .is_some_and(|prefix| !matches_checksum(prefix, address))
+                            {
+                                continue;
+                            }
+
+                            // skip or flag addresses in the reserved set
+                            let reserved = config
+                                .reserved
+                                .as_ref()
+                                .is_some_and(|r| r.contains(address));
+                            if reserved && config.reserved_action == ReservedAction::Skip {
+                                continue;
+                            }
+
+                            // score the address with the WASM scorer if one is loaded
+                            let Some(pattern_score) =
+                                reported_score(config, address, pattern_score)
+                            else {
+                                continue;
+                            };
+
+                            // get the full salt used to create the address
+                            let mut full_salt = [0; 32];
+                            full_salt[..26].copy_from_slice(&header[21..]);
+                            full_salt[26..].copy_from_slice(salt_incremented_segment);
+
+                            found(Solution {
+                                salt: full_salt.into(),
+                                address: *address,
+                                factory: Address::from_slice(factory),
+                                caller: (config.salt_mode == SaltMode::Caller)
+                                    .then(|| Address::from_slice(caller)),
+                                init_code_hash: (create2_hashes.len() > 1)
+                                    .then(|| create2_hash.into()),
+                                reward: reward_amount.unwrap_or("0").parse().unwrap(),
+                                score: pattern_score,
+                                reserved,
+                                device: None,
+                            });
+                        }
+                    },
+                );
 
-                // only proceed if there are at least three zero bytes
-                if total < 3 {
-                    return;
-                }
+            // a chunk cut short by a shutdown is searched again on resuming
+            if shutdown.requested() {
+                break;
+            }
+            offset = end;
+            if config.deterministic {
+                advance(checkpoint::Position {
+                    segment: position.segment,
+                    offset,
+                });
+            }
+        }
+        position = checkpoint::Position {
+            segment: position.segment + 1,
+            offset: 0,
+        };
+    }
+}
 
-                // look up the reward amount
-                let key = leading * 20 + total;
-                let reward_amount = rewards.get(&key);
+/// Counts the hashes done by a rayon job, adding them to the shared total in
+/// batches (and when dropped) to keep the shared counter off the hot path.
+struct Tally<'a> {
+    local: u64,
+    total: &'a AtomicU64,
+}
 
-                // only proceed if an efficient address has been found
-                if reward_amount.is_none() {
-                    return;
-                }
+impl<'a> Tally<'a> {
+    fn new(total: &'a AtomicU64) -> Self {
+        Self { local: 0, total }
+    }
 
-                // get the full salt used to create the address
-                let header_hex_string = hex::encode(header);
-                let body_hex_string = hex::encode(salt_incremented_segment);
-                let full_salt = format!("0x{}{}", &header_hex_string[42..], &body_hex_string);
+    /// Count a hash, returning whether the shared total was just updated.
+    fn add(&mut self) -> bool {
+        self.local += 1;
+        if self.local == 1 << 16 {
+            self.total.fetch_add(self.local, Ordering::Relaxed);
+            self.local = 0;
+            return true;
+        }
+        false
+    }
+}
 
-                // display the salt and the address.
-                let output = format!(
-                    "{full_salt} => {address} => {}",
-                    reward_amount.unwrap_or("0")
-                );
-                println!("{output}");
-
-                // create a lock on the file before writing
-                file.lock_exclusive().expect("Couldn't lock file.");
-
-                // write the result to file
-                writeln!(&file, "{output}")
-                    .expect("Couldn't write to `efficient_addresses.txt` file.");
-
-                // release the file lock
-                file.unlock().expect("Couldn't unlock file.");
-
-                // Send result to configured endpoint if available
-                if let (Some(client), Some(endpoint_url)) = (&client, &config.endpoint_url) {
-                    let score = reward_amount.unwrap_or("0").to_string();
-
-                    // Try to send the result to the endpoint
-                    let _ = client
-                        .post(endpoint_url)
-                        .json(&json!({
-                            "salt": format!("0x{}{}{}",
-                                hex::encode(config.calling_address),
-                                hex::encode(&header[41..]),
-                                hex::encode(salt_incremented_segment)),
-                            "address": address.to_string(),
-                            "score": score
-                        }))
-                        .send();
-                }
-            });
+impl Drop for Tally<'_> {
+    fn drop(&mut self) {
+        self.total.fetch_add(self.local, Ordering::Relaxed);
     }
 }
 
+/// Print the totals of a run that has been asked to stop.
+fn print_summary(runtime: f64, hashes: u128, found: u64, best: Option<&str>) {
+    let seconds = runtime as u64;
+    println!(
+        "stopped after {}:{:02}:{:02}: {} attempts ({:.2} million per second), {} found",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        hashes.separated_string(),
+        hashes as f64 / 1_000_000.0 / runtime.max(1.0),
+        found
+    );
+    println!("best: {}", best.unwrap_or("none"));
+}
+
 /// Given a Config object with a factory address, a caller address, a keccak-256
 /// hash of the contract initialization code, and a device ID, search for salts
 /// using OpenCL that will enable the factory contract to deploy a contract to a
@@ -275,7 +1086,8 @@ pub fn cpu(config: Config) -> Result<(), Box<dyn Error>> {
 /// are three leading zeroes or five total zeroes.
 ///
 /// The 32-byte salt is constructed as follows:
-///   - the 20-byte calling address (to prevent frontrunning)
+///   - the 20-byte calling address (to prevent frontrunning), taking each of
+///     the configured callers in turn, each with its own compiled program
 ///   - a random 4-byte segment (to prevent collisions with other runs)
 ///   - a 4-byte segment unique to each work group running in parallel
 ///   - a 4-byte nonce segment (incrementally stepped through during the run)
@@ -285,56 +1097,104 @@ pub fn cpu(config: Config) -> Result<(), Box<dyn Error>> {
 /// with the resultant address and the "value" (i.e. approximate rarity) of the
 /// resultant address.
 ///
+/// Each device in the Config object is driven by its own worker thread, and
+/// the random segments are partitioned between workers (by their value modulo
+/// the number of devices, within the share of this process in a fleet) so that
+/// they never overlap. Results and attempt rates from every device are
+/// reported from the calling thread.
+///
+/// With a warm start, every other segment reuses the random segment of an
+/// earlier salt for the same caller, with the nonce starting just past it.
+///
+/// If a device runs out of resources, its work size is halved and the
+/// dispatch retried until it settles on a size that the device tolerates.
+///
+/// On SIGINT or SIGTERM, each worker finishes its dispatch and hands over its
+/// solutions, which are recorded and submitted before a summary of the run is
+/// printed.
+///
 /// This method is still highly experimental and could almost certainly use
 /// further optimization - contributions are more than welcome!
 pub fn gpu(config: Config) -> ocl::Result<()> {
-    println!(
-        "Setting up experimental OpenCL miner using device {}...",
-        config.gpu_device
-    );
-
-    // (create if necessary) and open a file where found salts will be written
-    let file = output_file();
-
-    // create object for computing rewards (relative rarity) for a given address
-    let rewards = Reward::new();
-
-    // Create reqwest client if endpoint is provided
-    let client = config.endpoint_url.as_ref().map(|_| Client::new());
+    gpu_until(config, shutdown::Shutdown::install()?)
+}
 
-    // track how many addresses have been found and information about them
-    let mut found: u64 = 0;
-    let mut found_list: Vec<String> = vec![];
+/// Search on the GPU as [`gpu`] does until `shutdown` is requested.
+fn gpu_until(config: Config, shutdown: shutdown::Shutdown) -> ocl::Result<()> {
+    let devices: Vec<u8> = [config.gpu_device]
+        .into_iter()
+        .chain(config.extra_gpu_devices.iter().copied())
+        .collect();
+    info!(
+        "Setting up experimental OpenCL miner using device(s) {}...",
+        devices
+            .iter()
+            .map(u8::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
 
-    // set up a controller for terminal output
-    let term = Term::stdout();
+    // make sure that every device derives the same addresses as the host
+    if config.selftest {
+        for &device in &devices {
+            selftest(config.platform, device, DEFAULT_SELFTEST_COUNT)
+                .map_err(|e| format!("self-test of device {device} failed: {e}"))?;
+        }
+    }
 
-    // set up a platform to use
-    let platform = Platform::new(ocl::core::default_platform()?);
+    // lower the priority of the host thread and the workers it spawns
+    if let Some(nice) = config.nice {
+        set_niceness(nice).map_err(|e| e.to_string())?;
+    }
 
-    // set up the device to use
-    let device = Device::by_idx_wrap(platform, config.gpu_device as usize)?;
+    // hold off while on battery power if so configured
+    let power_gate = power::PowerGate::spawn(config.on_battery);
 
-    // set up the context to use
-    let context = Context::builder()
-        .platform(platform)
-        .devices(device)
-        .build()?;
+    // create object for computing rewards (relative rarity) for a given address
+    let rewards = Reward::new();
 
-    // set up the program to use
-    let program = Program::builder()
-        .devices(device)
-        .src(mk_kernel_src(&config))
-        .build(&context)?;
+    // record, post and report on solutions as they are found
+    let config = Arc::new(config);
+    let recorder = record::Recorder::new(&config, shutdown.clone()).map_err(|e| e.to_string())?;
 
-    // set up the queue to use
-    let queue = Queue::new(&context, device, None)?;
+    // track how many solutions did not fit in the buffer
+    let mut dropped: u64 = 0;
 
-    // set up the "proqueue" (or amalgamation of various elements) to use
-    let ocl_pq = ProQue::new(context, queue, program, Some(WORK_SIZE));
+    // track how many solutions have been checked against the host
+    let mut checked: u64 = 0;
+    let mut found_list: VecDeque<String> = VecDeque::with_capacity(RECENT_FOUND);
 
-    // create a random number generator
-    let mut rng = thread_rng();
+    // set up a controller for terminal output, or the dashboard in its place
+    let term = Term::stdout();
+    let mut dashboard = config.tui.then(tui::Dashboard::new).transpose()?;
+
+    // the factory and caller searched by each program, in program order
+    let targets = targets(&config);
+
+    // counts of candidates by leading zero nibbles over the whole run
+    let mut histogram = [0u64; HISTOGRAM_BUCKETS];
+
+    // start a worker thread for each device (and the CPU, in hybrid mode),
+    // reporting back over a channel
+    let mut workers: Vec<WorkerStatus> = devices
+        .iter()
+        .copied()
+        .chain(config.hybrid.then_some(255))
+        .map(|device| WorkerStatus {
+            device,
+            ..Default::default()
+        })
+        .collect();
+    let control = recorder.control();
+    let received = spawn_gpu_workers(
+        &config,
+        &control,
+        &devices,
+        &recorder.checkpoint(),
+        &power_gate,
+        &shutdown,
+    );
+    let mut running = workers.len();
 
     // determine the start time
     let start_time: f64 = SystemTime::now()
@@ -342,191 +1202,432 @@ pub fn gpu(config: Config) -> ocl::Result<()> {
         .unwrap()
         .as_secs_f64();
 
-    // set up variables for tracking performance
-    let mut rate: f64 = 0.0;
-    let mut cumulative_nonce: u64 = 0;
-
     // the previous timestamp of printing to the terminal
     let mut previous_time: f64 = 0.0;
 
-    // the last work duration in milliseconds
-    let mut work_duration_millis: u64 = 0;
-
-    // begin searching for addresses
+    // handle events from the workers, refreshing the terminal at most once a
+    // second (or the dashboard after every dispatch)
     loop {
-        // construct the 4-byte message to hash, leaving last 8 of salt empty
-        let salt = FixedBytes::<4>::random();
-
-        // build a corresponding buffer for passing the message to the kernel
-        let message_buffer = Buffer::builder()
-            .queue(ocl_pq.queue().clone())
-            .flags(MemFlags::new().read_only())
-            .len(4)
-            .copy_host_slice(&salt[..])
-            .build()?;
+        let mut dispatched = false;
+        let mut solutions = vec![];
+        match received.recv_timeout(Duration::from_secs(1)) {
+            Ok(GpuEvent::Started { worker, name }) => {
+                let status = &mut workers[worker];
+                recorder.name_device(status.device, &name);
+                status.name = name;
+            }
+            Ok(GpuEvent::Dispatched {
+                worker,
+                work_size,
+                search_space,
+                histogram: dispatch_histogram,
+                advance,
+                rolled_over,
+            }) => {
+                let status = &mut workers[worker];
+                if rolled_over {
+                    recorder.roll_over(status.device);
+                }
+                status.dispatches += 1;
+                status.hashes += work_size as u128;
+                status.work_size = work_size;
+                status.search_space = search_space;
+                for (total, count) in histogram.iter_mut().zip(dispatch_histogram) {
+                    *total += count as u64;
+                }
+                if let Some((stream, position)) = advance {
+                    recorder.advance(stream, position);
+                }
+                dispatched = true;
+            }
+            Ok(GpuEvent::Sampled { worker, sample }) => {
+                let status = &mut workers[worker];
+                recorder.sample_device(status.device, sample);
+                status.sample = Some(sample);
+            }
+            Ok(GpuEvent::Solved(solved)) => {
+                // account for any solutions that did not fit in the buffer
+                dropped += (solved.count - solved.solutions.len()) as u64;
+
+                let (found, mismatched, rejected) = gpu_solutions(
+                    &config,
+                    &rewards,
+                    &targets[solved.target],
+                    solved,
+                    &mut checked,
+                );
+                recorder.discard(mismatched, rejected);
+                solutions = found;
+            }
+            Ok(GpuEvent::Searched { worker, hashes }) => {
+                workers[worker].hashes = hashes;
+            }
+            Ok(GpuEvent::Found(solution)) => solutions.push(*solution),
+            Ok(GpuEvent::Stopped) => {
+                running -= 1;
+                if running == 0 {
+                    break;
+                }
+            }
+            Ok(GpuEvent::Failed { device, error }) => {
+                return Err(format!("device {device}: {error}").into());
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
 
-        // reset nonce & create a buffer to view it in little-endian
-        // for more uniformly distributed nonces, we shall initialize it to a random value
-        let mut nonce: [u32; 1] = rng.gen();
-        let mut view_buf = [0; 8];
+        // record what the GPUs and the CPU found, in the order it came in
+        for solution in solutions {
+            let (leading, total) = zero_bytes(&solution.address);
+            let mut flags = String::new();
+            if let Some(score) = solution.score {
+                write!(flags, " score {score}").unwrap();
+            }
+            if config.factory_addresses.len() > 1 {
+                write!(flags, " factory {}", solution.factory).unwrap();
+            }
+            if solution.reserved {
+                flags.push_str(" RESERVED");
+            }
+            if found_list.len() == RECENT_FOUND {
+                found_list.pop_front();
+            }
+            found_list.push_back(format!("{} ({leading} / {total}){flags}", solution.line()));
 
-        // build a corresponding buffer for passing the nonce to the kernel
-        let mut nonce_buffer = Buffer::builder()
-            .queue(ocl_pq.queue().clone())
-            .flags(MemFlags::new().read_only())
-            .len(1)
-            .copy_host_slice(&nonce)
-            .build()?;
+            recorder.record(&solution)?;
+        }
 
-        // establish a buffer for nonces that result in desired addresses
-        let mut solutions: Vec<u64> = vec![0; 1];
-        let solutions_buffer = Buffer::builder()
-            .queue(ocl_pq.queue().clone())
-            .flags(MemFlags::new().write_only())
-            .len(1)
-            .copy_host_slice(&solutions)
-            .build()?;
+        // calculate the current time
+        let current_time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs_f64();
+
+        // stop once the budget for the run is spent, and let a coordinator
+        // (or orchestrator) know how the run is going
+        let devices: Vec<(u8, u128)> = workers.iter().map(|w| (w.device, w.hashes)).collect();
+        recorder.progress(current_time - start_time, &devices);
+
+        // stop when asked to from the dashboard
+        if let Some(dashboard) = &mut dashboard {
+            if dashboard.quit_requested()? {
+                shutdown.request();
+            }
+        }
 
-        // repeatedly enqueue kernel to search for new addresses
-        loop {
-            // build the kernel and define the type of each buffer
-            let kern = ocl_pq
-                .kernel_builder("hashMessage")
-                .arg_named("message", None::<&Buffer<u8>>)
-                .arg_named("nonce", None::<&Buffer<u32>>)
-                .arg_named("solutions", None::<&Buffer<u64>>)
-                .build()?;
-
-            // set each buffer
-            kern.set_arg("message", Some(&message_buffer))?;
-            kern.set_arg("nonce", Some(&nonce_buffer))?;
-            kern.set_arg("solutions", &solutions_buffer)?;
-
-            // enqueue the kernel
-            unsafe { kern.enq()? };
-
-            // calculate the current time
-            let mut now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
-            let current_time = now.as_secs() as f64;
-
-            // we don't want to print too fast
-            let print_output = current_time - previous_time > 0.99;
-            previous_time = current_time;
-
-            // clear the terminal screen
-            if print_output {
-                term.clear_screen()?;
-
-                // get the total runtime and parse into hours : minutes : seconds
-                let total_runtime = current_time - start_time;
-                let total_runtime_hrs = total_runtime as u64 / 3600;
-                let total_runtime_mins = (total_runtime as u64 - total_runtime_hrs * 3600) / 60;
-                let total_runtime_secs = total_runtime
-                    - (total_runtime_hrs * 3600) as f64
-                    - (total_runtime_mins * 60) as f64;
-
-                // determine the number of attempts being made per second
-                let work_rate: u128 = WORK_FACTOR * cumulative_nonce as u128;
-                if total_runtime > 0.0 {
-                    rate = 1.0 / total_runtime;
-                }
+        // we don't want to print too fast
+        if current_time - previous_time < 0.99 && !(dashboard.is_some() && dispatched) {
+            continue;
+        }
+        previous_time = current_time;
+        let found = recorder.found();
+        let (mismatched, rejected) = (recorder.mismatched(), recorder.rejected());
+        let cumulative_hashes: u128 = workers.iter().map(|w| w.hashes).sum();
+        let tuning = control.tuning();
+        let estimate = probability::estimate(
+            &config,
+            &tuning,
+            recorder.best_rank(),
+            cumulative_hashes,
+            current_time - start_time,
+        );
+
+        // draw the dashboard instead of the plain output if enabled
+        if let Some(dashboard) = &mut dashboard {
+            let mut warnings = vec![];
+            if dropped != 0 {
+                warnings.push(format!("{dropped} solutions dropped (buffer overflowed)"));
+            }
+            if mismatched != 0 {
+                warnings.push(format!(
+                    "{mismatched} solutions discarded (device and host disagree)"
+                ));
+            }
+            if rejected != 0 {
+                warnings.push(format!("{rejected} solutions discarded (criteria not met)"));
+            }
+            dashboard.draw(&tui::Snapshot {
+                runtime: current_time - start_time,
+                workers: &workers,
+                found,
+                best: recorder.best().as_deref(),
+                estimate: estimate.map(|estimate| estimate.to_string()),
+                submission: recorder.submission().as_deref(),
+                warnings,
+                recent: &found_list,
+            })?;
+            continue;
+        }
 
-                // fill the buffer for viewing the properly-formatted nonce
-                LittleEndian::write_u64(&mut view_buf, (nonce[0] as u64) << 32);
+        // clear the terminal screen
+        term.clear_screen()?;
+
+        // get the total runtime and parse into hours : minutes : seconds
+        let total_runtime = current_time - start_time;
+        let total_runtime_hrs = total_runtime as u64 / 3600;
+        let total_runtime_mins = (total_runtime as u64 - total_runtime_hrs * 3600) / 60;
+        let total_runtime_secs =
+            total_runtime - (total_runtime_hrs * 3600) as f64 - (total_runtime_mins * 60) as f64;
+
+        // determine the number of attempts being made per second
+        let rate = |hashes: u128| hashes as f64 / 1_000_000.0 / total_runtime.max(1.0);
+        let cumulative_nonce: u64 = workers.iter().map(|w| w.dispatches).sum();
+        let work_size: u64 = workers.iter().map(|w| w.work_size as u64).sum();
+
+        // calculate the terminal height, defaulting to a height of ten rows
+        let height = terminal_size().map(|(_w, Height(h))| h).unwrap_or(10);
+
+        // display information about the total runtime and work size
+        term.write_line(&format!(
+            "total runtime: {}:{:02}:{:02} ({} cycles)\t\t\t\
+             work size per cycle: {}",
+            total_runtime_hrs,
+            total_runtime_mins,
+            total_runtime_secs,
+            cumulative_nonce,
+            work_size.separated_string(),
+        ))?;
+
+        // display information about the attempt rate and found solutions
+        term.write_line(&format!(
+            "rate: {:.2} million attempts per second\t\t\t\
+             total found this run: {}",
+            rate(cumulative_hashes),
+            found
+        ))?;
+
+        // display how long the next better (or target) score should take
+        if let Some(estimate) = estimate {
+            term.write_line(&format!("estimate: {estimate}"))?;
+        }
 
-                // calculate the terminal height, defaulting to a height of ten rows
-                let height = terminal_size().map(|(_w, Height(h))| h).unwrap_or(10);
+        // warn if the solutions buffer has been too small to hold every find
+        if dropped != 0 {
+            term.write_line(&format!(
+                "WARNING: {dropped} solutions dropped because the solutions buffer \
+                 overflowed; raise the thresholds or \
+                 --solutions-fraction"
+            ))?;
+        }
 
-                // display information about the total runtime and work size
-                term.write_line(&format!(
-                    "total runtime: {}:{:02}:{:02} ({} cycles)\t\t\t\
-                     work size per cycle: {}",
-                    total_runtime_hrs,
-                    total_runtime_mins,
-                    total_runtime_secs,
-                    cumulative_nonce,
-                    WORK_SIZE.separated_string(),
-                ))?;
+        // warn if the device has returned addresses that the host disagrees with
+        if mismatched != 0 {
+            term.write_line(&format!(
+                "WARNING: {mismatched} solutions discarded because the device derived \
+                 a different address than the host; run `create2crunch selftest`"
+            ))?;
+        }
 
-                // display information about the attempt rate and found solutions
-                term.write_line(&format!(
-                    "rate: {:.2} million attempts per second\t\t\t\
-                     total found this run: {}",
-                    work_rate as f64 * rate,
-                    found
-                ))?;
+        // warn if the device has returned addresses that it should not have
+        if rejected != 0 {
+            term.write_line(&format!(
+                "WARNING: {rejected} solutions discarded because they do not meet the \
+                 criteria of the search; run `create2crunch selftest`"
+            ))?;
+        }
 
-                // display information about the current search criteria
+        // display information about the current search criteria of each device,
+        // and the rate of the CPU in hybrid mode
+        for status in &workers {
+            if status.device == 255 {
                 term.write_line(&format!(
-                    "current search space: {}xxxxxxxx{:08x}\t\t\
-                     threshold: {} leading or {} total zeroes",
-                    hex::encode(salt),
-                    BigEndian::read_u64(&view_buf),
-                    config.leading_zeroes_threshold,
-                    config.total_zeroes_threshold
+                    "CPU ({:.2} million per second)",
+                    rate(status.hashes)
                 ))?;
-
-                // display recently found solutions based on terminal height
-                let rows = if height < 5 { 1 } else { height as usize - 4 };
-                let last_rows: Vec<String> = found_list.iter().cloned().rev().take(rows).collect();
-                let ordered: Vec<String> = last_rows.iter().cloned().rev().collect();
-                let recently_found = &ordered.join("\n");
-                term.write_line(recently_found)?;
+                continue;
             }
-
-            // increment the cumulative nonce (does not reset after a match)
-            cumulative_nonce += 1;
-
-            // record the start time of the work
-            let work_start_time_millis = now.as_secs() * 1000 + now.subsec_nanos() as u64 / 1000000;
-
-            // sleep for 98% of the previous work duration to conserve CPU
-            if work_duration_millis != 0 {
-                std::thread::sleep(std::time::Duration::from_millis(
-                    work_duration_millis * 980 / 1000,
-                ));
+            let mut device = match workers.len() {
+                1 => String::new(),
+                _ => format!(
+                    "device {} ({:.2} million per second) ",
+                    status.device,
+                    rate(status.hashes)
+                ),
+            };
+            if let Some(sample) = status.sample {
+                write!(device, "{sample} ").unwrap();
             }
+            term.write_line(&format!(
+                "{device}current search space: {}\t\t\
+                 threshold: {} leading or {} total zeroes",
+                status.search_space, tuning.leading_zeroes_threshold, tuning.total_zeroes_threshold
+            ))?;
+        }
 
-            // read the solutions from the device
-            solutions_buffer.read(&mut solutions).enq()?;
+        // display the near-miss histogram against its expected values
+        if config.histogram {
+            // every nonce is tried with each init code hash, and only the GPUs
+            // keep the histogram
+            let gpu_hashes: u128 = workers
+                .iter()
+                .filter(|w| w.device != 255)
+                .map(|w| w.hashes)
+                .sum();
+            let hashes = gpu_hashes as f64 * mode::create2_hashes(&config).len() as f64;
+            let mut line = String::from("leading zero nibbles (found / expected):");
+            for (nibbles, &count) in histogram.iter().enumerate().skip(HISTOGRAM_MIN_NIBBLES) {
+                let expected = hashes * 16f64.powi(-(nibbles as i32)) * 15.0 / 16.0;
+                if count == 0 && expected < 1.0 {
+                    break;
+                }
+                write!(line, "  {nibbles}: {count} / {expected:.1}").unwrap();
+            }
+            term.write_line(&line)?;
+        }
 
-            // record the end time of the work and compute how long the work took
-            now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
-            work_duration_millis = (now.as_secs() * 1000 + now.subsec_nanos() as u64 / 1000000)
-                - work_start_time_millis;
+        // display recently found solutions based on terminal height
+        let header = 3
+            + workers.len()
+            + (dropped != 0) as usize
+            + (mismatched != 0) as usize
+            + (rejected != 0) as usize
+            + config.histogram as usize;
+        let rows = (height as usize).saturating_sub(header).max(1);
+        let last_rows: Vec<String> = found_list.iter().rev().take(rows).cloned().collect();
+        let ordered: Vec<String> = last_rows.iter().cloned().rev().collect();
+        let recently_found = &ordered.join("\n");
+        term.write_line(recently_found)?;
+    }
 
-            // if at least one solution is found, end the loop
-            if solutions[0] != 0 {
-                break;
-            }
+    // every worker has stopped; restore the terminal and sum up the run
+    drop(dashboard);
+    let runtime = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs_f64()
+        - start_time;
+    recorder.finish(runtime, workers.iter().map(|w| w.hashes).sum())?;
 
-            // if no solution has yet been found, increment the nonce
-            nonce[0] += 1;
+    Ok(())
+}
 
-            // update the nonce buffer with the incremented nonce value
-            nonce_buffer = Buffer::builder()
-                .queue(ocl_pq.queue().clone())
-                .flags(MemFlags::new().read_write())
-                .len(1)
-                .copy_host_slice(&nonce)
-                .build()?;
-        }
+/// Start a worker thread for each device, which report back over the returned
+/// channel. In a deterministic search, each carries on from its positions in
+/// `start`. In hybrid mode, another worker searches on the CPU, after the
+/// devices.
+fn spawn_gpu_workers(
+    config: &Arc<Config>,
+    control: &Arc<Control>,
+    devices: &[u8],
+    start: &checkpoint::Checkpoint,
+    power_gate: &power::PowerGate,
+    shutdown: &shutdown::Shutdown,
+) -> mpsc::Receiver<GpuEvent> {
+    let (events, received) = mpsc::channel();
+    let targets = targets(config).len();
+    for (worker, &device) in devices.iter().enumerate() {
+        let config = config.clone();
+        let control = control.clone();
+        let events = events.clone();
+        let power_gate = power_gate.clone();
+        let shutdown = shutdown.clone();
+        let positions = (0..targets)
+            .map(|target| start.position(&checkpoint::gpu_stream(device, target)))
+            .collect();
+        std::thread::spawn(move || {
+            let result = gpu_worker(
+                &config,
+                &control,
+                device,
+                worker,
+                positions,
+                &power_gate,
+                &shutdown,
+                &events,
+            );
+            let _ = events.send(match result {
+                Ok(()) => GpuEvent::Stopped,
+                Err(error) => GpuEvent::Failed {
+                    device,
+                    error: error.to_string(),
+                },
+            });
+        });
+    }
 
-        // iterate over each solution, first converting to a fixed array
-        for &solution in &solutions {
-            if solution == 0 {
-                continue;
-            }
+    if config.hybrid {
+        let worker = devices.len();
+        let config = config.clone();
+        let control = control.clone();
+        let shutdown = shutdown.clone();
+        std::thread::spawn(move || {
+            let hashes = AtomicU64::new(0);
+            let searched = || {
+                let hashes = hashes.load(Ordering::Relaxed) as u128;
+                let _ = events.send(GpuEvent::Searched { worker, hashes });
+            };
+            cpu_search(
+                &config,
+                &control,
+                &shutdown,
+                &hashes,
+                checkpoint::Checkpoint::new(&config).position(checkpoint::CPU_STREAM),
+                searched,
+                |solution| {
+                    if events.send(GpuEvent::Found(Box::new(solution))).is_err() {
+                        shutdown.request();
+                    }
+                },
+                |_| {},
+            );
+            searched();
+            let _ = events.send(GpuEvent::Stopped);
+        });
+    }
+    received
+}
 
-            let solution = solution.to_le_bytes();
+/// The number of workers that share the salt segments of this process between
+/// them: one for each device in GPU mode, and the CPU in hybrid mode.
+fn local_workers(config: &Config) -> usize {
+    1 + config.extra_gpu_devices.len() + config.hybrid as usize
+}
 
+/// Turn the solutions that a device returned for a target (given as its factory
+/// index, factory and caller), each with the index of its init code hash, into
+/// [`Solution`]s, leaving out any that are reserved (if skipping those).
+///
+/// One in every `verify_sample` of them, counted by `checked`, is re-derived
+/// on the host first and discarded if the device got it wrong, and every one
+/// is discarded if its address does not meet the criteria that the device
+/// searched with. The numbers discarded for each reason are returned
+/// alongside.
+fn gpu_solutions(
+    config: &Config,
+    rewards: &Reward,
+    &(_, factory, caller): &(usize, [u8; 20], [u8; 20]),
+    solved: Solved,
+    checked: &mut u64,
+) -> (Vec<Solution>, u64, u64) {
+    let Solved {
+        device,
+        tuning,
+        message,
+        solutions,
+        ..
+    } = solved;
+    let mut mismatched = 0;
+    let mut rejected = 0;
+    let mut found = vec![];
+    let hashes = mode::create2_hashes(config);
+
+    // iterate over each solution
+    for (solution, address, hash) in solutions {
+        let solution = solution.to_le_bytes();
+
+        // get the address that the device derived from the solution
+        let address = Address::from(address);
+
+        // re-derive a sample of addresses on the host to catch kernel bugs
+        *checked += 1;
+        if config.verify_sample != 0 && *checked % config.verify_sample == 0 {
             let mut solution_message = [0; 85];
             solution_message[0] = CONTROL_CHARACTER;
-            solution_message[1..21].copy_from_slice(&config.factory_address);
-            solution_message[21..41].copy_from_slice(&config.calling_address);
-            solution_message[41..45].copy_from_slice(&salt[..]);
+            solution_message[1..21].copy_from_slice(&factory);
+            solution_message[21..41].copy_from_slice(&caller);
+            solution_message[41..45].copy_from_slice(&message);
             solution_message[45..53].copy_from_slice(&solution);
-            solution_message[53..].copy_from_slice(&config.init_code_hash);
+            solution_message[53..].copy_from_slice(&hashes[hash as usize]);
 
             // create new hash object
             let mut hash = Keccak::v256();
@@ -538,92 +1639,1182 @@ pub fn gpu(config: Config) -> ocl::Result<()> {
             let mut res: [u8; 32] = [0; 32];
             hash.finalize(&mut res);
 
-            // get the address that results from the hash
-            let address = <&Address>::try_from(&res[12..]).unwrap();
-
-            // count total and leading zero bytes
-            let mut total = 0;
-            let mut leading = 0;
-            for (i, &b) in address.iter().enumerate() {
-                if b == 0 {
-                    total += 1;
-                } else if leading == 0 {
-                    // set leading on finding non-zero byte
-                    leading = i;
+            // discard the solution if the device got it wrong
+            if mode::deployed(config, Address::from_slice(&res[12..])) != address {
+                mismatched += 1;
+                continue;
+            }
+        }
+
+        // discard addresses that the device should not have reported, which
+        // only a faulty kernel does
+        if !qualifies(config, &tuning, &address) {
+            debug!("device {device} reported {address}, which does not qualify");
+            rejected += 1;
+            continue;
+        }
+
+        // count total and leading zero bytes
+        let (leading, total) = zero_bytes(&address);
+
+        // skip or flag addresses in the reserved set
+        let reserved = config
+            .reserved
+            .as_ref()
+            .is_some_and(|r| r.contains(&address));
+        if reserved && config.reserved_action == ReservedAction::Skip {
+            continue;
+        }
+
+        // score the address if mining for a pattern
+        let pattern_score = config.scoring.map(|s| score_address(&s, &address));
+        if config
+            .checksum_prefix
+            .as_ref()
+            .is_some_and(|prefix| !matches_checksum(prefix, &address))
+        {
+            continue;
+        }
+        let Some(pattern_score) = reported_score(config, &address, pattern_score) else {
+            continue;
+        };
+
+        let key = leading * 20 + total;
+        let mut salt = [0; 32];
+        salt[..20].copy_from_slice(&caller);
+        salt[20..24].copy_from_slice(&message);
+        salt[24..].copy_from_slice(&solution);
+        found.push(Solution {
+            salt: salt.into(),
+            address,
+            factory: Address::from(factory),
+            caller: (config.salt_mode == SaltMode::Caller).then(|| Address::from(caller)),
+            init_code_hash: (hashes.len() > 1).then(|| hashes[hash as usize].into()),
+            reward: rewards.get(&key).unwrap_or("0").parse().unwrap(),
+            score: pattern_score,
+            reserved,
+            device: Some(device),
+        });
+    }
+
+    if rejected != 0 {
+        warn!("device {device} reported {rejected} solutions that do not qualify");
+    }
+
+    (found, mismatched, rejected)
+}
+
+/// Whether an address meets what the device is asked to report: the pattern,
+/// or else the minimum score (leaving out the uppercase points, as the device
+/// does), or else the zero byte thresholds, as they were in `tuning`.
+fn qualifies(config: &Config, tuning: &Tuning, address: &Address) -> bool {
+    if let Some(pattern) = config.pattern {
+        return pattern.matches(address);
+    }
+    match config.scoring {
+        Some(scoring) if tuning.min_score != 0 => {
+            let device = Scoring {
+                uppercase_weight: 0,
+                ..scoring
+            };
+            score_address(&device, address) >= tuning.min_score
+        }
+        _ => {
+            let (leading, total) = zero_bytes(address);
+            leading >= tuning.leading_zeroes_threshold as usize
+                || total >= tuning.total_zeroes_threshold as usize
+        }
+    }
+}
+
+/// Messages from the GPU worker threads to the thread reporting on them.
+enum GpuEvent {
+    /// The worker has set up its device, which goes by `name`.
+    Started { worker: usize, name: String },
+    /// A dispatch of `work_size` work items has completed, after any solutions
+    /// it returned were sent. In a deterministic search, it has moved the
+    /// stream of its target on to `advance`. If the nonces of its salt segment
+    /// `rolled_over`, the worker moves on to another segment.
+    Dispatched {
+        worker: usize,
+        work_size: u32,
+        search_space: String,
+        histogram: [u32; HISTOGRAM_BUCKETS],
+        advance: Option<(String, checkpoint::Position)>,
+        rolled_over: bool,
+    },
+    /// The sensors of the device were read.
+    Sampled {
+        worker: usize,
+        sample: monitor::Sample,
+    },
+    /// A dispatch returned solutions.
+    Solved(Solved),
+    /// The CPU worker (in hybrid mode) has made `hashes` attempts so far.
+    Searched { worker: usize, hashes: u128 },
+    /// The CPU worker found a solution.
+    Found(Box<Solution>),
+    /// The worker stopped as the run was asked to stop.
+    Stopped,
+    /// The worker stopped because of an error.
+    Failed { device: u8, error: String },
+}
+
+/// The solutions returned by a dispatch for a target and message.
+struct Solved {
+    device: u8,
+    /// The parameters that the device searched with.
+    tuning: Tuning,
+    target: usize,
+    message: [u8; 4],
+    /// How many solutions were found, which may exceed those returned.
+    count: usize,
+    solutions: Vec<(u64, [u8; 20], u32)>,
+}
+
+/// What the reporting thread knows about each GPU worker.
+#[derive(Default)]
+struct WorkerStatus {
+    device: u8,
+    name: String,
+    dispatches: u64,
+    hashes: u128,
+    work_size: u32,
+    search_space: String,
+    sample: Option<monitor::Sample>,
+}
+
+/// Drive one device, cycling through the factories and callers and sending
+/// progress and solutions back as events. Random segments are only taken from
+/// the share of `worker` out of the devices in this process, within the share
+/// of this process in the fleet. In a deterministic search, the segments of
+/// that share are walked in order instead, carrying on for each target from
+/// its position in `start`. A segment is never searched past the point where
+/// its nonces roll over. Changes to the parameters of the run through
+/// `control` are picked up before the next dispatch.
+#[allow(clippy::too_many_arguments)]
+fn gpu_worker(
+    config: &Config,
+    control: &Control,
+    device: u8,
+    worker: usize,
+    start: Vec<checkpoint::Position>,
+    power_gate: &power::PowerGate,
+    shutdown: &shutdown::Shutdown,
+    events: &mpsc::Sender<GpuEvent>,
+) -> ocl::Result<()> {
+    // the factory and caller searched by each program
+    let targets = targets(config);
+
+    // the work sizes to try when auto-tuning, up to four times larger or 16
+    // times smaller than the configured work size
+    let work_sizes: Vec<u32> = if config.auto_tune {
+        [16, 4, 1]
+            .into_iter()
+            .map(|divisor| config.work_size / divisor)
+            .chain([4, 16].map(|factor| config.work_size.saturating_mul(factor)))
+            .map(|work_size| work_size.clamp(MIN_WORK_SIZE, MAX_WORK_SIZE))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    } else {
+        vec![config.work_size]
+    };
+    let largest = *work_sizes.last().unwrap();
+
+    // set up OpenCL on the device, or wgpu if there is no OpenCL platform
+    let mut generation = control.generation();
+    let mut tuning = control.tuning();
+    let mut backend = backend(config, &tuning, device, largest)?;
+    let started = GpuEvent::Started {
+        worker,
+        name: backend.name().to_string(),
+    };
+    if events.send(started).is_err() {
+        return Ok(());
+    }
+
+    // read the temperature of the device, if it has sensors that can be read
+    let mut monitor = monitor::Monitor::new(config, device);
+    if monitor.is_none() && config.max_temp.is_some() {
+        warn!(
+            device,
+            "no temperature sensor found, so the maximum temperature does not apply"
+        );
+    }
+
+    // create a random number generator
+    let mut rng = thread_rng();
+
+    // the global work size, reduced if the device runs out of resources
+    let mut work_size = config.work_size;
+    if config.auto_tune {
+        let message = FixedBytes::<4>::random();
+        let nonce: u32 = rng.gen();
+        work_size = auto_tune(&work_sizes, |work_size| {
+            backend.dispatch(0, &message.0, nonce, work_size)
+        })?;
+        info!(device, "auto-tuned the work size to {work_size:#x}");
+    }
+
+    // this worker's share of the random segments, across every device of every
+    // process in the fleet
+    let workers = local_workers(config);
+    let shares = config.worker_count as u64 * workers as u64;
+    let share = config.worker_id as u64 * workers as u64 + worker as u64;
+
+    // the next warm start salt to seed a segment from
+    let mut warm_next = 0;
+
+    // where the stream of each target has got to, in a deterministic search
+    let mut positions = start;
+    let nonce_start = config.nonce_start.unwrap_or(0) as u32;
+
+    // the random segments of each target whose nonces have run out, which
+    // are never picked again
+    let segments = share_segments(4 - config.salt_prefix.len(), share, shares);
+    let mut finished = vec![HashSet::new(); targets.len()];
+
+    // begin searching for addresses, cycling through the factories and callers
+    let rounds = targets.iter().enumerate().cycle().enumerate();
+    for (round, (target, &(_, factory, caller))) in rounds {
+        // on alternate rounds, pick up next to an earlier salt for this caller
+        let seed = (round % 2 == 1)
+            .then(|| {
+                (0..config.warm_start.len())
+                    .map(|i| (warm_next + i) % config.warm_start.len())
+                    .find(|&i| {
+                        let seed = &config.warm_start[i];
+                        let prefix = &config.salt_prefix;
+                        seed[..20] == caller[..]
+                            && seed[20..20 + prefix.len()] == prefix[..]
+                            && in_share(&seed[20 + prefix.len()..24], share, shares)
+                    })
+            })
+            .flatten()
+            .map(|i| {
+                warm_next = i + 1;
+                config.warm_start[i]
+            });
+
+        // construct the 4-byte message to hash, leaving last 8 of salt empty
+        let mut salt = match seed {
+            Some(seed) => FixedBytes::<4>::from_slice(&seed[20..24]),
+            None if config.deterministic => salt_segment::<4>(
+                &config.salt_prefix,
+                Some(positions[target].segment),
+                share,
+                shares,
+            ),
+            None => match fresh_segment(&config.salt_prefix, &finished[target], share, shares) {
+                Some(segment) => segment,
+                // move on to a target that still has segments to search
+                None if finished.iter().any(|f| (f.len() as u64) < segments) => continue,
+                None => {
+                    warn!(
+                        device,
+                        "every salt segment of this device's share has been searched, stopping"
+                    );
+                    return Ok(());
                 }
+            },
+        };
+
+        // reset the nonce; for more uniformly distributed nonces, we shall
+        // initialize it to a random value unless a starting nonce has been
+        // configured or the segment is seeded (or the search deterministic)
+        let mut nonce: u32 = match (seed, config.nonce_start) {
+            (Some(seed), _) => {
+                let seed_nonce = u32::from_le_bytes(seed[28..].try_into().unwrap());
+                seed_nonce.wrapping_add(config.nonce_stride as u32)
+            }
+            _ if config.deterministic => {
+                nonce_start.wrapping_add((positions[target].offset * config.nonce_stride) as u32)
             }
+            (None, Some(start)) => start as u32,
+            (None, None) => rng.gen(),
+        };
+        let mut view_buf = [0; 8];
+        debug!(
+            device,
+            segment = %hex::encode(salt),
+            nonce,
+            factory = %Address::from(factory),
+            caller = %Address::from(caller),
+            warm = seed.is_some(),
+            "starting salt segment"
+        );
+
+        // repeatedly dispatch work to search for new addresses, until some
+        // are found
+        loop {
+            // don't dispatch more work while paused for battery power, or
+            // while the device is too hot
+            power_gate.wait();
+            if let (Some(monitor), Some(max_temp)) = (&mut monitor, config.max_temp) {
+                monitor.cool_down(device, max_temp, shutdown);
+            }
+
+            // stop once asked to, having handed over any earlier solutions
+            if shutdown.requested() {
+                return Ok(());
+            }
+
+            // pick up any change to the parameters of the run, setting the
+            // device up again only if that is the only way to apply it
+            if control.generation() != generation {
+                generation = control.generation();
+                let next = control.tuning();
+                if next.changes_kernel(&tuning) && !backend.retune(&next) {
+                    info!(device, "rebuilding the kernels for the new parameters");
+                    backend = crate::backend(config, &next, device, largest)?;
+                }
+                tuning = next;
+            }
+
+            // run the dispatch, backing off if the device runs out of resources
+            let span = debug_span!("dispatch", device, nonce, work_size).entered();
+            let dispatch = match backend.dispatch(target, &salt.0, nonce, work_size) {
+                Ok(dispatch) => dispatch,
+                Err(e) => {
+                    work_size = back_off(work_size, e)?;
+                    continue;
+                }
+            };
+            debug!(solutions = dispatch.count, "dispatch complete");
+            drop(span);
+
+            // hand any returned solutions to the reporting thread
+            let solved = dispatch.count != 0;
+            if solved {
+                let solved = GpuEvent::Solved(Solved {
+                    device,
+                    tuning,
+                    target,
+                    message: salt.0,
+                    count: dispatch.count as usize,
+                    solutions: dispatch.solutions,
+                });
+                if events.send(solved).is_err() {
+                    return Ok(());
+                }
+            }
+
+            // step the nonce; in a deterministic search, the stream moves on
+            // to its next segment once the nonces run out
+            let (next, rolled_over) = nonce.overflowing_add(config.nonce_stride as u32);
+            let advance = config.deterministic.then(|| {
+                let position = &mut positions[target];
+                if rolled_over {
+                    position.segment += 1;
+                    position.offset = 0;
+                } else {
+                    position.offset += 1;
+                }
+                (checkpoint::gpu_stream(device, target), *position)
+            });
 
-            let key = leading * 20 + total;
-            let reward = rewards.get(&key).unwrap_or("0");
-            let output = format!(
-                "0x{}{}{} => {} => {}",
-                hex::encode(config.calling_address),
+            // report the dispatch along with the search space it covered
+            LittleEndian::write_u64(&mut view_buf, (nonce as u64) << 32);
+            let search_space = format!(
+                "{}{}xxxxxxxx{:08x} (factory {})",
+                hex::encode(caller),
                 hex::encode(salt),
-                hex::encode(solution),
-                address,
-                reward,
+                BigEndian::read_u64(&view_buf),
+                Address::from(factory),
             );
+            let dispatched = GpuEvent::Dispatched {
+                worker,
+                work_size,
+                search_space,
+                histogram: dispatch.histogram,
+                advance,
+                rolled_over,
+            };
+            if events.send(dispatched).is_err() {
+                return Ok(());
+            }
+            if let Some(sample) = monitor.as_mut().and_then(monitor::Monitor::sample) {
+                if events.send(GpuEvent::Sampled { worker, sample }).is_err() {
+                    return Ok(());
+                }
+            }
 
-            let show = format!("{output} ({leading} / {total})");
-            found_list.push(show.to_string());
+            // if at least one solution is found, move on to the next target
+            if solved {
+                break;
+            }
 
-            file.lock_exclusive().expect("Couldn't lock file.");
+            // once the nonces of the segment run out, move on to a segment
+            // that has not been searched (the next one of the share in a
+            // deterministic search) rather than wrapping around onto nonces
+            // already searched, stopping if the share has no other segment
+            if rolled_over {
+                let previous = salt;
+                let fresh = if config.deterministic {
+                    (positions[target].segment < segments).then(|| {
+                        salt_segment::<4>(
+                            &config.salt_prefix,
+                            Some(positions[target].segment),
+                            share,
+                            shares,
+                        )
+                    })
+                } else {
+                    finished[target].insert(previous);
+                    fresh_segment(&config.salt_prefix, &finished[target], share, shares)
+                };
+                let Some(fresh) = fresh else {
+                    warn!(
+                        device,
+                        segment = %hex::encode(salt),
+                        "every salt segment of this device's share has been searched"
+                    );
+                    // other targets may still have segments to search
+                    if config.deterministic {
+                        return Ok(());
+                    }
+                    break;
+                };
+                salt = fresh;
+                info!(
+                    device,
+                    from = %hex::encode(previous),
+                    to = %hex::encode(salt),
+                    "nonces of the salt segment ran out, moving on to another"
+                );
+                nonce = nonce_start;
+                continue;
+            }
+            nonce = next;
+        }
+    }
 
-            writeln!(&file, "{output}").expect("Couldn't write to `efficient_addresses.txt` file.");
+    Ok(())
+}
 
-            file.unlock().expect("Couldn't unlock file.");
+/// The outcome of searching one range of nonces on a device.
+struct Dispatch {
+    /// How many solutions were found, which may exceed those returned.
+    count: u32,
+    /// The returned solutions, as the 8-byte nonce, the derived address and
+    /// the index of the init code hash it was derived with.
+    solutions: Vec<(u64, [u8; 20], u32)>,
+    /// Candidates by leading zero nibbles, if the histogram is enabled.
+    histogram: [u32; HISTOGRAM_BUCKETS],
+}
+
+/// A compute API that the GPU workers can dispatch work to.
+trait Backend {
+    /// Search `work_size` nonces (the global work item IDs, with `nonce` as
+    /// the upper half) for the given target and 4-byte message.
+    fn dispatch(
+        &mut self,
+        target: usize,
+        message: &[u8; 4],
+        nonce: u32,
+        work_size: u32,
+    ) -> ocl::Result<Dispatch>;
+
+    /// The name of the device, as its driver reports it.
+    fn name(&self) -> &str;
+
+    /// Search for what `tuning` asks for from the next dispatch on, if that
+    /// can be done without setting up again; returns whether it was.
+    fn retune(&mut self, tuning: &Tuning) -> bool;
+}
+
+/// Set up OpenCL on the device for dispatches of up to `work_size`, or wgpu if
+/// that feature is enabled and there is no OpenCL platform, searching for what
+/// `tuning` asks for.
+fn backend(
+    config: &Config,
+    tuning: &Tuning,
+    device: u8,
+    work_size: u32,
+) -> ocl::Result<Box<dyn Backend>> {
+    #[cfg(feature = "wgpu")]
+    if ocl::core::get_platform_ids().map_or(true, |p| p.is_empty()) {
+        let targets = targets(config);
+        return Ok(Box::new(webgpu::Wgpu::new(
+            config, tuning, device, &targets, work_size,
+        )?));
+    }
+
+    Ok(Box::new(OpenCl::new(config, tuning, device, work_size)?))
+}
 
-            // Send result to configured endpoint if available
-            if let (Some(client), Some(endpoint_url)) = (&client, &config.endpoint_url) {
-                let score = reward.to_string();
+/// Dispatches work to an OpenCL device, with a program for each target. The
+/// kernels are built once with their buffers bound, and the nonce is passed as
+/// a scalar argument, so a dispatch only transfers what has changed.
+struct OpenCl {
+    name: String,
+    queue: Queue,
+    kernels: Vec<Kernel>,
+    message: Buffer<u8>,
+    solutions: Buffer<u64>,
+    solution_count: Buffer<u32>,
+    solution_addresses: Buffer<u8>,
+    solution_hashes: Buffer<u32>,
+    histogram: Buffer<u32>,
+    slots: usize,
+    histogram_enabled: bool,
+    poll_interval_ms: u64,
+    // the message currently in the message buffer
+    current_message: Option<[u8; 4]>,
+}
+
+impl OpenCl {
+    /// Build the programs, buffers and kernels for dispatches of up to
+    /// `work_size`.
+    fn new(config: &Config, tuning: &Tuning, device: u8, work_size: u32) -> ocl::Result<Self> {
+        // set up a "proqueue" (or amalgamation of various elements) for each
+        // factory and caller, in the order given by `targets`
+        let ocl_pqs = pro_ques(config, tuning, device, work_size)?;
+        let queue = ocl_pqs[0].queue().clone();
+        let name = queue.device().name()?;
+
+        // the number of solutions that can be returned from a single dispatch
+        let slots = solution_slots(config, work_size);
+
+        // build a buffer for passing the message to the kernel
+        let message = Buffer::builder()
+            .queue(queue.clone())
+            .flags(MemFlags::new().read_only())
+            .len(4)
+            .build()?;
+
+        // establish a buffer for nonces that result in desired addresses
+        let solutions = Buffer::builder()
+            .queue(queue.clone())
+            .flags(MemFlags::new().write_only())
+            .len(slots)
+            .build()?;
+
+        // and a counter of how many were found, which may exceed the slots
+        let solution_count = Buffer::builder()
+            .queue(queue.clone())
+            .flags(MemFlags::new().read_write())
+            .len(1)
+            .copy_host_slice(&[0u32])
+            .build()?;
+
+        // the addresses derived from each solution
+        let solution_addresses = Buffer::builder()
+            .queue(queue.clone())
+            .flags(MemFlags::new().write_only())
+            .len(slots * 20)
+            .build()?;
+
+        // and the index of the init code hash each was derived with
+        let solution_hashes = Buffer::builder()
+            .queue(queue.clone())
+            .flags(MemFlags::new().write_only())
+            .len(slots)
+            .build()?;
+
+        // counts of candidates by leading zero nibbles, accumulated on the
+        // device over a dispatch
+        let histogram = Buffer::builder()
+            .queue(queue.clone())
+            .flags(MemFlags::new().read_write())
+            .len(HISTOGRAM_BUCKETS)
+            .copy_host_slice(&[0u32; HISTOGRAM_BUCKETS])
+            .build()?;
 
-                // Try to send the result to the endpoint
-                let _ = client
-                    .post(endpoint_url)
-                    .json(&json!({
-                        "salt": format!("0x{}{}{}",
-                            hex::encode(config.calling_address),
-                            hex::encode(salt),
-                            hex::encode(solution)),
-                        "address": address.to_string(),
-                        "score": score
-                    }))
-                    .send();
+        // build the kernel for each target, binding the buffers
+        let kernels = ocl_pqs
+            .iter()
+            .map(|ocl_pq| {
+                ocl_pq
+                    .kernel_builder("hashMessage")
+                    .arg_named("message", &message)
+                    .arg_named("nonce", 0u32)
+                    .arg_named("solutions", &solutions)
+                    .arg_named("solutionCount", &solution_count)
+                    .arg_named("solutionAddresses", &solution_addresses)
+                    .arg_named("solutionHashes", &solution_hashes)
+                    .arg_named("histogram", &histogram)
+                    .build()
+            })
+            .collect::<ocl::Result<_>>()?;
+
+        Ok(Self {
+            name,
+            queue,
+            kernels,
+            message,
+            solutions,
+            solution_count,
+            solution_addresses,
+            solution_hashes,
+            histogram,
+            slots,
+            histogram_enabled: config.histogram,
+            poll_interval_ms: config.poll_interval_ms,
+            current_message: None,
+        })
+    }
+}
+
+impl Backend for OpenCl {
+    fn dispatch(
+        &mut self,
+        target: usize,
+        message: &[u8; 4],
+        nonce: u32,
+        work_size: u32,
+    ) -> ocl::Result<Dispatch> {
+        let kern = &self.kernels[target];
+
+        // pass the message when starting a new segment, and the nonce
+        if self.current_message != Some(*message) {
+            self.message.write(&message[..]).enq()?;
+            self.current_message = Some(*message);
+        }
+        kern.set_arg("nonce", nonce)?;
+
+        // enqueue the kernel
+        let mut kernel_event = Event::empty();
+        unsafe {
+            kern.cmd()
+                .global_work_size(work_size)
+                .enew(&mut kernel_event)
+                .enq()?;
+        }
+
+        // sleep until the kernel completes, woken by the driver (or polling
+        // for it) rather than blocking in a driver that may busy-wait
+        if self.poll_interval_ms != 0 {
+            self.queue.flush()?;
+            while !kernel_event.is_complete()? {
+                std::thread::sleep(std::time::Duration::from_millis(self.poll_interval_ms));
+            }
+        } else {
+            wait_for(&self.queue, &kernel_event)?;
+        }
+
+        // read the number of solutions from the device, which is also where
+        // a dispatch that ran out of resources may first report it
+        let mut count = [0u32];
+        self.solution_count.read(&mut count[..]).enq()?;
+
+        // collect the near-miss histogram of the dispatch
+        let mut histogram = [0u32; HISTOGRAM_BUCKETS];
+        if self.histogram_enabled {
+            self.histogram.read(&mut histogram[..]).enq()?;
+            self.histogram.write(&[0u32; HISTOGRAM_BUCKETS][..]).enq()?;
+        }
+
+        // if at least one solution is found, read them and reset the count,
+        // which is otherwise still zero for the next dispatch
+        let returned = (count[0] as usize).min(self.slots);
+        let mut solutions = vec![0u64; returned];
+        let mut addresses = vec![0u8; returned * 20];
+        let mut hashes = vec![0u32; returned];
+        if count[0] != 0 {
+            self.solutions.read(&mut solutions).enq()?;
+            self.solution_addresses.read(&mut addresses).enq()?;
+            self.solution_hashes.read(&mut hashes).enq()?;
+            self.solution_count.write(&[0u32][..]).enq()?;
+        }
+
+        Ok(Dispatch {
+            count: count[0],
+            solutions: solutions
+                .into_iter()
+                .zip(addresses.chunks_exact(20))
+                .zip(hashes)
+                .map(|((solution, address), hash)| (solution, address.try_into().unwrap(), hash))
+                .collect(),
+            histogram,
+        })
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    // the thresholds and minimum score are compiled into the programs
+    fn retune(&mut self, _: &Tuning) -> bool {
+        false
+    }
+}
+
+/// Block until an OpenCL command has completed (or failed), sleeping on a
+/// channel that the driver's completion callback wakes, so that the worker
+/// takes no CPU time while the device is busy. The solutions are processed on
+/// the reporting thread in the meantime.
+fn wait_for(queue: &Queue, event: &Event) -> ocl::Result<()> {
+    extern "C" fn completed(_: ocl::ffi::cl_event, _: i32, sender: *mut ocl::ffi::c_void) {
+        // SAFETY: the sender was leaked for this callback, which the driver
+        // calls exactly once
+        let sender = unsafe { Box::from_raw(sender as *mut mpsc::SyncSender<()>) };
+        let _ = sender.send(());
+    }
+
+    let (sender, receiver) = mpsc::sync_channel(1);
+    let sender = Box::into_raw(Box::new(sender));
+    // SAFETY: the callback takes back ownership of the sender
+    if let Err(error) = unsafe { event.set_callback(completed, sender as *mut _) } {
+        drop(unsafe { Box::from_raw(sender) });
+        return Err(error.into());
+    }
+    queue.flush()?;
+    receiver
+        .recv()
+        .map_err(|_| "the OpenCL completion callback was dropped".into())
+}
+
+/// The factory and caller searched by each program from [`pro_ques`], along
+/// with the index of the factory.
+fn targets(config: &Config) -> Vec<(usize, [u8; 20], [u8; 20])> {
+    config
+        .factory_addresses
+        .iter()
+        .enumerate()
+        .flat_map(|(index, &factory)| {
+            salt::heads(config)
+                .into_iter()
+                .map(move |caller| (index, factory, caller))
+        })
+        .collect()
+}
+
+/// Set up the OpenCL platform, device, context, program and queue for the
+/// device named in the Config object and its first factory and calling
+/// address, with the given default work size.
+fn pro_que(config: &Config, work_size: u32) -> ocl::Result<ProQue> {
+    let tuning = Tuning::new(config);
+    Ok(pro_ques(config, &tuning, config.gpu_device, work_size)?.swap_remove(0))
+}
+
+/// Set up a program for each factory and calling address in the Config object
+/// (callers varying fastest), sharing a single context and queue on the given
+/// device.
+fn pro_ques(
+    config: &Config,
+    tuning: &Tuning,
+    device: u8,
+    work_size: u32,
+) -> ocl::Result<Vec<ProQue>> {
+    // set up a platform to use
+    let platform = platform(config)?;
+
+    // set up the device to use
+    let device = Device::by_idx_wrap(platform, device as usize)?;
+
+    // set up the context to use
+    let context = Context::builder()
+        .platform(platform)
+        .devices(device)
+        .build()?;
+
+    // set up the queue to use
+    let queue = Queue::new(&context, device, None)?;
+
+    // set up a program with the constants for each factory and caller baked in
+    let slots = solution_slots(config, work_size);
+    targets(config)
+        .iter()
+        .map(|(_, factory, caller)| {
+            let program = Program::builder()
+                .devices(device)
+                .src(mk_kernel_src(config, tuning, factory, caller, slots))
+                .build(&context)?;
+            Ok(ProQue::new(
+                context.clone(),
+                queue.clone(),
+                program,
+                Some(work_size),
+            ))
+        })
+        .collect()
+}
+
+/// Time dispatches of each of the given work sizes, in increasing order, and
+/// return the one with the best hash rate. Larger sizes are not tried once the
+/// device runs out of resources or a dispatch becomes slow enough to hold up
+/// the reporting of results. Anything found while tuning is discarded.
+fn auto_tune(
+    work_sizes: &[u32],
+    mut dispatch: impl FnMut(u32) -> ocl::Result<Dispatch>,
+) -> ocl::Result<u32> {
+    let mut best = (work_sizes[0], 0.0);
+    for &work_size in work_sizes {
+        // a warm-up dispatch, which also shows whether the size is workable
+        let start = Instant::now();
+        match dispatch(work_size) {
+            Ok(_) => {}
+            Err(e) if out_of_resources(&e) && work_size != work_sizes[0] => break,
+            Err(e) => return Err(e),
+        }
+        if start.elapsed().as_millis() > AUTO_TUNE_MAX_MILLIS && work_size != work_sizes[0] {
+            break;
+        }
+
+        let start = Instant::now();
+        for _ in 0..AUTO_TUNE_DISPATCHES {
+            dispatch(work_size)?;
+        }
+        let rate = (work_size as f64 * AUTO_TUNE_DISPATCHES as f64) / start.elapsed().as_secs_f64();
+        if rate > best.1 {
+            best = (work_size, rate);
+        }
+    }
+
+    Ok(best.0)
+}
+
+/// Whether an error shows that the device has run out of resources.
+fn out_of_resources(error: &ocl::Error) -> bool {
+    matches!(
+        error.api_status(),
+        Some(Status::CL_OUT_OF_RESOURCES | Status::CL_MEM_OBJECT_ALLOCATION_FAILURE)
+    )
+}
+
+/// The configured OpenCL platform, or else the default platform if it has a
+/// GPU, or else the first platform that does. This keeps a CPU-only ICD (e.g.
+/// one installed alongside a GPU driver) from being picked by accident.
+fn platform(config: &Config) -> ocl::Result<Platform> {
+    let platforms: Vec<Platform> = ocl::core::get_platform_ids()?
+        .into_iter()
+        .map(Platform::new)
+        .collect();
+    if let Some(index) = config.platform {
+        return platforms
+            .get(index as usize)
+            .copied()
+            .ok_or_else(|| format!("no OpenCL platform {index} found").into());
+    }
+
+    let default = Platform::new(ocl::core::default_platform()?);
+    let has_gpu = |platform: &Platform| {
+        Device::list(platform, Some(DeviceType::GPU)).is_ok_and(|devices| !devices.is_empty())
+    };
+    Ok([default]
+        .into_iter()
+        .chain(platforms)
+        .find(has_gpu)
+        .unwrap_or(default))
+}
+
+/// Halve the work size after the device has run out of resources, or pass the
+/// error on if it has another cause or the work size cannot shrink any further.
+fn back_off(work_size: u32, error: ocl::Error) -> ocl::Result<u32> {
+    if !out_of_resources(&error) || work_size / 2 < MIN_WORK_SIZE {
+        return Err(error);
+    }
+
+    let work_size = work_size / 2;
+    warn!("device ran out of resources, reducing the work size to {work_size:#x}");
+    Ok(work_size)
+}
+
+/// The number of slots in the solutions buffer for a dispatch of the given
+/// work size, always at least one.
+fn solution_slots(config: &Config, work_size: u32) -> usize {
+    ((work_size as f64 * config.solutions_fraction).ceil() as usize).max(1)
+}
+
+/// Count the leading and total zero bytes of an address.
+fn zero_bytes(address: &Address) -> (usize, usize) {
+    let leading = address.iter().take_while(|&&b| b == 0).count();
+    let total = address.iter().filter(|&&b| b == 0).count();
+    (leading, total)
+}
+
+/// Add an address to a JSON object in EIP-55 checksummed form, along with its
+/// lowercase form under `<key>_lowercase` for tools that want that instead.
+fn add_address(object: &mut serde_json::Value, key: &str, address: &Address) {
+    object[key] = json!(address.to_checksum(None));
+    object[format!("{key}_lowercase")] = json!(hex::encode_prefixed(address));
+}
+
+/// Whether a result scoring `rank` beats the best score submitted so far,
+/// which it then replaces.
+fn improves(best: &mut Option<u128>, rank: u128) -> bool {
+    if best.is_some_and(|best| rank <= best) {
+        return false;
+    }
+    *best = Some(rank);
+    true
+}
+
+/// Whether a salt segment, read as a big-endian number, falls in share `share`
+/// of `shares` (by its remainder), so that the devices and processes of a fleet
+/// never search the same segment.
+fn in_share(segment: &[u8], share: u64, shares: u64) -> bool {
+    segment.iter().fold(0, |remainder, &byte| {
+        ((remainder << 8) | byte as u64) % shares
+    }) == share
+}
+
+/// Segment `segment` of share `share` of `shares` (see [`in_share`]), so that
+/// the segments of a share can be walked in order.
+fn share_segment<const N: usize>(segment: u64, share: u64, shares: u64) -> FixedBytes<N> {
+    let value = (segment as u128 * shares as u128 + share as u128).to_be_bytes();
+    FixedBytes::from_slice(&value[16 - N..])
+}
+
+/// How many segments of `len` bytes there are in share `share` of `shares`
+/// (see [`in_share`]).
+fn share_segments(len: usize, share: u64, shares: u64) -> u64 {
+    ((1u64 << (8 * len)) - share).div_ceil(shares)
+}
+
+/// A salt segment of share `share` of `shares` (see [`in_share`]) that starts
+/// with `prefix`, the rest of which is either the segment numbered `number`
+/// (see [`share_segment`]) or random.
+fn salt_segment<const N: usize>(
+    prefix: &[u8],
+    number: Option<u64>,
+    share: u64,
+    shares: u64,
+) -> FixedBytes<N> {
+    let mut segment = match number {
+        Some(number) => share_segment::<N>(number, share, shares),
+        None => loop {
+            let segment = FixedBytes::<N>::random();
+            if in_share(&segment[prefix.len()..], share, shares) {
+                break segment;
             }
+        },
+    };
+    segment[..prefix.len()].copy_from_slice(prefix);
+    segment
+}
 
-            found += 1;
+/// A random salt segment of share `share` of `shares` that starts with
+/// `prefix` (see [`salt_segment`]) and is not one of the `finished` ones, or
+/// `None` if every segment of the share is.
+fn fresh_segment(
+    prefix: &[u8],
+    finished: &HashSet<FixedBytes<4>>,
+    share: u64,
+    shares: u64,
+) -> Option<FixedBytes<4>> {
+    if finished.len() as u64 >= share_segments(4 - prefix.len(), share, shares) {
+        return None;
+    }
+    loop {
+        let segment = salt_segment::<4>(prefix, None, share, shares);
+        if !finished.contains(&segment) {
+            return Some(segment);
         }
     }
 }
 
+/// The score that an address is reported with: that of the WASM scorer if
+/// one is loaded (or `None` if it drops the address), and otherwise the
+/// pattern score.
+#[cfg_attr(not(feature = "wasm"), allow(unused_variables))]
+fn reported_score(
+    config: &Config,
+    address: &Address,
+    pattern_score: Option<u32>,
+) -> Option<Option<u32>> {
+    #[cfg(feature = "wasm")]
+    if let Some(scorer) = &config.scorer {
+        return scorer.score(address).map(Some);
+    }
+    Some(pattern_score)
+}
+
+/// Whether any value appears more than once.
+fn has_duplicates<T: PartialEq>(values: &[T]) -> bool {
+    (1..values.len()).any(|i| values[i..].contains(&values[i - 1]))
+}
+
+/// The file that results for a factory and init code hash are written to: the
+/// usual output file when there is only one of each, and otherwise one file
+/// per factory, per hash or both.
+fn output_path(config: &Config, factory: &[u8; 20], hash: &[u8; 32]) -> String {
+    let mut path = OUTPUT_FILE.strip_suffix(".txt").unwrap().to_string();
+    if config.factory_addresses.len() > 1 {
+        write!(path, "_0x{}", hex::encode(factory)).unwrap();
+    }
+    if !config.extra_init_code_hashes.is_empty() {
+        write!(path, "_0x{}", hex::encode(hash)).unwrap();
+    }
+    path + ".txt"
+}
+
 #[track_caller]
-fn output_file() -> File {
+fn output_file(path: &str) -> File {
     OpenOptions::new()
         .append(true)
         .create(true)
         .read(true)
-        .open("efficient_addresses.txt")
-        .expect("Could not create or open `efficient_addresses.txt` file.")
+        .open(path)
+        .unwrap_or_else(|e| panic!("Could not create or open `{path}` file: {e}"))
 }
 
 /// Creates the OpenCL kernel source code by populating the template with the
-/// values from the Config object.
-fn mk_kernel_src(config: &Config) -> String {
+/// values from the Config object (and the thresholds and minimum score from
+/// `tuning`) for the given factory and caller.
+fn mk_kernel_src(
+    config: &Config,
+    tuning: &Tuning,
+    factory: &[u8; 20],
+    caller: &[u8; 20],
+    solution_slots: usize,
+) -> String {
     let mut src = String::with_capacity(2048 + KERNEL_SRC.len());
 
-    let factory = config.factory_address.iter();
-    let caller = config.calling_address.iter();
-    let hash = config.init_code_hash.iter();
-    let hash = hash.enumerate().map(|(i, x)| (i + 52, x));
-    for (i, x) in factory.chain(caller).enumerate().chain(hash) {
+    let prefix = factory.iter().chain(caller.iter());
+    for (i, x) in prefix.enumerate() {
         writeln!(src, "#define S_{} {}u", i + 1, x).unwrap();
     }
-    let lz = config.leading_zeroes_threshold;
+    let lz = tuning.leading_zeroes_threshold;
     writeln!(src, "#define LEADING_ZEROES {lz}").unwrap();
-    let tz = config.total_zeroes_threshold;
+    let tz = tuning.total_zeroes_threshold;
     writeln!(src, "#define TOTAL_ZEROES {tz}").unwrap();
+    writeln!(src, "#define MAX_SOLUTIONS {solution_slots}u").unwrap();
+    let histogram = config.histogram as u8;
+    writeln!(src, "#define HISTOGRAM {histogram}").unwrap();
+    writeln!(
+        src,
+        "#define HISTOGRAM_MIN_NIBBLES {HISTOGRAM_MIN_NIBBLES}u"
+    )
+    .unwrap();
+    let scoring = config.scoring.unwrap_or_default();
+    writeln!(src, "#define SCORING {}", config.scoring.is_some() as u8).unwrap();
+    writeln!(src, "#define SCORE_NIBBLE {}u", scoring.target_nibble).unwrap();
+    writeln!(
+        src,
+        "#define SCORE_LEADING_WEIGHT {}u",
+        scoring.leading_weight
+    )
+    .unwrap();
+    writeln!(src, "#define SCORE_RUN_BONUS {}u", scoring.run_bonus).unwrap();
+    writeln!(
+        src,
+        "#define SCORE_RUN_END_BONUS {}u",
+        scoring.run_end_bonus
+    )
+    .unwrap();
+    writeln!(src, "#define SCORE_TAIL_BONUS {}u", scoring.tail_bonus).unwrap();
+    writeln!(
+        src,
+        "#define SCORE_NIBBLE_WEIGHT {}u",
+        scoring.nibble_weight
+    )
+    .unwrap();
+    writeln!(
+        src,
+        "#define SCORE_REQUIRE_TARGET {}",
+        scoring.require_target_first as u8
+    )
+    .unwrap();
+    writeln!(src, "#define MIN_SCORE {}u", tuning.min_score).unwrap();
+    let create3 = (config.mode == Mode::Create3) as u8;
+    writeln!(src, "#define CREATE3 {create3}").unwrap();
+    writeln!(src, "#define PATTERN {}", config.pattern.is_some() as u8).unwrap();
+    let words = config
+        .pattern
+        .map_or([(0, 0); 5], |pattern| pattern.words());
+    for (i, (mask, value)) in words.iter().enumerate() {
+        writeln!(src, "#define PATTERN_MASK_{i} 0x{mask:08x}u").unwrap();
+        writeln!(src, "#define PATTERN_VALUE_{i} 0x{value:08x}u").unwrap();
+    }
+
+    // pin the salt prefix at the start of the message lane
+    let mut prefix = [0u8; 8];
+    let mut mask = [0u8; 8];
+    prefix[..config.salt_prefix.len()].copy_from_slice(&config.salt_prefix);
+    mask[..config.salt_prefix.len()].fill(0xff);
+    let prefix = u64::from_le_bytes(prefix);
+    writeln!(src, "#define SALT_PREFIX 0x{prefix:016x}UL").unwrap();
+    let mask = u64::from_le_bytes(mask);
+    writeln!(src, "#define SALT_PREFIX_MASK 0x{mask:016x}UL").unwrap();
+
+    // the padded preimage for each init code hash, with the message and
+    // nonce left empty
+    let hashes = mode::create2_hashes(config);
+    writeln!(src, "#define INIT_CODE_HASHES {}u", hashes.len()).unwrap();
+    let preimages: Vec<[u8; 200]> = hashes
+        .iter()
+        .map(|hash| {
+            let mut preimage = [0u8; 200];
+            preimage[0] = CONTROL_CHARACTER;
+            preimage[1..21].copy_from_slice(factory);
+            preimage[21..41].copy_from_slice(caller);
+            preimage[53..85].copy_from_slice(hash);
+            preimage[85] = 0x01;
+            preimage[135] = 0x80;
+            preimage
+        })
+        .collect();
+    let lane = |preimage: &[u8; 200], i: usize| {
+        u64::from_le_bytes(preimage[i * 8..i * 8 + 8].try_into().unwrap())
+    };
+
+    // define a constant for each hash as a macro of the index of the hash,
+    // which is known once the loop over the hashes is unrolled
+    let mut define = |name: &str, values: &[u64]| {
+        let value = values
+            .iter()
+            .enumerate()
+            .rev()
+            .fold(String::new(), |rest, (i, value)| match rest.is_empty() {
+                true => format!("0x{value:016x}UL"),
+                false => format!("((hash) == {i}u ? 0x{value:016x}UL : {rest})"),
+            });
+        writeln!(src, "#define {name}(hash) {value}").unwrap();
+    };
+
+    // the lanes that hold the init code hash and the start of the padding
+    for i in 6..=10 {
+        let lanes: Vec<u64> = preimages.iter().map(|preimage| lane(preimage, i)).collect();
+        define(&format!("HASH_LANE_{i}"), &lanes);
+    }
+
+    // precompute the first round's theta column parities from the constant
+    // lanes of the padded preimage, leaving out the message and nonce lanes
+    let parities: Vec<[u64; 5]> = preimages
+        .iter()
+        .map(|preimage| {
+            std::array::from_fn(|x| {
+                (0..5)
+                    .map(|y| x + 5 * y)
+                    .filter(|&i| i != 5 && i != 6)
+                    .fold(0, |c, i| c ^ lane(preimage, i))
+            })
+        })
+        .collect();
+    for x in 0..5 {
+        let column: Vec<u64> = parities.iter().map(|parities| parities[x]).collect();
+        define(&format!("THETA_C{x}"), &column);
+    }
+    let d3: Vec<u64> = parities
+        .iter()
+        .map(|parities| parities[2] ^ parities[4].rotate_left(1))
+        .collect();
+    define("THETA_D3", &d3);
 
     src.push_str(KERNEL_SRC);
 
     src
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // with one free byte, rollovers walk every segment of the share once
+    #[test]
+    fn fresh_segments_run_out() {
+        let prefix = [0xaa, 0xbb, 0xcc];
+        let (share, shares) = (1, 3);
+        let mut finished = HashSet::new();
+        while let Some(segment) = fresh_segment(&prefix, &finished, share, shares) {
+            assert_eq!(segment[..3], prefix);
+            assert!(in_share(&segment[3..], share, shares));
+            assert!(finished.insert(segment));
+        }
+        assert_eq!(finished.len() as u64, share_segments(1, share, shares));
+        assert_eq!(finished.len(), 85);
+    }
+}
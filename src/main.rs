@@ -1,20 +1,52 @@
-use create2crunch::Config;
-use std::env;
+use clap::Parser;
+use create2crunch::cli::Cli;
 use std::process;
+use tracing::{error, warn};
 
 fn main() {
-    let config = Config::new(env::args()).unwrap_or_else(|err| {
-        eprintln!("Failed parsing arguments: {err}");
+    let cli = Cli::parse();
+
+    // keep informational logs from drawing over the dashboard
+    let level = if cli.mine.tui { "warn" } else { "info" };
+    create2crunch::init_logging(cli.mine.log_format, level);
+
+    if let Some(command) = cli.command {
+        if let Err(e) = command.run() {
+            error!("Application error: {e}");
+            process::exit(1);
+        }
+        return;
+    }
+
+    // run as a worker daemon that fetches its jobs
+    if cli.mine.job_url.is_some() {
+        if let Err(e) = create2crunch::work_jobs(cli.mine) {
+            error!("Job worker error: {e}");
+            process::exit(1);
+        }
+        return;
+    }
+
+    let config = cli.mine.into_config().unwrap_or_else(|err| {
+        error!("Failed parsing arguments: {err}");
         process::exit(1);
     });
 
+    let warnings = config.validate().unwrap_or_else(|err| {
+        error!("Invalid arguments: {err}");
+        process::exit(1);
+    });
+    for warning in warnings {
+        warn!("{warning}");
+    }
+
     if config.gpu_device == 255 {
         if let Err(e) = create2crunch::cpu(config) {
-            eprintln!("CPU application error: {e}");
+            error!("CPU application error: {e}");
             process::exit(1);
         }
     } else if let Err(e) = create2crunch::gpu(config) {
-        eprintln!("GPU application error: {e}");
+        error!("GPU application error: {e}");
         process::exit(1);
     }
 }
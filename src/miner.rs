@@ -0,0 +1,328 @@
+use crate::checkpoint::{self, Checkpoint};
+use crate::control::Control;
+use crate::shutdown::Shutdown;
+use crate::{
+    add_address, cpu_search, gpu_solutions, power, set_niceness, spawn_gpu_workers, targets,
+    Config, GpuEvent, Reward, Scoring,
+};
+use alloy_primitives::{hex, Address, B256, U256};
+use serde_json::{json, Value};
+use std::error::Error;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// A salt found by a search, with the address that it deploys to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Solution {
+    pub salt: B256,
+    pub address: Address,
+    pub factory: Address,
+    /// The caller that the salt starts with, or `None` for a free salt.
+    pub caller: Option<Address>,
+    /// The init code hash that the address is for, when several are mined.
+    pub init_code_hash: Option<B256>,
+    /// The reward for the zero bytes of the address (see [`Reward`]), which
+    /// is zero for rarities without one.
+    pub reward: U256,
+    /// The pattern score of the address, if scoring.
+    pub score: Option<u32>,
+    /// Whether the address is in the reserved set (if flagging rather than
+    /// skipping reserved addresses).
+    pub reserved: bool,
+    /// The device that found the salt, or `None` for the CPU.
+    pub device: Option<u8>,
+}
+
+impl Solution {
+    /// What solutions are ranked by: the pattern score if scoring, or else
+    /// the reward.
+    pub fn rank(&self) -> u128 {
+        match self.score {
+            Some(score) => score as u128,
+            None => self.reward.try_into().unwrap_or(0),
+        }
+    }
+
+    /// The line that the solution is written to the results file as.
+    pub(crate) fn line(&self) -> String {
+        format!("{} => {} => {}", self.salt, self.address, self.reward)
+    }
+
+    /// The JSON object that the solution is posted and recorded as.
+    pub(crate) fn payload(&self, job_id: Option<&str>) -> Value {
+        let mut payload = json!({
+            "salt": hex::encode_prefixed(self.salt),
+            "score": self.reward.to_string(),
+        });
+        add_address(&mut payload, "address", &self.address);
+        add_address(&mut payload, "factory", &self.factory);
+        if let Some(caller) = &self.caller {
+            add_address(&mut payload, "caller", caller);
+        }
+        if let Some(hash) = &self.init_code_hash {
+            payload["init_code_hash"] = json!(hex::encode_prefixed(hash));
+        }
+        if let Some(score) = self.score {
+            payload["pattern_score"] = json!(score);
+        }
+        if self.reserved {
+            payload["reserved"] = json!(true);
+        }
+        if let Some(id) = job_id {
+            payload["job_id"] = json!(id);
+        }
+        payload
+    }
+}
+
+/// Where a [`Miner`] searches.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Backend {
+    /// Every core of the CPU.
+    Cpu,
+    /// The given GPU devices (OpenCL, or wgpu where available).
+    Gpu(Vec<u8>),
+}
+
+/// Builds a search from a [`Config`], such as
+/// `Miner::new(config).with_backend(Backend::Gpu(vec![0])).with_min_score(60)`,
+/// which is then started to get a [`Mining`] handle to iterate over. Everything
+/// else (such as the thresholds, scoring rules, reserved set and stop
+/// conditions) is taken from the config.
+///
+/// The search runs on threads of its own and only hands back what it finds:
+/// nothing is written to disk, posted or printed, which is left to the caller
+/// (as the command line tool does on top of the same search). Neither is a
+/// deterministic search checkpointed, so it starts from `start_segment` every
+/// time.
+pub struct Miner {
+    config: Config,
+    backend: Backend,
+}
+
+impl Miner {
+    /// A miner for the given config, searching on the device it names.
+    pub fn new(config: Config) -> Self {
+        let backend = match config.gpu_device {
+            255 => Backend::Cpu,
+            device => Backend::Gpu(
+                [device]
+                    .into_iter()
+                    .chain(config.extra_gpu_devices.iter().copied())
+                    .collect(),
+            ),
+        };
+        Self { config, backend }
+    }
+
+    pub fn with_backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Only report addresses that score at least `min_score` (with the
+    /// default scoring rules unless the config has its own).
+    pub fn with_min_score(mut self, min_score: u32) -> Self {
+        self.config
+            .scoring
+            .get_or_insert_with(Scoring::default)
+            .min_score = min_score;
+        self
+    }
+
+    /// Validate the config and start searching on threads of its own.
+    pub fn start(mut self) -> Result<Mining, Box<dyn Error>> {
+        match &self.backend {
+            Backend::Cpu => {
+                self.config.gpu_device = 255;
+                self.config.extra_gpu_devices.clear();
+            }
+            Backend::Gpu(devices) => {
+                let (&device, extra) = devices.split_first().ok_or("no GPU device given")?;
+                self.config.gpu_device = device;
+                self.config.extra_gpu_devices = extra.to_vec();
+            }
+        }
+        for warning in self.config.validate()? {
+            warn!("{warning}");
+        }
+        if let Some(nice) = self.config.nice {
+            set_niceness(nice)?;
+        }
+
+        let shutdown = Shutdown::default();
+        let hashes = Arc::new(AtomicU64::new(0));
+        let (solutions, received) = mpsc::channel();
+        let thread = {
+            let (shutdown, hashes) = (shutdown.clone(), hashes.clone());
+            thread::spawn(move || match self.backend {
+                Backend::Cpu => {
+                    search_cpu(&self.config, &shutdown, &hashes, &solutions);
+                    Ok(())
+                }
+                Backend::Gpu(_) => search_gpu(self.config, &shutdown, &hashes, &solutions),
+            })
+        };
+
+        Ok(Mining {
+            solutions: received,
+            hashes,
+            shutdown,
+            thread,
+        })
+    }
+}
+
+/// A running search, which yields each solution as it is found and ends once
+/// the search stops.
+pub struct Mining {
+    solutions: mpsc::Receiver<Solution>,
+    hashes: Arc<AtomicU64>,
+    shutdown: Shutdown,
+    thread: JoinHandle<Result<(), String>>,
+}
+
+impl Mining {
+    /// Ask the search to stop. Solutions found by work already in flight are
+    /// still yielded.
+    pub fn stop(&self) {
+        self.shutdown.request();
+    }
+
+    /// The number of addresses derived so far.
+    pub fn hashes(&self) -> u64 {
+        self.hashes.load(Ordering::Relaxed)
+    }
+
+    /// Stop the search and wait for it to end, returning why it failed if it
+    /// did.
+    pub fn finish(self) -> Result<(), String> {
+        self.stop();
+        drop(self.solutions);
+        self.thread
+            .join()
+            .map_err(|_| "the search panicked".to_string())?
+    }
+}
+
+impl Iterator for Mining {
+    type Item = Solution;
+
+    fn next(&mut self) -> Option<Solution> {
+        self.solutions.recv().ok()
+    }
+}
+
+/// Send a solution on, stopping the search if it is good enough or nobody is
+/// listening any more.
+fn send(
+    config: &Config,
+    shutdown: &Shutdown,
+    solutions: &mpsc::Sender<Solution>,
+    solution: Solution,
+) {
+    let rank = solution.rank();
+    if !solution.reserved && config.stop_at_score.is_some_and(|target| rank >= target) {
+        shutdown.request_because("found an address reaching the target score");
+    }
+    if solutions.send(solution).is_err() {
+        shutdown.request();
+    }
+}
+
+fn search_cpu(
+    config: &Config,
+    shutdown: &Shutdown,
+    hashes: &AtomicU64,
+    solutions: &mpsc::Sender<Solution>,
+) {
+    let start_time = Instant::now();
+    cpu_search(
+        config,
+        &Control::new(config),
+        shutdown,
+        hashes,
+        Checkpoint::new(config).position(checkpoint::CPU_STREAM),
+        || {
+            let runtime = start_time.elapsed().as_secs_f64();
+            let total = hashes.load(Ordering::Relaxed) as u128;
+            if let Some(reason) = config.budget_spent(runtime, total) {
+                shutdown.request_because(reason);
+            }
+        },
+        |solution| send(config, shutdown, solutions, solution),
+        |_| {},
+    );
+}
+
+fn search_gpu(
+    config: Config,
+    shutdown: &Shutdown,
+    hashes: &AtomicU64,
+    solutions: &mpsc::Sender<Solution>,
+) -> Result<(), String> {
+    let config = Arc::new(config);
+    let devices: Vec<u8> = [config.gpu_device]
+        .into_iter()
+        .chain(config.extra_gpu_devices.iter().copied())
+        .collect();
+    let power_gate = power::PowerGate::spawn(config.on_battery);
+    let start = Checkpoint::new(&config);
+    let control = Arc::new(Control::new(&config));
+    let received = spawn_gpu_workers(&config, &control, &devices, &start, &power_gate, shutdown);
+
+    let rewards = Reward::new();
+    let targets = targets(&config);
+    let mut checked = 0;
+    // the attempts made on the CPU so far, in hybrid mode
+    let mut cpu_hashes = 0;
+    let mut running = devices.len() + config.hybrid as usize;
+    let start_time = Instant::now();
+    loop {
+        match received.recv_timeout(Duration::from_secs(1)) {
+            Ok(GpuEvent::Started { .. } | GpuEvent::Sampled { .. }) => {}
+            Ok(GpuEvent::Dispatched { work_size, .. }) => {
+                hashes.fetch_add(work_size as u64, Ordering::Relaxed);
+            }
+            Ok(GpuEvent::Searched { hashes: total, .. }) => {
+                let total = total as u64;
+                hashes.fetch_add(total - cpu_hashes, Ordering::Relaxed);
+                cpu_hashes = total;
+            }
+            Ok(GpuEvent::Found(solution)) => send(&config, shutdown, solutions, *solution),
+            Ok(GpuEvent::Solved(solved)) => {
+                let (found, _, _) = gpu_solutions(
+                    &config,
+                    &rewards,
+                    &targets[solved.target],
+                    solved,
+                    &mut checked,
+                );
+                for solution in found {
+                    send(&config, shutdown, solutions, solution);
+                }
+            }
+            Ok(GpuEvent::Stopped) => {
+                running -= 1;
+                if running == 0 {
+                    return Ok(());
+                }
+            }
+            Ok(GpuEvent::Failed { device, error }) => {
+                shutdown.request();
+                return Err(format!("device {device}: {error}"));
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+
+        let runtime = start_time.elapsed().as_secs_f64();
+        let total = hashes.load(Ordering::Relaxed) as u128;
+        if let Some(reason) = config.budget_spent(runtime, total) {
+            shutdown.request_because(reason);
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use crate::Config;
+use alloy_primitives::{hex, Address};
+use tiny_keccak::{Hasher, Keccak};
+
+/// keccak-256 of the creation code of the CREATE3 proxy
+/// (`0x67363d3d37363d34f03d5260086018f3`), which deploys whatever code it is
+/// called with using CREATE.
+pub const CREATE3_PROXY_INIT_CODE_HASH: [u8; 32] =
+    hex!("21c35dbe1b344a2488cf3321d6ce542f8e9f305544ff09e4993a62319a497c1f");
+
+/// How the address of a contract follows from the factory and the salt.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Mode {
+    /// The factory deploys the contract with CREATE2, so the address also
+    /// depends on the init code hash.
+    #[default]
+    Create2,
+    /// The factory deploys a fixed proxy with CREATE2, which then deploys the
+    /// contract with CREATE (from its nonce of 1), so the address does not
+    /// depend on the init code.
+    Create3,
+}
+
+/// The init code hash that goes into the CREATE2 preimage: that of the
+/// contract, or with CREATE3 that of the proxy.
+pub(crate) fn create2_hash(config: &Config) -> &[u8; 32] {
+    match config.mode {
+        Mode::Create2 => &config.init_code_hash,
+        Mode::Create3 => &CREATE3_PROXY_INIT_CODE_HASH,
+    }
+}
+
+/// The init code hashes that go into the CREATE2 preimage, in the order that
+/// solutions are tagged with: that of the contract followed by any others, or
+/// with CREATE3 that of the proxy.
+pub(crate) fn create2_hashes(config: &Config) -> Vec<[u8; 32]> {
+    match config.mode {
+        Mode::Create2 => [config.init_code_hash]
+            .into_iter()
+            .chain(config.extra_init_code_hashes.iter().copied())
+            .collect(),
+        Mode::Create3 => vec![CREATE3_PROXY_INIT_CODE_HASH],
+    }
+}
+
+/// The address that the contract ends up at, given the address derived with
+/// CREATE2.
+pub(crate) fn deployed(config: &Config, create2_address: Address) -> Address {
+    match config.mode {
+        Mode::Create2 => create2_address,
+        Mode::Create3 => proxy_deployment(&create2_address),
+    }
+}
+
+/// The address that a salt deploys the contract to through a factory (with
+/// the given init code hash in the CREATE2 preimage), hashing on the host.
+pub(crate) fn address(
+    config: &Config,
+    factory: &Address,
+    salt: &[u8; 32],
+    create2_hash: &[u8; 32],
+) -> Address {
+    let mut hash = Keccak::v256();
+    hash.update(&[0xff]);
+    hash.update(factory.as_slice());
+    hash.update(salt);
+    hash.update(create2_hash);
+    let mut res = [0; 32];
+    hash.finalize(&mut res);
+    deployed(config, Address::from_slice(&res[12..]))
+}
+
+/// The address of the first contract created by a CREATE3 proxy, hashing
+/// rlp([proxy, 1]) by hand as it is always the same 23 bytes.
+fn proxy_deployment(proxy: &Address) -> Address {
+    let mut hash = Keccak::v256();
+    hash.update(&[0xd6, 0x94]);
+    hash.update(proxy.as_slice());
+    hash.update(&[0x01]);
+    let mut res = [0; 32];
+    hash.finalize(&mut res);
+    Address::from_slice(&res[12..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::keccak256;
+
+    fn config(mode: Mode, init_code_hash: [u8; 32]) -> Config {
+        Config {
+            mode,
+            init_code_hash,
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn proxy_init_code_hash() {
+        let hash = keccak256(hex!("67363d3d37363d34f03d5260086018f3"));
+        assert_eq!(hash, CREATE3_PROXY_INIT_CODE_HASH);
+    }
+
+    // examples 0 and 1 of EIP-1014, with an init code of 0x00
+    #[test]
+    fn create2_address() {
+        let config = config(Mode::Create2, keccak256([0x00]).0);
+        for (factory, expected) in [
+            (Address::ZERO, "0x4D1A2e2bB4F88F0250f26Ffff098B0b30B26BF38"),
+            (
+                "0xdeadbeef00000000000000000000000000000000"
+                    .parse()
+                    .unwrap(),
+                "0xB928f69Bb1D91Cd65274e3c79d8986362984fDA3",
+            ),
+        ] {
+            let derived = address(&config, &factory, &[0; 32], create2_hash(&config));
+            assert_eq!(derived, expected.parse::<Address>().unwrap());
+        }
+    }
+
+    // the widely quoted second contract of 0x6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0
+    #[test]
+    fn proxy_deployment_address() {
+        let proxy = "0x6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0"
+            .parse()
+            .unwrap();
+        let expected: Address = "0x343c43a37d37dff08ae8c4a11544c718abb4fcf8"
+            .parse()
+            .unwrap();
+        assert_eq!(proxy_deployment(&proxy), expected);
+    }
+
+    // the proxy as derived by alloy-primitives, and the contract it deploys
+    #[test]
+    fn create3_address() {
+        let factory: Address = "0x9fBB3DF7C40Da2e5A0dE984fFE2CCB7C47cd0ABf"
+            .parse()
+            .unwrap();
+        let salt = keccak256("create2crunch");
+        let proxy = factory.create2(salt, CREATE3_PROXY_INIT_CODE_HASH);
+        assert_eq!(
+            proxy,
+            "0x4231F18D659d360864d1a57Bf43fa769FD212283"
+                .parse::<Address>()
+                .unwrap()
+        );
+
+        // the init code hash plays no part
+        let config = config(Mode::Create3, [0x11; 32]);
+        let derived = address(&config, &factory, &salt, create2_hash(&config));
+        assert_eq!(
+            derived,
+            "0x26FB5E4ac4084ea3Df4cf6926F7a6A5aaE6943fa"
+                .parse::<Address>()
+                .unwrap()
+        );
+        assert_eq!(deployed(&config, proxy), derived);
+    }
+}
@@ -0,0 +1,166 @@
+use crate::shutdown::Shutdown;
+use crate::Config;
+use ocl::Device;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+// how often the sensors of a device are read
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+// how far below the maximum temperature a paused device has to cool before
+// dispatches resume
+const COOL_DOWN_DEGREES: u32 = 5;
+
+// the OpenCL extensions that give the PCI location of a device
+#[cfg(feature = "nvml")]
+const CL_DEVICE_PCI_BUS_ID_NV: u32 = 0x4008;
+#[cfg(feature = "nvml")]
+const CL_DEVICE_PCI_DOMAIN_ID_NV: u32 = 0x400a;
+const CL_DEVICE_TOPOLOGY_AMD: u32 = 0x4037;
+
+/// The temperature (in °C) and utilization (in percent) of a GPU.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Sample {
+    pub(crate) temperature: u32,
+    pub(crate) utilization: Option<u32>,
+}
+
+impl fmt::Display for Sample {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}°C", self.temperature)?;
+        if let Some(utilization) = self.utilization {
+            write!(f, ", {utilization}% busy")?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads the sensors of a GPU, through NVML for NVIDIA cards (with the `nvml`
+/// feature) or from the amdgpu driver for AMD cards (where ROCm SMI reads
+/// them), so that a run can report how hot each card is and pause dispatches
+/// while it is over `--max-temp`.
+pub(crate) struct Monitor {
+    sensors: Sensors,
+    last: Option<(Instant, Option<Sample>)>,
+}
+
+enum Sensors {
+    #[cfg(feature = "nvml")]
+    Nvml(Box<nvml_wrapper::Nvml>, u32),
+    Amdgpu(PathBuf),
+}
+
+impl Monitor {
+    /// Find the sensors of an OpenCL device by its PCI location, if it has any
+    /// that can be read.
+    pub(crate) fn new(config: &Config, device: u8) -> Option<Self> {
+        let platform = crate::platform(config).ok()?;
+        let device = Device::by_idx_wrap(platform, device as usize).ok()?;
+        let sensors = nvml(&device).or_else(|| amdgpu(&device))?;
+        Some(Self {
+            sensors,
+            last: None,
+        })
+    }
+
+    /// The latest reading, taken again once it is a second old.
+    pub(crate) fn sample(&mut self) -> Option<Sample> {
+        if let Some((taken, sample)) = self.last {
+            if taken.elapsed() < SAMPLE_INTERVAL {
+                return sample;
+            }
+        }
+        let sample = self.sensors.read();
+        self.last = Some((Instant::now(), sample));
+        sample
+    }
+
+    /// Block while the device is at or over `max_temp`, until it has cooled
+    /// down a few degrees below it or the run is asked to stop.
+    pub(crate) fn cool_down(&mut self, device: u8, max_temp: u32, shutdown: &Shutdown) {
+        let hot = |sample: Option<Sample>| sample.map_or(0, |s| s.temperature);
+        let temperature = hot(self.sample());
+        if temperature < max_temp {
+            return;
+        }
+        let resume = max_temp.saturating_sub(COOL_DOWN_DEGREES);
+        info!(
+            device,
+            "at {temperature}°C, pausing until it cools to {resume}°C"
+        );
+        while hot(self.sample()) > resume && !shutdown.requested() {
+            thread::sleep(SAMPLE_INTERVAL);
+        }
+        info!(device, "cooled down, resuming");
+    }
+}
+
+impl Sensors {
+    fn read(&self) -> Option<Sample> {
+        match self {
+            #[cfg(feature = "nvml")]
+            Self::Nvml(nvml, index) => {
+                use nvml_wrapper::enum_wrappers::device::TemperatureSensor;
+                let device = nvml.device_by_index(*index).ok()?;
+                Some(Sample {
+                    temperature: device.temperature(TemperatureSensor::Gpu).ok()?,
+                    utilization: device.utilization_rates().ok().map(|u| u.gpu),
+                })
+            }
+            Self::Amdgpu(path) => {
+                let read = |path: PathBuf| -> Option<u32> {
+                    fs::read_to_string(path).ok()?.trim().parse().ok()
+                };
+                let hwmon = fs::read_dir(path.join("hwmon")).ok()?.flatten().next()?;
+                Some(Sample {
+                    // in millidegrees, at the edge of the die
+                    temperature: read(hwmon.path().join("temp1_input"))? / 1000,
+                    utilization: read(path.join("gpu_busy_percent")),
+                })
+            }
+        }
+    }
+}
+
+// the NVML device on the same PCI bus as an NVIDIA OpenCL device
+#[cfg(feature = "nvml")]
+fn nvml(device: &Device) -> Option<Sensors> {
+    let word = |info| {
+        let bytes = ocl::core::get_device_info_raw(device, info).ok()?;
+        Some(u32::from_ne_bytes(bytes.get(..4)?.try_into().ok()?))
+    };
+    let bus = word(CL_DEVICE_PCI_BUS_ID_NV)?;
+    let domain = word(CL_DEVICE_PCI_DOMAIN_ID_NV).unwrap_or(0);
+
+    let nvml = nvml_wrapper::Nvml::init()
+        .map_err(|e| tracing::debug!("could not load NVML: {e}"))
+        .ok()?;
+    let index = (0..nvml.device_count().ok()?).find(|&index| {
+        nvml.device_by_index(index)
+            .and_then(|device| device.pci_info())
+            .is_ok_and(|pci| pci.bus == bus && pci.domain == domain)
+    })?;
+    Some(Sensors::Nvml(Box::new(nvml), index))
+}
+
+#[cfg(not(feature = "nvml"))]
+fn nvml(_: &Device) -> Option<Sensors> {
+    None
+}
+
+// the sysfs directory of an AMD OpenCL device, from its PCI topology (a
+// 24-byte structure ending with the bus, device and function)
+fn amdgpu(device: &Device) -> Option<Sensors> {
+    let topology = ocl::core::get_device_info_raw(device, CL_DEVICE_TOPOLOGY_AMD).ok()?;
+    let &[bus, slot, function] = topology.get(21..24)? else {
+        return None;
+    };
+    let path = PathBuf::from(format!(
+        "/sys/bus/pci/devices/0000:{bus:02x}:{slot:02x}.{function:x}"
+    ));
+    path.exists().then_some(Sensors::Amdgpu(path))
+}
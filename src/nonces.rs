@@ -0,0 +1,189 @@
+use crate::{score_address, zero_bytes, Reward, Scoring};
+use alloy_primitives::{Address, U256};
+use rayon::prelude::*;
+use separator::Separatable;
+use std::cmp::Reverse;
+use std::error::Error;
+use std::ops::Range;
+use tiny_keccak::{Hasher, Keccak};
+
+/// A nonce of the deployer and the address that a contract created at it
+/// gets, with what it is ranked by.
+struct Found {
+    rank: u128,
+    nonce: u64,
+    address: Address,
+    reward: U256,
+    score: Option<u32>,
+}
+
+/// Search the given nonces of a deployer for the best addresses of the
+/// contracts it would create with CREATE, and print the `top` of them with
+/// the nonce to deploy at. Addresses are ranked by their pattern score if
+/// scoring (leaving out those below the minimum score), and otherwise by the
+/// reward for their zero bytes.
+pub fn nonces(
+    deployer: [u8; 20],
+    nonces: Range<u64>,
+    scoring: Option<Scoring>,
+    top: usize,
+) -> Result<(), Box<dyn Error>> {
+    let deployer = Address::from(deployer);
+    println!(
+        "searching nonces {} to {} of {deployer}",
+        nonces.start.separated_string(),
+        nonces.end.saturating_sub(1).separated_string()
+    );
+
+    let rewards = Reward::new();
+    let best = nonces
+        .into_par_iter()
+        .filter_map(|nonce| {
+            let address = create_address(&deployer, nonce);
+            let (leading, total) = zero_bytes(&address);
+            let reward: U256 = rewards
+                .get(&(leading * 20 + total))
+                .unwrap_or("0")
+                .parse()
+                .unwrap();
+            let score = scoring.map(|s| score_address(&s, &address));
+            let rank = match (scoring, score) {
+                (Some(scoring), Some(score)) if score < scoring.min_score => return None,
+                (_, Some(score)) => score as u128,
+                _ => reward.try_into().unwrap_or(u128::MAX),
+            };
+            (rank != 0).then_some(Found {
+                rank,
+                nonce,
+                address,
+                reward,
+                score,
+            })
+        })
+        .fold(Vec::new, |mut best, found| {
+            keep(&mut best, found, top);
+            best
+        })
+        .reduce(Vec::new, |mut best, other| {
+            for found in other {
+                keep(&mut best, found, top);
+            }
+            best
+        });
+
+    if best.is_empty() {
+        return Err("no nonce in the range gives an efficient address".into());
+    }
+    for found in best {
+        let score = found
+            .score
+            .map(|score| format!(" (score {score})"))
+            .unwrap_or_default();
+        println!(
+            "nonce {} => {} => {}{score}",
+            found.nonce, found.address, found.reward
+        );
+    }
+    Ok(())
+}
+
+/// Add a find to the best ones so far, keeping at most `top` of them in order
+/// of rank (and then of nonce, the lowest being the soonest to deploy at).
+fn keep(best: &mut Vec<Found>, found: Found, top: usize) {
+    let before = |a: &Found| (a.rank, Reverse(a.nonce)) > (found.rank, Reverse(found.nonce));
+    // most finds rank no better than the worst of a full list
+    if best.len() == top && best.last().is_none_or(before) {
+        return;
+    }
+    let at = best.partition_point(before);
+    best.insert(at, found);
+    best.truncate(top);
+}
+
+/// The address of the contract that `deployer` creates with CREATE at
+/// `nonce`: the last 20 bytes of the hash of rlp([deployer, nonce]).
+fn create_address(deployer: &Address, nonce: u64) -> Address {
+    // a nonce is encoded as a single byte below 0x80 (0x80 itself for zero),
+    // and otherwise as its big-endian bytes after their length
+    let bytes = nonce.to_be_bytes();
+    let significant = &bytes[nonce.leading_zeros() as usize / 8..];
+    let mut encoded = [0u8; 9];
+    let encoded = match nonce {
+        0 => &[0x80][..],
+        1..=0x7f => &bytes[7..],
+        _ => {
+            encoded[0] = 0x80 + significant.len() as u8;
+            encoded[1..=significant.len()].copy_from_slice(significant);
+            &encoded[..=significant.len()]
+        }
+    };
+
+    let mut hash = Keccak::v256();
+    hash.update(&[0xc0 + 21 + encoded.len() as u8, 0x94]);
+    hash.update(deployer.as_slice());
+    hash.update(encoded);
+    let mut res = [0; 32];
+    hash.finalize(&mut res);
+    Address::from_slice(&res[12..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{hex, keccak256};
+
+    const DEPLOYER: Address = Address::new(hex!("6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0"));
+
+    // the first contracts of a widely quoted deployer
+    #[test]
+    fn known_addresses() {
+        for (nonce, expected) in [
+            (0, "0xcd234a471b72ba2f1ccf0a70fcaba648a5eecd8d"),
+            (1, "0x343c43a37d37dff08ae8c4a11544c718abb4fcf8"),
+            (2, "0xf778b86fa74e846c4f0a1fbd1335fe81c00a0c91"),
+            (3, "0xfffd933a0bc612844eaf0c6fe3e5b8e9b6c1d19c"),
+        ] {
+            let expected: Address = expected.parse().unwrap();
+            assert_eq!(create_address(&DEPLOYER, nonce), expected, "nonce {nonce}");
+        }
+    }
+
+    // the nonces where the encoding changes, against rlp([deployer, nonce])
+    // written out by hand
+    #[test]
+    fn encoding_boundaries() {
+        for (nonce, prefix, encoded) in [
+            (0, 0xd6, &hex!("80")[..]),
+            (0x7f, 0xd6, &hex!("7f")[..]),
+            (0x80, 0xd7, &hex!("8180")[..]),
+            (0xff, 0xd7, &hex!("81ff")[..]),
+            (0x100, 0xd8, &hex!("820100")[..]),
+            (u64::MAX, 0xde, &hex!("88ffffffffffffffff")[..]),
+        ] {
+            let rlp = [&[prefix, 0x94][..], DEPLOYER.as_slice(), encoded].concat();
+            let expected = Address::from_slice(&keccak256(rlp)[12..]);
+            assert_eq!(
+                create_address(&DEPLOYER, nonce),
+                expected,
+                "nonce {nonce:#x}"
+            );
+        }
+    }
+
+    #[test]
+    fn keeps_the_best() {
+        let found = |rank, nonce| Found {
+            rank,
+            nonce,
+            address: Address::ZERO,
+            reward: U256::ZERO,
+            score: None,
+        };
+        let mut best = Vec::new();
+        for (rank, nonce) in [(1, 0), (3, 1), (2, 2), (3, 3), (1, 4), (3, 0), (4, 5)] {
+            keep(&mut best, found(rank, nonce), 3);
+        }
+        let kept: Vec<_> = best.iter().map(|f| (f.rank, f.nonce)).collect();
+        assert_eq!(kept, [(4, 5), (3, 0), (3, 1)]);
+    }
+}
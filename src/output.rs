@@ -0,0 +1,78 @@
+use fs4::FileExt;
+use serde_json::{json, Value};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const CSV_HEADER: &str = "salt,address,factory,caller,score,pattern_score,reserved\n";
+
+/// A machine-readable record of every result (`--output-file`): the payload
+/// that is posted to the endpoint with the time it was found (`found_at`, in
+/// Unix seconds), as one JSON object per line, or the main
+/// fields as comma-separated values if the file name ends in `.csv`.
+///
+/// Each result is written with a single append under an exclusive lock and
+/// synced to disk before mining carries on, so a crash can at worst cut the
+/// last line short and never loses a result that was reported.
+pub(crate) struct ResultLog {
+    file: File,
+    csv: bool,
+}
+
+impl ResultLog {
+    pub(crate) fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .read(true)
+            .open(path)?;
+        let csv = path.extension().is_some_and(|ext| ext == "csv");
+
+        if csv {
+            file.lock_exclusive()?;
+            let written = file.metadata().and_then(|metadata| match metadata.len() {
+                0 => (&file).write_all(CSV_HEADER.as_bytes()),
+                _ => Ok(()),
+            });
+            FileExt::unlock(&file)?;
+            written?;
+        }
+
+        Ok(Self { file, csv })
+    }
+
+    pub(crate) fn record(&self, payload: &Value) -> io::Result<()> {
+        let line = if self.csv {
+            let field = |key: &str| match &payload[key] {
+                Value::Null => String::new(),
+                Value::String(value) => value.clone(),
+                value => value.to_string(),
+            };
+            format!(
+                "{},{},{},{},{},{},{}\n",
+                field("salt"),
+                field("address"),
+                field("factory"),
+                field("caller"),
+                field("score"),
+                field("pattern_score"),
+                payload["reserved"].as_bool().unwrap_or(false)
+            )
+        } else {
+            let mut payload = payload.clone();
+            payload["found_at"] = json!(SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs());
+            format!("{payload}\n")
+        };
+
+        self.file.lock_exclusive()?;
+        let written = (&self.file)
+            .write_all(line.as_bytes())
+            .and_then(|()| self.file.sync_data());
+        FileExt::unlock(&self.file)?;
+        written
+    }
+}
@@ -0,0 +1,138 @@
+use alloy_primitives::Address;
+use std::str::FromStr;
+
+/// An exact pattern for the 40 nibbles of an address (`--pattern`), for
+/// hunting a vanity prefix or suffix rather than a score. Each nibble is
+/// either fixed or a `?` wildcard, and a single `...` stands for as many
+/// wildcards as it takes to make up 40 nibbles, as in `badc0de...face`.
+///
+/// The pattern is compiled into the kernel as a mask and value for each
+/// 32-bit word of the address, so that every nibble is compared at once.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pattern {
+    value: [u8; 20],
+    mask: [u8; 20],
+}
+
+impl Pattern {
+    pub fn matches(&self, address: &Address) -> bool {
+        address
+            .iter()
+            .zip(self.mask.iter().zip(&self.value))
+            .all(|(byte, (mask, value))| byte & mask == *value)
+    }
+
+    /// The number of nibbles that are fixed.
+    pub fn fixed(&self) -> u32 {
+        self.mask.iter().map(|mask| mask.count_ones() / 4).sum()
+    }
+
+    /// The mask and value of each 32-bit word of the address, as the device
+    /// reads them (little-endian).
+    pub(crate) fn words(&self) -> [(u32, u32); 5] {
+        let word = |bytes: &[u8; 20], i: usize| {
+            u32::from_le_bytes(bytes[i * 4..i * 4 + 4].try_into().unwrap())
+        };
+        std::array::from_fn(|i| (word(&self.mask, i), word(&self.value, i)))
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.strip_prefix("0x").unwrap_or(s);
+        let nibbles = match s.split_once("...") {
+            Some((head, tail)) if head.len() + tail.len() <= 40 => {
+                format!("{head}{}{tail}", "?".repeat(40 - head.len() - tail.len()))
+            }
+            Some(_) => return Err("pattern must be at most 40 nibbles"),
+            None if s.len() == 40 => s.to_string(),
+            None => return Err("pattern must be 40 nibbles (or use ... for the rest)"),
+        };
+
+        let mut pattern = Self {
+            value: [0; 20],
+            mask: [0; 20],
+        };
+        for (i, c) in nibbles.chars().enumerate() {
+            if c == '?' {
+                continue;
+            }
+            let nibble = c
+                .to_digit(16)
+                .ok_or("pattern must be made of hex digits and ? wildcards")?
+                as u8;
+            let shift = if i % 2 == 0 { 4 } else { 0 };
+            pattern.value[i / 2] |= nibble << shift;
+            pattern.mask[i / 2] |= 0xf << shift;
+        }
+        Ok(pattern)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn address(s: &str) -> Address {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_full_patterns() {
+        let pattern: Pattern = "0x0000????????????????????????????????beef"
+            .parse()
+            .unwrap();
+        assert_eq!(pattern.fixed(), 8);
+        assert_eq!(
+            pattern,
+            "0000...beef".parse().unwrap(),
+            "... stands for the same wildcards"
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_patterns() {
+        for s in [
+            "",
+            "0x",
+            "0000beef",
+            &"0".repeat(39),
+            &"0".repeat(41),
+            &format!("{}...{}", "0".repeat(20), "0".repeat(21)),
+            &format!("{}g", "0".repeat(39)),
+            "badc0de...fa.ce",
+            "dead...be...ef",
+        ] {
+            assert!(s.parse::<Pattern>().is_err(), "{s:?} should not parse");
+        }
+    }
+
+    #[test]
+    fn matches_fixed_nibbles() {
+        let pattern: Pattern = "badc0de...fa?e".parse().unwrap();
+        assert_eq!(pattern.fixed(), 10);
+        assert!(pattern.matches(&address("0xbadc0de00000000000000000000000000000fabe")));
+        assert!(pattern.matches(&address("0xBADC0DE123456789abcdef0123456789abcdfa0e")));
+        assert!(!pattern.matches(&address("0xbadc0df00000000000000000000000000000fabe")));
+        assert!(!pattern.matches(&address("0xbadc0de00000000000000000000000000000fabf")));
+
+        // an odd number of fixed nibbles splits a byte
+        let pattern: Pattern = "abc...".parse().unwrap();
+        assert!(pattern.matches(&address("0xabcf000000000000000000000000000000000000")));
+        assert!(!pattern.matches(&address("0xabdf000000000000000000000000000000000000")));
+
+        let anything: Pattern = "...".parse().unwrap();
+        assert_eq!(anything.fixed(), 0);
+        assert!(anything.matches(&Address::ZERO));
+    }
+
+    #[test]
+    fn words_are_little_endian() {
+        let pattern: Pattern = "12345678...".parse().unwrap();
+        let words = pattern.words();
+        assert_eq!(words[0], (0xffff_ffff, 0x7856_3412));
+        assert!(words[1..].iter().all(|&word| word == (0, 0)));
+    }
+}
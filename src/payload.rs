@@ -0,0 +1,331 @@
+use crate::miner::Solution;
+use crate::Config;
+use alloy_primitives::hex;
+use serde_json::{json, Map, Value};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+// the fields that a template can refer to: those of the recorded payload, the
+// init code hash (even when only one is mined) and the parts of the salt
+const FIELDS: &[&str] = &[
+    "salt",
+    "salt_caller",
+    "salt_segment",
+    "salt_nonce",
+    "address",
+    "address_lowercase",
+    "factory",
+    "factory_lowercase",
+    "caller",
+    "caller_lowercase",
+    "init_code_hash",
+    "score",
+    "pattern_score",
+    "reserved",
+    "job_id",
+    "score_breakdown",
+    "deploy_command",
+];
+
+/// The shape of the JSON that results are posted to the endpoints in
+/// (`--payload-format`), so that the miner can feed a collector that expects
+/// something other than the payload it records.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum PayloadFormat {
+    /// The payload as it is written to the output file.
+    #[default]
+    Default,
+    /// Only the salt, address and score, as the original create2crunch
+    /// posted them.
+    Classic,
+    /// The payload that `--payload-template` describes.
+    Custom,
+}
+
+/// A JSON document that results are posted as (`--payload-template`), with
+/// `{{field}}` placeholders for the fields of each result. A string that is
+/// nothing but a placeholder takes the value of the field as it is (a number
+/// stays a number, and a missing field is `null`), while placeholders within
+/// other text are replaced by the text of the field. So `{"saltHex":
+/// "{{salt}}", "minedAddress": "{{address_lowercase}}", "tag": "run-{{job_id}}"}`
+/// renames fields and adds one.
+///
+/// Besides the fields of the recorded payload, a template can refer to the
+/// `init_code_hash` (which is always given) and to the parts of the salt: the
+/// `salt_caller` (its first 20 bytes), the random `salt_segment` and the
+/// `salt_nonce` that the miner stepped through.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PayloadTemplate(Value);
+
+impl PayloadTemplate {
+    /// Load a template from a JSON file, checking that every placeholder
+    /// names a known field.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        let template: Value =
+            serde_json::from_str(&text).map_err(|e| format!("{}: {e}", path.display()))?;
+        let mut unknown = None;
+        visit(&template, &mut |name| {
+            if !FIELDS.contains(&name) {
+                unknown.get_or_insert_with(|| name.to_string());
+            }
+        });
+        if let Some(name) = unknown {
+            return Err(format!(
+                "{} refers to an unknown field {name} (known: {})",
+                path.display(),
+                FIELDS.join(", ")
+            )
+            .into());
+        }
+        Ok(Self(template))
+    }
+
+    fn render(&self, fields: &Map<String, Value>) -> Value {
+        render(&self.0, fields)
+    }
+}
+
+/// What a solution is posted as, given the payload that it is recorded as.
+pub(crate) fn format(config: &Config, payload: Value, solution: &Solution) -> Value {
+    match (config.payload_format, &config.payload_template) {
+        (PayloadFormat::Classic, _) => json!({
+            "salt": payload["salt"],
+            "address": payload["address"],
+            "score": payload["score"],
+        }),
+        (PayloadFormat::Custom, Some(template)) => {
+            template.render(&fields(config, payload, solution))
+        }
+        _ => payload,
+    }
+}
+
+// the fields of a payload that templates can refer to
+fn fields(config: &Config, payload: Value, solution: &Solution) -> Map<String, Value> {
+    let Value::Object(mut fields) = payload else {
+        return Map::new();
+    };
+    let hash = solution
+        .init_code_hash
+        .map_or(config.init_code_hash, |hash| hash.0);
+    fields
+        .entry("init_code_hash")
+        .or_insert_with(|| json!(hex::encode_prefixed(hash)));
+
+    // salts found on the CPU have a 6-byte segment and nonce, and those found
+    // on a GPU a 4-byte segment and an 8-byte nonce
+    let salt = solution.salt.as_slice();
+    let nonce = if solution.device.is_some() { 24 } else { 26 };
+    fields.insert(
+        "salt_caller".into(),
+        json!(hex::encode_prefixed(&salt[..20])),
+    );
+    fields.insert(
+        "salt_segment".into(),
+        json!(hex::encode_prefixed(&salt[20..nonce])),
+    );
+    fields.insert(
+        "salt_nonce".into(),
+        json!(hex::encode_prefixed(&salt[nonce..])),
+    );
+    fields
+}
+
+fn render(template: &Value, fields: &Map<String, Value>) -> Value {
+    match template {
+        Value::String(text) => match placeholder(text) {
+            Some(name) => fields.get(name).cloned().unwrap_or(Value::Null),
+            None => Value::String(interpolate(text, fields)),
+        },
+        Value::Array(items) => items.iter().map(|item| render(item, fields)).collect(),
+        Value::Object(object) => Value::Object(
+            object
+                .iter()
+                .map(|(key, value)| (key.clone(), render(value, fields)))
+                .collect(),
+        ),
+        value => value.clone(),
+    }
+}
+
+// the name of the field that a string is nothing but a placeholder for
+fn placeholder(text: &str) -> Option<&str> {
+    let name = text.strip_prefix("{{")?.strip_suffix("}}")?;
+    (!name.contains("{{") && !name.contains("}}")).then_some(name.trim())
+}
+
+// the text with each placeholder replaced by the text of its field
+fn interpolate(text: &str, fields: &Map<String, Value>) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some((before, name, after)) = next_placeholder(rest) {
+        out.push_str(before);
+        match fields.get(name) {
+            Some(Value::String(value)) => out.push_str(value),
+            Some(Value::Null) | None => {}
+            Some(value) => out.push_str(&value.to_string()),
+        }
+        rest = after;
+    }
+    out.push_str(rest);
+    out
+}
+
+// the text before the first placeholder, the field it names and the text after
+fn next_placeholder(text: &str) -> Option<(&str, &str, &str)> {
+    let (before, rest) = text.split_once("{{")?;
+    let (name, after) = rest.split_once("}}")?;
+    Some((before, name.trim(), after))
+}
+
+// call `f` with the name of every placeholder in a template
+fn visit(template: &Value, f: &mut impl FnMut(&str)) {
+    match template {
+        Value::String(text) => {
+            let mut rest = text.as_str();
+            while let Some((_, name, after)) = next_placeholder(rest) {
+                f(name);
+                rest = after;
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|item| visit(item, f)),
+        Value::Object(object) => object.values().for_each(|value| visit(value, f)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{Address, B256, U256};
+
+    fn solution(device: Option<u8>) -> Solution {
+        let mut salt = [0u8; 32];
+        salt[..20].fill(0xca);
+        salt[20..].copy_from_slice(&hex!("0102030405060708090a0b0c"));
+        Solution {
+            salt: B256::from(salt),
+            address: Address::repeat_byte(0x01),
+            factory: Address::repeat_byte(0x02),
+            caller: Some(Address::repeat_byte(0xca)),
+            init_code_hash: None,
+            reward: U256::from(7),
+            score: Some(42),
+            reserved: false,
+            device,
+        }
+    }
+
+    fn payload() -> Value {
+        json!({
+            "salt": "0xsalt",
+            "address": "0xaddress",
+            "score": 42,
+            "reserved": false,
+            "job_id": null,
+        })
+    }
+
+    fn config(payload_format: PayloadFormat, template: Option<Value>) -> Config {
+        Config {
+            init_code_hash: [0x33; 32],
+            payload_format,
+            payload_template: template.map(PayloadTemplate),
+            ..Config::default()
+        }
+    }
+
+    // a template saved to a file of its own
+    fn load(name: &str, template: &str) -> Result<PayloadTemplate, Box<dyn Error>> {
+        let path =
+            std::env::temp_dir().join(format!("create2crunch-{}-{name}.json", std::process::id()));
+        fs::write(&path, template).unwrap();
+        let loaded = PayloadTemplate::load(&path);
+        fs::remove_file(&path).unwrap();
+        loaded
+    }
+
+    #[test]
+    fn substitutes_placeholders() {
+        let template = json!({
+            "saltHex": "{{salt}}",
+            "points": "{{ score }}",
+            "tag": "run-{{job_id}}-{{score}}",
+            "missing": "{{job_id}}",
+            "nested": [{"flag": "{{reserved}}"}, 3, "plain"],
+        });
+        let config = config(PayloadFormat::Custom, Some(template));
+        assert_eq!(
+            format(&config, payload(), &solution(None)),
+            json!({
+                "saltHex": "0xsalt",
+                "points": 42,
+                "tag": "run--42",
+                "missing": null,
+                "nested": [{"flag": false}, 3, "plain"],
+            })
+        );
+    }
+
+    #[test]
+    fn fills_in_the_parts_of_the_salt() {
+        let template = json!({
+            "caller": "{{salt_caller}}",
+            "segment": "{{salt_segment}}",
+            "nonce": "{{salt_nonce}}",
+            "hash": "{{init_code_hash}}",
+        });
+        let config = config(PayloadFormat::Custom, Some(template));
+        let caller = format!("0x{}", "ca".repeat(20));
+        let hash = format!("0x{}", "33".repeat(32));
+
+        assert_eq!(
+            format(&config, payload(), &solution(None)),
+            json!({
+                "caller": caller,
+                "segment": "0x010203040506",
+                "nonce": "0x0708090a0b0c",
+                "hash": hash,
+            })
+        );
+        assert_eq!(
+            format(&config, payload(), &solution(Some(0))),
+            json!({
+                "caller": caller,
+                "segment": "0x01020304",
+                "nonce": "0x05060708090a0b0c",
+                "hash": hash,
+            })
+        );
+    }
+
+    #[test]
+    fn format_decides_over_template() {
+        let template = Some(json!({"only": "{{salt}}"}));
+        let classic = json!({"salt": "0xsalt", "address": "0xaddress", "score": 42});
+        for (payload_format, template, expected) in [
+            (PayloadFormat::Default, template.clone(), payload()),
+            (PayloadFormat::Classic, template.clone(), classic),
+            (PayloadFormat::Custom, None, payload()),
+            (PayloadFormat::Custom, template, json!({"only": "0xsalt"})),
+        ] {
+            let config = config(payload_format, template);
+            assert_eq!(format(&config, payload(), &solution(None)), expected);
+        }
+    }
+
+    #[test]
+    fn loads_templates() {
+        let template = load("known", r#"{"a": "{{address_lowercase}}-{{salt_nonce}}"}"#).unwrap();
+        assert_eq!(
+            template,
+            PayloadTemplate(json!({"a": "{{address_lowercase}}-{{salt_nonce}}"}))
+        );
+
+        let error = load("unknown", r#"{"a": ["x-{{salt}}", "{{ nonce }}"]}"#).unwrap_err();
+        assert!(error.to_string().contains("unknown field nonce"), "{error}");
+        assert!(load("malformed", r#"{"a": "#).is_err());
+    }
+}
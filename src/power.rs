@@ -0,0 +1,129 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tracing::info;
+
+// how often the power source is checked
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+// while throttled, work is paused for this long out of every two periods
+const THROTTLE_PERIOD: Duration = Duration::from_secs(1);
+
+/// What to do while the machine is running on battery power.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum BatteryAction {
+    /// Keep mining at full speed.
+    #[default]
+    Ignore,
+    /// Mine at half the duty cycle.
+    Throttle,
+    /// Stop mining until the machine is plugged in again.
+    Pause,
+}
+
+/// Whether the machine is running on battery power, or `None` if that cannot
+/// be determined (e.g. on a desktop or an unsupported platform).
+#[cfg(target_os = "linux")]
+pub fn on_battery() -> Option<bool> {
+    let mut battery = false;
+    for entry in std::fs::read_dir("/sys/class/power_supply").ok()?.flatten() {
+        let path = entry.path();
+        let kind = std::fs::read_to_string(path.join("type")).unwrap_or_default();
+        match kind.trim() {
+            "Mains" | "USB" => {
+                let online = std::fs::read_to_string(path.join("online")).unwrap_or_default();
+                if online.trim() == "1" {
+                    return Some(false);
+                }
+            }
+            "Battery" => battery = true,
+            _ => {}
+        }
+    }
+    battery.then_some(true)
+}
+
+/// Whether the machine is running on battery power, or `None` if that cannot
+/// be determined (e.g. on a desktop or an unsupported platform).
+#[cfg(target_os = "macos")]
+pub fn on_battery() -> Option<bool> {
+    let output = std::process::Command::new("pmset")
+        .args(["-g", "batt"])
+        .output()
+        .ok()?;
+    let output = String::from_utf8_lossy(&output.stdout);
+    let source = output.lines().next()?;
+    if source.contains("'Battery Power'") {
+        Some(true)
+    } else if source.contains("'AC Power'") {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Whether the machine is running on battery power, or `None` if that cannot
+/// be determined (e.g. on a desktop or an unsupported platform).
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn on_battery() -> Option<bool> {
+    None
+}
+
+/// A flag that tells workers to hold off, set from a background thread that
+/// watches the power source.
+#[derive(Clone, Default)]
+pub(crate) struct PowerGate(Arc<AtomicBool>);
+
+impl PowerGate {
+    /// Start watching the power source, unless the action is to ignore it.
+    pub(crate) fn spawn(action: BatteryAction) -> Self {
+        let gate = Self::default();
+        if action == BatteryAction::Ignore {
+            return gate;
+        }
+
+        let paused = gate.0.clone();
+        thread::spawn(move || {
+            let mut was_on_battery = false;
+            loop {
+                let battery = on_battery().unwrap_or(false);
+                if battery != was_on_battery {
+                    match (battery, action) {
+                        (true, BatteryAction::Throttle) => {
+                            info!("running on battery power, throttling")
+                        }
+                        (true, _) => info!("running on battery power, pausing"),
+                        (false, _) => info!("running on mains power, resuming"),
+                    }
+                    was_on_battery = battery;
+                }
+
+                match (battery, action) {
+                    (true, BatteryAction::Throttle) => {
+                        let cycles = POLL_INTERVAL.as_secs() / (2 * THROTTLE_PERIOD.as_secs());
+                        for _ in 0..cycles.max(1) {
+                            paused.store(true, Ordering::Relaxed);
+                            thread::sleep(THROTTLE_PERIOD);
+                            paused.store(false, Ordering::Relaxed);
+                            thread::sleep(THROTTLE_PERIOD);
+                        }
+                    }
+                    _ => {
+                        paused.store(battery, Ordering::Relaxed);
+                        thread::sleep(POLL_INTERVAL);
+                    }
+                }
+            }
+        });
+
+        gate
+    }
+
+    /// Block for as long as work is meant to be paused.
+    pub(crate) fn wait(&self) {
+        while self.0.load(Ordering::Relaxed) {
+            thread::sleep(Duration::from_millis(100));
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use std::io;
+
+/// Set the niceness of the current process (0 to 19, higher is lower
+/// priority). On Linux this only applies to the calling thread and threads it
+/// spawns afterwards, so it should be called before any worker threads exist.
+#[cfg(unix)]
+pub fn set_niceness(nice: i32) -> io::Result<()> {
+    // SAFETY: setpriority has no memory safety requirements.
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, nice) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn set_niceness(_nice: i32) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "setting niceness is only supported on unix",
+    ))
+}
@@ -0,0 +1,294 @@
+use crate::control::Tuning;
+use crate::{mode, Config, Reward, Scoring};
+use std::collections::HashMap;
+use std::fmt;
+
+/// The chance that a random address scores at least `score` under the given
+/// rules, worked out from the chance of each nibble being a zero or the target
+/// nibble. Points for uppercase letters are left out, as they depend on
+/// another hash, so this is a lower bound if they are given a weight.
+pub fn score_probability(scoring: &Scoring, score: u32) -> f64 {
+    if score == 0 {
+        return 1.0;
+    }
+    (0..=40)
+        .map(|leading| {
+            // exactly that many zero nibbles, then a nonzero one
+            let zeroes = 16f64.powi(-leading);
+            let ended = if leading < 40 { 15.0 / 16.0 } else { 1.0 };
+            zeroes * ended * rest_probability(scoring, leading as usize, score)
+        })
+        .sum()
+}
+
+// what is known about the nibbles after the leading zeroes so far, once the
+// nibble straight after them is known to be the target (if that is required)
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct Nibbles {
+    count: u32,
+    run: bool,
+    run_end: bool,
+    tail: bool,
+}
+
+// the chance that an address with `leading` zero nibbles reaches `score` on
+// the rest of its nibbles, each of which is the target or not
+fn rest_probability(scoring: &Scoring, leading: usize, score: u32) -> f64 {
+    let mut states = HashMap::from([(
+        Nibbles {
+            count: 0,
+            run: leading + 4 <= 40,
+            run_end: false,
+            // leading zeroes among the last four nibbles rule out the tail
+            tail: leading <= 36,
+        },
+        1.0,
+    )]);
+    for i in leading..40 {
+        // the nibble straight after the zeroes cannot be zero itself
+        let target = if i == leading { 1.0 / 15.0 } else { 1.0 / 16.0 };
+        let mut next = HashMap::with_capacity(states.len() * 2);
+        for (state, probability) in states {
+            for (is_target, chance) in [(true, target), (false, 1.0 - target)] {
+                if i == leading && !is_target && scoring.require_target_first {
+                    continue;
+                }
+                let run_nibble = i < leading + 4;
+                let state = Nibbles {
+                    count: state.count + is_target as u32,
+                    run: state.run && (is_target || !run_nibble),
+                    run_end: state.run_end || (i == leading + 4 && !is_target),
+                    tail: state.tail && (is_target || i < 36),
+                };
+                *next.entry(state).or_insert(0.0) += probability * chance;
+            }
+        }
+        states = next;
+    }
+
+    states
+        .into_iter()
+        .filter(|(state, _)| {
+            let mut points = leading as u32 * scoring.leading_weight;
+            points += state.count * scoring.nibble_weight;
+            if state.run {
+                points += scoring.run_bonus;
+                if state.run_end {
+                    points += scoring.run_end_bonus;
+                }
+            }
+            if state.tail {
+                points += scoring.tail_bonus;
+            }
+            points >= score
+        })
+        .map(|(_, probability)| probability)
+        .sum()
+}
+
+/// The chance that a random address earns at least `reward` for its leading
+/// and total zero bytes.
+pub fn reward_probability(rewards: &Reward, reward: u128) -> f64 {
+    let zero: f64 = 1.0 / 256.0;
+    let mut probability = 0.0;
+    for leading in 0..=20 {
+        for total in leading..=20 {
+            let earned = rewards
+                .get(&(leading * 20 + total))
+                .and_then(|earned| earned.parse::<u128>().ok());
+            if earned.is_none_or(|earned| earned < reward) {
+                continue;
+            }
+
+            // exactly that many leading zero bytes, then a nonzero one, and
+            // the rest of the zero bytes anywhere after it
+            if leading == 20 {
+                probability += zero.powi(20);
+                continue;
+            }
+            let rest = 19 - leading;
+            let others = total - leading;
+            probability += zero.powi(leading as i32)
+                * (1.0 - zero)
+                * choose(rest, others)
+                * zero.powi(others as i32)
+                * (1.0 - zero).powi((rest - others) as i32);
+        }
+    }
+    probability
+}
+
+fn choose(n: usize, k: usize) -> f64 {
+    (0..k).fold(1.0, |product, i| product * (n - i) as f64 / (i + 1) as f64)
+}
+
+/// How long an address ranking at least `rank` (a pattern score if scoring,
+/// or else a reward) can be expected to take at the current rate.
+pub(crate) struct Estimate {
+    pub(crate) rank: u128,
+    pub(crate) probability: f64,
+    // addresses derived per second
+    pub(crate) rate: f64,
+}
+
+impl Estimate {
+    /// The expected number of seconds between such addresses.
+    pub(crate) fn expected_secs(&self) -> f64 {
+        1.0 / (self.probability * self.rate)
+    }
+}
+
+impl fmt::Display for Estimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self.expected_secs();
+        let every = match secs {
+            secs if secs < 120.0 => format!("{secs:.0}s"),
+            secs if secs < 7200.0 => format!("{:.0}m", secs / 60.0),
+            secs if secs < 172_800.0 => format!("{:.0}h", secs / 3600.0),
+            secs if secs < 63_072_000.0 => format!("{:.0}d", secs / 86400.0),
+            secs => format!("{:.0}y", secs / 31_536_000.0),
+        };
+        let (rate, unit) = match self.rate {
+            rate if rate >= 1e9 => (rate / 1e9, "GH/s"),
+            rate if rate >= 1e6 => (rate / 1e6, "MH/s"),
+            rate if rate >= 1e3 => (rate / 1e3, "kH/s"),
+            rate => (rate, "H/s"),
+        };
+        write!(
+            f,
+            "score ≥ {} expected every ~{every} at {rate:.1} {unit}",
+            self.rank
+        )
+    }
+}
+
+/// An estimate of the time to the target score (the current `stop_at_score`),
+/// or else to beating the best address found so far, after `attempts` in `uptime`
+/// seconds. There is none before the rate is known, with a pattern or WASM
+/// scorer (whose odds are not known), or for a score that cannot be reached.
+pub(crate) fn estimate(
+    config: &Config,
+    tuning: &Tuning,
+    best: Option<u128>,
+    attempts: u128,
+    uptime: f64,
+) -> Option<Estimate> {
+    #[cfg(feature = "wasm")]
+    if config.scorer.is_some() {
+        return None;
+    }
+    if config.pattern.is_some() || attempts == 0 {
+        return None;
+    }
+    let rank = tuning.stop_at_score.or(best.map(|best| best + 1))?;
+    let probability = match &config.scoring {
+        Some(scoring) => score_probability(scoring, rank.try_into().ok()?),
+        None => reward_probability(&Reward::new(), rank),
+    };
+
+    // every attempt derives an address for each init code hash
+    let hashes = mode::create2_hashes(config).len() as f64;
+    let rate = attempts as f64 * hashes / uptime.max(1.0);
+    (probability > 0.0).then_some(Estimate {
+        rank,
+        probability,
+        rate,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // rules under which nothing scores, for tests to give points to
+    fn nothing() -> Scoring {
+        Scoring {
+            leading_weight: 0,
+            run_bonus: 0,
+            run_end_bonus: 0,
+            tail_bonus: 0,
+            nibble_weight: 0,
+            require_target_first: false,
+            ..Scoring::default()
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        let error = ((actual - expected) / expected).abs();
+        assert!(error < 1e-9, "{actual} is not {expected}");
+    }
+
+    #[test]
+    fn leading_zeroes() {
+        let scoring = Scoring {
+            leading_weight: 10,
+            ..nothing()
+        };
+        assert_eq!(score_probability(&scoring, 0), 1.0);
+        assert_close(score_probability(&scoring, 10), 1.0 / 16.0);
+        // one leading zero byte
+        assert_close(score_probability(&scoring, 20), 1.0 / 256.0);
+        assert_close(score_probability(&scoring, 400), 16f64.powi(-40));
+        assert_eq!(score_probability(&scoring, 401), 0.0);
+    }
+
+    #[test]
+    fn target_first() {
+        // a zero nibble, then the target out of the 15 nonzero ones
+        let scoring = Scoring {
+            require_target_first: true,
+            ..Scoring {
+                leading_weight: 10,
+                ..nothing()
+            }
+        };
+        let expected: f64 = (1..40).map(|leading| 16f64.powi(-leading - 1)).sum();
+        assert_close(score_probability(&scoring, 10), expected + 16f64.powi(-40));
+    }
+
+    #[test]
+    fn nibbles_and_tail() {
+        let scoring = Scoring {
+            nibble_weight: 1,
+            ..nothing()
+        };
+        assert_close(
+            score_probability(&scoring, 1),
+            1.0 - (15.0f64 / 16.0).powi(40),
+        );
+
+        let scoring = Scoring {
+            tail_bonus: 20,
+            ..nothing()
+        };
+        assert_close(score_probability(&scoring, 20), 16f64.powi(-4));
+    }
+
+    #[test]
+    fn rewards() {
+        let rewards = Reward::new();
+        assert_eq!(reward_probability(&rewards, u128::MAX), 0.0);
+        let mut last = 1.0;
+        for reward in [4, 454, 57926, 9100294] {
+            let probability = reward_probability(&rewards, reward);
+            assert!(probability > 0.0 && probability < last, "{reward}");
+            last = probability;
+        }
+        assert_eq!(choose(20, 0), 1.0);
+        assert_eq!(choose(20, 5), 15504.0);
+    }
+
+    #[test]
+    fn expected_time() {
+        let estimate = Estimate {
+            rank: 20,
+            probability: 1.0 / 256.0,
+            rate: 2.56e6,
+        };
+        assert_close(estimate.expected_secs(), 1e-4);
+        assert_eq!(
+            estimate.to_string(),
+            "score ≥ 20 expected every ~0s at 2.6 MH/s"
+        );
+    }
+}
@@ -0,0 +1,476 @@
+use crate::checkpoint::{Checkpoint, Position};
+use crate::control::Control;
+#[cfg(feature = "deploy")]
+use crate::deploy::Deployer;
+use crate::miner::Solution;
+use crate::rpc::{Mismatch, Verifier};
+use crate::shutdown::Shutdown;
+use crate::{
+    db, heartbeat, improves, leaderboard, mode, monitor, output, output_file, output_path, payload,
+    print_summary, score_address_detailed, status, submit, Config,
+};
+use alloy_primitives::Address;
+use fs4::FileExt;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+/// Everything the command line tool does with the solutions of a search:
+/// writing them to the results file of their factory (and to the output file,
+/// database and leaderboard, if given), posting them to the endpoints, and
+/// keeping track of the best one. It also reports on the progress of the run
+/// to a coordinator and the status server, stops the run once its budget is
+/// spent, keeps the checkpoint of a deterministic search, and holds the
+/// parameters of the run that can be changed while it runs.
+pub(crate) struct Recorder<'a> {
+    config: &'a Config,
+    shutdown: Shutdown,
+    files: Vec<File>,
+    submitter: Option<submit::Submitter>,
+    result_log: Option<output::ResultLog>,
+    result_db: Option<db::ResultDb>,
+    leaderboard: Option<leaderboard::Leaderboard>,
+    heartbeat: Option<heartbeat::Heartbeat>,
+    status: Option<status::StatusServer>,
+    verifier: Option<Verifier>,
+    #[cfg(feature = "deploy")]
+    deployer: Option<Deployer>,
+    control: Arc<Control>,
+    found: AtomicU64,
+    // GPU solutions discarded for an address that the host derives
+    // differently, or that does not meet the criteria of the search
+    mismatched: AtomicU64,
+    rejected: AtomicU64,
+    // the name of each device (255 for the CPU), once it is set up, and the
+    // latest reading of its sensors
+    names: Mutex<BTreeMap<u8, String>>,
+    samples: Mutex<BTreeMap<u8, monitor::Sample>>,
+    rollovers: Mutex<BTreeMap<u8, u64>>,
+    // the best address found this run (by pattern score, or else by reward),
+    // and the best score submitted so far
+    best: Mutex<Option<(u128, String)>>,
+    best_submitted: Mutex<Option<u128>>,
+    // the progress of a deterministic search, and when it was last saved
+    checkpoint: Option<Mutex<Checkpoint>>,
+    saved: Mutex<Instant>,
+}
+
+impl<'a> Recorder<'a> {
+    pub(crate) fn new(config: &'a Config, shutdown: Shutdown) -> Result<Self, Box<dyn Error>> {
+        // (create if necessary) and open a file per factory where found salts will be written
+        let hashes = mode::create2_hashes(config);
+        let files: Vec<File> = config
+            .factory_addresses
+            .iter()
+            .flat_map(|factory| hashes.iter().map(move |hash| (factory, hash)))
+            .map(|(factory, hash)| output_file(&output_path(config, factory, hash)))
+            .collect();
+
+        // Create a submitter if endpoints are provided, retrying failures from a spool
+        let submitter = (!config.endpoint_urls.is_empty())
+            .then(|| {
+                submit::Submitter::new(
+                    &config.endpoint_urls,
+                    &config.spool,
+                    config.batch_size,
+                    Duration::from_secs(config.batch_interval_secs),
+                    &config.headers,
+                    config.ws_endpoint.as_deref(),
+                    config.max_memory,
+                )
+            })
+            .transpose()?;
+
+        // append every result to the output file if one is given
+        let result_log = config
+            .output_file
+            .as_deref()
+            .map(output::ResultLog::open)
+            .transpose()?;
+        let result_db = config.db.as_deref().map(db::ResultDb::open).transpose()?;
+        let leaderboard = config
+            .leaderboard
+            .as_deref()
+            .map(|path| leaderboard::Leaderboard::open(path, config.leaderboard_size))
+            .transpose()?;
+
+        // report on progress to a coordinator if asked to
+        let heartbeat = config
+            .heartbeat_url
+            .clone()
+            .map(|url| {
+                heartbeat::Heartbeat::new(
+                    url,
+                    Duration::from_secs(config.heartbeat_interval_secs),
+                    &config.headers,
+                )
+            })
+            .transpose()?;
+        let control = Arc::new(Control::new(config));
+        let status = config
+            .status_addr
+            .as_deref()
+            .map(|addr| status::StatusServer::start(addr, control.clone()))
+            .transpose()?;
+
+        // check results against the factory if given a node to ask
+        let verifier = config
+            .rpc_url
+            .clone()
+            .map(|url| Verifier::new(url, config))
+            .transpose()?;
+
+        // deploy the first address to reach the target score if asked to
+        #[cfg(feature = "deploy")]
+        let deployer = config
+            .deploy
+            .as_ref()
+            .zip(config.rpc_url.as_deref())
+            .map(|(deployment, url)| {
+                Deployer::new(
+                    url,
+                    deployment,
+                    config.init_code.as_deref().unwrap_or_default(),
+                )
+            })
+            .transpose()?;
+
+        // carry on from the checkpoint of a deterministic search if resuming
+        let checkpoint = config
+            .deterministic
+            .then(|| Checkpoint::load(config))
+            .transpose()?
+            .map(Mutex::new);
+
+        Ok(Self {
+            config,
+            shutdown,
+            files,
+            submitter,
+            result_log,
+            result_db,
+            leaderboard,
+            heartbeat,
+            status,
+            verifier,
+            #[cfg(feature = "deploy")]
+            deployer,
+            control,
+            found: AtomicU64::new(0),
+            mismatched: AtomicU64::new(0),
+            rejected: AtomicU64::new(0),
+            names: Mutex::new(BTreeMap::from([(255, "CPU".to_string())])),
+            samples: Mutex::new(BTreeMap::new()),
+            rollovers: Mutex::new(BTreeMap::new()),
+            best: Mutex::new(None),
+            best_submitted: Mutex::new(config.current_best),
+            checkpoint,
+            saved: Mutex::new(Instant::now()),
+        })
+    }
+
+    /// Record a solution, stopping the run if it is good enough. A solution
+    /// that the factory derives a different address for is an error.
+    pub(crate) fn record(&self, solution: &Solution) -> Result<(), String> {
+        if let Some(verifier) = &self.verifier {
+            match verifier.verify(solution) {
+                Ok(()) => debug!(salt = %solution.salt, "the factory agrees"),
+                Err(Mismatch::Address(address)) => {
+                    return Err(format!(
+                        "the factory derives {address} from salt {} rather than {}; \
+                         check the factory, caller, mode and init code hash",
+                        solution.salt, solution.address
+                    ))
+                }
+                Err(Mismatch::Failed(error)) => {
+                    warn!(
+                        "could not check salt {} with the factory: {error}",
+                        solution.salt
+                    )
+                }
+            }
+        }
+
+        let line = solution.line();
+        info!(
+            address = %solution.address,
+            factory = %solution.factory,
+            score = solution.score,
+            reserved = solution.reserved,
+            "{line}"
+        );
+
+        // create a lock on the file of the factory (and init code hash) before
+        // writing
+        let factory = self
+            .config
+            .factory_addresses
+            .iter()
+            .position(|factory| solution.factory == Address::from(*factory))
+            .unwrap();
+        let hash = solution.init_code_hash.map_or(0, |hash| {
+            mode::create2_hashes(self.config)
+                .iter()
+                .position(|create2_hash| hash == *create2_hash)
+                .unwrap()
+        });
+        let index = factory * (1 + self.config.extra_init_code_hashes.len()) + hash;
+        let file = &self.files[index];
+        file.lock_exclusive().expect("Couldn't lock file.");
+
+        // write the result to file
+        writeln!(&self.files[index], "{line}")
+            .expect("Couldn't write to `efficient_addresses.txt` file.");
+
+        // release the file lock
+        FileExt::unlock(file).expect("Couldn't unlock file.");
+
+        // Record the result and send it to the configured endpoints if any
+        let mut payload = solution.payload(self.config.job_id.as_deref());
+        #[cfg(feature = "wasm")]
+        let scoring = self.config.scoring.filter(|_| self.config.scorer.is_none());
+        #[cfg(not(feature = "wasm"))]
+        let scoring = self.config.scoring;
+        if let Some(scoring) = &scoring {
+            let breakdown = score_address_detailed(scoring, &solution.address);
+            payload["score_breakdown"] = json!(breakdown);
+        }
+        if let Some(abi) = self.config.factory_abi {
+            // the init code is only known for the main init code hash
+            let init_code = match solution.init_code_hash {
+                Some(hash) if hash != self.config.init_code_hash => None,
+                _ => self.config.init_code.as_deref(),
+            };
+            let command = abi.cast_command(solution, init_code);
+            debug!("deploy with: {command}");
+            payload["deploy_command"] = json!(command);
+        }
+        if let Some(log) = &self.result_log {
+            log.record(&payload)
+                .map_err(|e| format!("could not write to the output file: {e}"))?;
+        }
+        if let Some(db) = &self.result_db {
+            db.record(&payload, solution.device)
+                .map_err(|e| format!("could not write to the database: {e}"))?;
+        }
+        if let Some(leaderboard) = &self.leaderboard {
+            leaderboard
+                .offer(solution)
+                .map_err(|e| format!("could not write the leaderboard: {e}"))?;
+        }
+        let rank = solution.rank();
+        let tuning = self.control.tuning();
+        if let Some(submitter) = &self.submitter {
+            if !tuning.only_improvements || improves(&mut self.best_submitted.lock().unwrap(), rank)
+            {
+                submitter.submit(payload::format(self.config, payload, solution));
+            }
+        }
+
+        // keep track of the best address of the run, stopping (and deploying
+        // the first such address, if asked to) once one is good enough
+        self.found.fetch_add(1, Ordering::Relaxed);
+        if solution.reserved {
+            return Ok(());
+        }
+        if tuning.stop_at_score.is_some_and(|target| rank >= target) {
+            self.shutdown
+                .request_because("found an address reaching the target score");
+            #[cfg(feature = "deploy")]
+            if let Some(deployer) = &self.deployer {
+                deployer
+                    .deploy(solution)
+                    .map_err(|e| format!("could not deploy {}: {e}", solution.address))?;
+            }
+        }
+        let mut best = self.best.lock().unwrap();
+        if best.as_ref().is_none_or(|(best, _)| rank > *best) {
+            *best = Some((rank, line));
+        }
+        Ok(())
+    }
+
+    /// The parameters of the run that can be changed while it runs.
+    pub(crate) fn control(&self) -> Arc<Control> {
+        self.control.clone()
+    }
+
+    /// Where a deterministic search is to carry on from.
+    pub(crate) fn checkpoint(&self) -> Checkpoint {
+        match &self.checkpoint {
+            Some(checkpoint) => checkpoint.lock().unwrap().clone(),
+            None => Checkpoint::new(self.config),
+        }
+    }
+
+    /// Take note that a stream of a deterministic search has searched (and
+    /// recorded the solutions of) everything before `position`.
+    pub(crate) fn advance(&self, stream: String, position: Position) {
+        if let Some(checkpoint) = &self.checkpoint {
+            checkpoint.lock().unwrap().advance(stream, position);
+        }
+    }
+
+    /// Take note of the attempts made so far on each device (255 for the CPU)
+    /// after `runtime` seconds: stop once the budget for the run is spent, let
+    /// a coordinator (or orchestrator) know how the run is going, and save the
+    /// checkpoint when it is due.
+    pub(crate) fn progress(&self, runtime: f64, devices: &[(u8, u128)]) {
+        let hashes = devices.iter().map(|(_, hashes)| hashes).sum();
+        if let Some(reason) = self.config.budget_spent(runtime, hashes) {
+            self.shutdown.request_because(reason);
+        }
+
+        let interval = Duration::from_secs(self.config.checkpoint_interval_secs);
+        let mut saved = self.saved.lock().unwrap();
+        if self.checkpoint.is_some() && saved.elapsed() >= interval {
+            *saved = Instant::now();
+            if let Err(error) = self.save_checkpoint() {
+                warn!("could not save the checkpoint: {error}");
+            }
+        }
+        drop(saved);
+
+        if let Some(heartbeat) = self.heartbeat.as_ref().filter(|h| h.due()) {
+            let best = self.best_rank();
+            let mut report = heartbeat::report(self.config, runtime, devices, self.found(), best);
+            self.add_discarded(&mut report);
+            self.add_devices(&mut report);
+            heartbeat.send(report, devices);
+        }
+        if let Some(status) = self.status.as_ref().filter(|s| s.due()) {
+            let mut report = status::report(
+                self.config,
+                &self.control.tuning(),
+                runtime,
+                devices,
+                self.found(),
+                self.best.lock().unwrap().as_ref(),
+                self.submitter.as_ref().and_then(|s| s.last_submitted()),
+            );
+            self.add_discarded(&mut report);
+            self.add_devices(&mut report);
+            status.publish(report);
+        }
+    }
+
+    /// Take note of GPU solutions that were discarded, as the host derived a
+    /// different address (`mismatched`) or the address does not meet the
+    /// criteria of the search (`rejected`).
+    pub(crate) fn discard(&self, mismatched: u64, rejected: u64) {
+        self.mismatched.fetch_add(mismatched, Ordering::Relaxed);
+        self.rejected.fetch_add(rejected, Ordering::Relaxed);
+    }
+
+    pub(crate) fn mismatched(&self) -> u64 {
+        self.mismatched.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn rejected(&self) -> u64 {
+        self.rejected.load(Ordering::Relaxed)
+    }
+
+    // add the discarded solutions to a report, so that a faulty kernel shows
+    fn add_discarded(&self, report: &mut Value) {
+        report["mismatched"] = json!(self.mismatched());
+        report["rejected"] = json!(self.rejected());
+    }
+
+    // add the name of each device, its temperature and utilization if they
+    // can be read and how many times its nonces have rolled over, to its entry
+    // in a report
+    fn add_devices(&self, report: &mut Value) {
+        let names = self.names.lock().unwrap();
+        let samples = self.samples.lock().unwrap();
+        let rollovers = self.rollovers.lock().unwrap();
+        for entry in report["devices"].as_array_mut().into_iter().flatten() {
+            let Some(device) = entry["device"].as_u64().map(|d| d as u8) else {
+                continue;
+            };
+            entry["name"] = json!(names.get(&device));
+            let sample = samples.get(&device);
+            entry["temperature"] = json!(sample.map(|s| s.temperature));
+            entry["utilization"] = json!(sample.and_then(|s| s.utilization));
+            entry["rollovers"] = json!(rollovers.get(&device).copied().unwrap_or(0));
+        }
+    }
+
+    /// Take note of the latest reading of the sensors of a device, for reports.
+    pub(crate) fn sample_device(&self, device: u8, sample: monitor::Sample) {
+        self.samples.lock().unwrap().insert(device, sample);
+    }
+
+    /// Count a salt segment whose nonces a device has run out of, for reports.
+    pub(crate) fn roll_over(&self, device: u8) {
+        *self.rollovers.lock().unwrap().entry(device).or_insert(0) += 1;
+    }
+
+    /// Take note of the name of a device once it is set up, for reports.
+    pub(crate) fn name_device(&self, device: u8, name: &str) {
+        self.names.lock().unwrap().insert(device, name.to_string());
+    }
+
+    pub(crate) fn found(&self) -> u64 {
+        self.found.load(Ordering::Relaxed)
+    }
+
+    /// The rank of the best address found this run.
+    pub(crate) fn best_rank(&self) -> Option<u128> {
+        self.best.lock().unwrap().as_ref().map(|(rank, _)| *rank)
+    }
+
+    pub(crate) fn best(&self) -> Option<String> {
+        self.best
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|(_, best)| best.clone())
+    }
+
+    /// The outcome of the most recent post to each endpoint, if any.
+    pub(crate) fn submission(&self) -> Option<String> {
+        self.submitter.as_ref().and_then(|s| s.last_status())
+    }
+
+    fn save_checkpoint(&self) -> std::io::Result<()> {
+        match &self.checkpoint {
+            Some(checkpoint) => checkpoint.lock().unwrap().save(&self.config.checkpoint),
+            None => Ok(()),
+        }
+    }
+
+    /// Post whatever is still queued, save the checkpoint and sum up a run of
+    /// `runtime` seconds and `hashes` attempts.
+    pub(crate) fn finish(self, runtime: f64, hashes: u128) -> std::io::Result<()> {
+        if let Some(submitter) = self.submitter {
+            submitter.finish();
+        }
+        for file in &self.files {
+            file.sync_all()?;
+        }
+        if let Some(checkpoint) = &self.checkpoint {
+            checkpoint.lock().unwrap().save(&self.config.checkpoint)?;
+            info!(
+                "saved the checkpoint to {}",
+                self.config.checkpoint.display()
+            );
+        }
+        print_summary(
+            runtime,
+            hashes,
+            self.found.into_inner(),
+            self.best
+                .into_inner()
+                .unwrap()
+                .map(|(_, best)| best)
+                .as_deref(),
+        );
+        Ok(())
+    }
+}
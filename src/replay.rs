@@ -0,0 +1,370 @@
+use crate::checkpoint::{self, Checkpoint};
+use crate::control::Tuning;
+use crate::{
+    local_workers, mode, pro_ques, qualifies, salt, salt_segment, score_address, solution_slots,
+    targets, zero_bytes, Config, Reward, SaltMode, CHECKPOINT_CHUNK, HISTOGRAM_BUCKETS,
+};
+use alloy_primitives::{hex, Address};
+use ocl::{Buffer, MemFlags};
+use rayon::prelude::*;
+use std::error::Error;
+
+/// Search a stretch of the salt space again, as the search described by the
+/// Config did, and print every address in it that qualifies (by the pattern,
+/// the minimum score or the zero byte thresholds, as on the device).
+///
+/// The stretch is given by its `first` salt and the number of salts in it:
+/// on the CPU (device 255) the 6-byte nonce at the end of the salt is stepped
+/// by the nonce stride, while on a GPU the salt is the 4-byte segment after
+/// the caller, the work item (4 bytes) and the dispatch nonce (4 bytes), and
+/// `count` work items from the first one are dispatched again on the device,
+/// which must report exactly the salts that qualify on the host. A single
+/// salt (a `count` of one) that does not qualify is an error.
+pub fn replay(config: &Config, first: [u8; 32], count: u64) -> Result<(), Box<dyn Error>> {
+    if count == 0 {
+        return Err("nothing to replay".into());
+    }
+    let head: [u8; 20] = first[..20].try_into().unwrap();
+    if config.salt_mode == SaltMode::Free && !salt::replay_head(head) {
+        return Err("the head of free salts has already been picked".into());
+    }
+    let targets: Vec<_> = targets(config)
+        .into_iter()
+        .enumerate()
+        .filter(|(_, (_, _, caller))| *caller == head)
+        .collect();
+    if targets.is_empty() {
+        return Err(format!(
+            "no caller of the search starts the salt 0x{}",
+            hex::encode(first)
+        )
+        .into());
+    }
+
+    let tuning = Tuning::new(config);
+    let hashes = mode::create2_hashes(config);
+    let salts = salts(config, first, count)?;
+    println!(
+        "replaying {count} salt(s) from 0x{} on {}",
+        hex::encode(first),
+        match config.gpu_device {
+            255 => "the host".to_string(),
+            device => format!("device {device}"),
+        }
+    );
+
+    // derive every address on the host, in the order the device reports them
+    let mut expected: Vec<(usize, [u8; 32], usize, Address)> = targets
+        .par_iter()
+        .flat_map(|&(target, (_, factory, _))| {
+            let factory = Address::from(factory);
+            let (hashes, tuning) = (&hashes, &tuning);
+            salts.par_iter().flat_map_iter(move |salt| {
+                hashes
+                    .iter()
+                    .enumerate()
+                    .filter_map(move |(hash, create2_hash)| {
+                        let address = mode::address(config, &factory, salt, create2_hash);
+                        qualifies(config, tuning, &address)
+                            .then_some((target, *salt, hash, address))
+                    })
+            })
+        })
+        .collect();
+    expected.sort_unstable_by_key(|&(target, salt, hash, _)| (target, salt, hash));
+
+    let rewards = Reward::new();
+    for &(target, salt, hash, address) in &expected {
+        let (_, (_, factory, _)) = targets.iter().find(|(t, _)| *t == target).unwrap();
+        let (leading, total) = zero_bytes(&address);
+        let reward = rewards.get(&(leading * 20 + total)).unwrap_or("0");
+        let score = config
+            .scoring
+            .map(|scoring| format!(", score {}", score_address(&scoring, &address)))
+            .unwrap_or_default();
+        let hash = match hashes.len() {
+            1 => String::new(),
+            _ => format!(", init code hash 0x{}", hex::encode(hashes[hash])),
+        };
+        println!(
+            "0x{} => {address} (factory {}, reward {reward}, {leading} leading / {total} total \
+             zero bytes{score}{hash})",
+            hex::encode(salt),
+            Address::from(*factory),
+        );
+    }
+
+    if config.gpu_device != 255 {
+        let mut divergences = 0;
+        for &(target, _) in &targets {
+            let expected: Vec<_> = expected
+                .iter()
+                .filter(|&&(t, ..)| t == target)
+                .map(|&(_, salt, hash, _)| (solution(&salt), hash as u32))
+                .collect();
+            divergences += dispatch(config, &tuning, target, first, count, &expected)?;
+        }
+        if divergences != 0 {
+            return Err(format!(
+                "device {} diverged from the host {divergences} time(s)",
+                config.gpu_device
+            )
+            .into());
+        }
+        println!("reproduced on device {}", config.gpu_device);
+    }
+
+    match (count, expected.len()) {
+        (1, 0) => Err("the salt does not produce an address that qualifies".into()),
+        (_, found) => {
+            println!("{found} address(es) qualify");
+            Ok(())
+        }
+    }
+}
+
+/// The first salt of the stretch that starts at `nonce` in a salt `segment`
+/// (the bytes after the caller), for the first caller of the search. On a GPU
+/// the nonce is the dispatch nonce (upper 4 bytes) and the first work item
+/// (lower 4 bytes).
+pub(crate) fn segment_start(
+    config: &Config,
+    segment: &[u8],
+    nonce: u64,
+) -> Result<[u8; 32], Box<dyn Error>> {
+    let len = match config.gpu_device {
+        255 => 6,
+        _ => 4,
+    };
+    if segment.len() != len {
+        return Err(format!("the salt segment is {len} bytes on this device").into());
+    }
+    let mut salt = [0; 32];
+    salt[..20].copy_from_slice(&first_head(config)?);
+    salt[20..20 + len].copy_from_slice(segment);
+    salt[20 + len..].copy_from_slice(&nonce.to_le_bytes()[..12 - len]);
+    Ok(salt)
+}
+
+// the head of the salts of a search that does not pick one at random
+fn first_head(config: &Config) -> Result<[u8; 20], Box<dyn Error>> {
+    if config.salt_mode == SaltMode::Free && !config.deterministic {
+        return Err(
+            "free salts are random unless the search is deterministic, so give a --salt".into(),
+        );
+    }
+    Ok(salt::heads(config)[0])
+}
+
+/// The first salt of the stretch that the given stream of a deterministic
+/// search has reached in its checkpoint (`cpu`, or `<device>/<target>`).
+pub(crate) fn stream_start(config: &Config, stream: &str) -> Result<[u8; 32], Box<dyn Error>> {
+    if !config.deterministic {
+        return Err("only a deterministic search (--deterministic) has streams".into());
+    }
+    let position = Checkpoint::open(config)?.position(stream);
+    let prefix = &config.salt_prefix;
+    let nonce_start = config.nonce_start.unwrap_or(0);
+    let targets = targets(config);
+    let mut salt = [0; 32];
+
+    if stream == checkpoint::CPU_STREAM {
+        // the CPU steps through the nonces of every target in turn
+        let (target, nonce) = (
+            position.offset % targets.len() as u64,
+            position.offset / targets.len() as u64,
+        );
+        let segment = salt_segment::<6>(
+            prefix,
+            Some(position.segment),
+            config.worker_id as u64,
+            config.worker_count as u64,
+        );
+        salt[..20].copy_from_slice(&targets[target as usize].2);
+        salt[20..26].copy_from_slice(&segment[..]);
+        salt[26..].copy_from_slice(&(nonce_start + nonce * config.nonce_stride).to_le_bytes()[..6]);
+        return Ok(salt);
+    }
+
+    let invalid = || format!("invalid stream {stream} (cpu, or <device>/<target>)");
+    let (device, target) = stream.split_once('/').ok_or_else(invalid)?;
+    let device: u8 = device.parse().map_err(|_| invalid())?;
+    let target: usize = target.parse().map_err(|_| invalid())?;
+    let (_, _, caller) = targets.get(target).ok_or_else(invalid)?;
+    let worker = [config.gpu_device]
+        .iter()
+        .chain(&config.extra_gpu_devices)
+        .position(|&d| d == device)
+        .ok_or_else(|| format!("device {device} is not one of the devices of the search"))?;
+
+    // each device walks its own share of the segments
+    let workers = local_workers(config) as u64;
+    let share = config.worker_id as u64 * workers + worker as u64;
+    let shares = config.worker_count as u64 * workers;
+    let segment = salt_segment::<4>(prefix, Some(position.segment), share, shares);
+    let nonce = (nonce_start + position.offset * config.nonce_stride) as u32;
+    salt[..20].copy_from_slice(caller);
+    salt[20..24].copy_from_slice(&segment[..]);
+    salt[28..].copy_from_slice(&nonce.to_le_bytes());
+    Ok(salt)
+}
+
+/// The number of salts replayed from a stream or segment by default: a
+/// dispatch on a GPU, or as many as the CPU searches between checkpoints.
+pub(crate) fn default_count(config: &Config) -> u64 {
+    match config.gpu_device {
+        255 => CHECKPOINT_CHUNK,
+        _ => config.work_size as u64,
+    }
+}
+
+// the salts of the stretch that starts with `first`
+fn salts(config: &Config, first: [u8; 32], count: u64) -> Result<Vec<[u8; 32]>, Box<dyn Error>> {
+    let at = |salt: &mut [u8; 32], range: std::ops::Range<usize>, value: u64| {
+        salt[range.clone()].copy_from_slice(&value.to_le_bytes()[..range.len()]);
+    };
+    let read = |range: std::ops::Range<usize>| {
+        let mut bytes = [0; 8];
+        bytes[..range.len()].copy_from_slice(&first[range]);
+        u64::from_le_bytes(bytes)
+    };
+
+    if config.gpu_device == 255 {
+        let nonce = read(26..32);
+        let last = nonce + (count - 1) * config.nonce_stride;
+        if last > 0xffffffffffff {
+            return Err("the salts run past the last nonce of the segment".into());
+        }
+        return Ok((0..count)
+            .map(|i| {
+                let mut salt = first;
+                at(&mut salt, 26..32, nonce + i * config.nonce_stride);
+                salt
+            })
+            .collect());
+    }
+
+    let item = read(24..28);
+    if item + count > 1 << 32 {
+        return Err("the salts run past the last work item of the dispatch".into());
+    }
+    Ok((0..count)
+        .map(|i| {
+            let mut salt = first;
+            at(&mut salt, 24..28, item + i);
+            salt
+        })
+        .collect())
+}
+
+// the solution that the device reports a salt as
+fn solution(salt: &[u8; 32]) -> u64 {
+    u64::from_le_bytes(salt[24..].try_into().unwrap())
+}
+
+/// Dispatch the work items of the stretch on the device again for a target,
+/// returning how many times it diverges from what the host `expected` (the
+/// solution and init code hash index of every address that qualifies).
+fn dispatch(
+    config: &Config,
+    tuning: &Tuning,
+    target: usize,
+    first: [u8; 32],
+    count: u64,
+    expected: &[(u64, u32)],
+) -> Result<u64, Box<dyn Error>> {
+    let ocl_pq = pro_ques(config, tuning, config.gpu_device, count as u32)?.swap_remove(target);
+    let slots = solution_slots(config, count as u32);
+
+    let message_buffer = Buffer::builder()
+        .queue(ocl_pq.queue().clone())
+        .flags(MemFlags::new().read_only())
+        .len(4)
+        .copy_host_slice(&first[20..24])
+        .build()?;
+    let solutions_buffer = Buffer::<u64>::builder()
+        .queue(ocl_pq.queue().clone())
+        .flags(MemFlags::new().write_only())
+        .len(slots)
+        .fill_val(0)
+        .build()?;
+    let mut solution_count: [u32; 1] = [0];
+    let solution_count_buffer = Buffer::builder()
+        .queue(ocl_pq.queue().clone())
+        .flags(MemFlags::new().read_write())
+        .len(1)
+        .copy_host_slice(&solution_count)
+        .build()?;
+    let solution_addresses_buffer = Buffer::<u8>::builder()
+        .queue(ocl_pq.queue().clone())
+        .flags(MemFlags::new().write_only())
+        .len(slots * 20)
+        .fill_val(0)
+        .build()?;
+    let solution_hashes_buffer = Buffer::<u32>::builder()
+        .queue(ocl_pq.queue().clone())
+        .flags(MemFlags::new().write_only())
+        .len(slots)
+        .fill_val(0)
+        .build()?;
+    let histogram_buffer = Buffer::<u32>::builder()
+        .queue(ocl_pq.queue().clone())
+        .flags(MemFlags::new().read_write())
+        .len(HISTOGRAM_BUCKETS)
+        .fill_val(0)
+        .build()?;
+    let nonce = u32::from_le_bytes(first[28..].try_into().unwrap());
+    let kern = ocl_pq
+        .kernel_builder("hashMessage")
+        .arg_named("message", &message_buffer)
+        .arg_named("nonce", nonce)
+        .arg_named("solutions", &solutions_buffer)
+        .arg_named("solutionCount", &solution_count_buffer)
+        .arg_named("solutionAddresses", &solution_addresses_buffer)
+        .arg_named("solutionHashes", &solution_hashes_buffer)
+        .arg_named("histogram", &histogram_buffer)
+        .build()?;
+
+    // run the work items from the one in the first salt
+    let item = u32::from_le_bytes(first[24..28].try_into().unwrap());
+    unsafe {
+        kern.cmd()
+            .global_work_offset(item as usize)
+            .global_work_size(count as usize)
+            .enq()?
+    };
+    solution_count_buffer.read(&mut solution_count[..]).enq()?;
+    let mut solutions = vec![0u64; slots];
+    solutions_buffer.read(&mut solutions).enq()?;
+    let mut hashes = vec![0u32; slots];
+    solution_hashes_buffer.read(&mut hashes).enq()?;
+
+    // the device must report exactly the salts that qualify on the host,
+    // although it can only return as many as there are slots for
+    let returned = (solution_count[0] as usize).min(slots);
+    let mut reported: Vec<_> = solutions[..returned]
+        .iter()
+        .copied()
+        .zip(hashes[..returned].iter().copied())
+        .collect();
+    reported.sort_unstable();
+    let mut divergences = 0;
+    if solution_count[0] as usize != expected.len() {
+        divergences += 1;
+        println!(
+            "  DIVERGENCE target {target}: device reported {} solutions, host expected {}",
+            solution_count[0],
+            expected.len()
+        );
+    }
+    for (solution, hash) in reported.iter().filter(|s| !expected.contains(s)) {
+        divergences += 1;
+        let mut salt = first;
+        salt[24..].copy_from_slice(&solution.to_le_bytes());
+        println!(
+            "  DIVERGENCE target {target}: unexpected salt 0x{} (init code hash {hash})",
+            hex::encode(salt)
+        );
+    }
+    Ok(divergences)
+}
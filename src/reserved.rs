@@ -0,0 +1,114 @@
+use alloy_primitives::Address;
+use rustc_hash::FxHashSet;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use tiny_keccak::{Hasher, Keccak};
+
+// sets larger than this are held in a bloom filter rather than exactly
+const BLOOM_THRESHOLD: usize = 1 << 20;
+
+// approximate memory used per address when the set is held exactly
+const EXACT_BYTES_PER_ENTRY: usize = 32;
+
+// bloom filter sizing: ~0.05% false positives at 16 bits per entry, and a
+// little over 2% at the minimum of 8 bits per entry
+const BLOOM_BITS_PER_ENTRY: usize = 16;
+const BLOOM_MIN_BITS_PER_ENTRY: usize = 8;
+const BLOOM_HASHES: u64 = 11;
+
+/// What to do when a mined address is found in the reserved set.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReservedAction {
+    /// Drop the result entirely.
+    #[default]
+    Skip,
+    /// Keep the result but mark it as reserved.
+    Flag,
+}
+
+/// A set of addresses that must not be reported as fresh finds, e.g. because
+/// they are already deployed or reserved by the organization.
+pub enum Reserved {
+    Exact(FxHashSet<Address>),
+    Bloom { bits: Vec<u64>, len: u64 },
+}
+
+impl Reserved {
+    /// Load a file containing one hex-encoded address per line. Blank lines and
+    /// lines starting with `#` are ignored. The file is streamed twice, once to
+    /// count the addresses and once to insert them, so that the set is held
+    /// exactly only when it fits within `max_memory` bytes (if given) and is
+    /// otherwise held in a bloom filter sized to the budget.
+    pub fn load(path: &Path, max_memory: Option<usize>) -> Result<Self, Box<dyn Error>> {
+        let mut count = 0;
+        for_each_address(path, |_| count += 1)?;
+
+        let budget = max_memory.unwrap_or(usize::MAX);
+        if count <= BLOOM_THRESHOLD && count.saturating_mul(EXACT_BYTES_PER_ENTRY) <= budget {
+            let mut set = FxHashSet::default();
+            set.reserve(count);
+            for_each_address(path, |address| {
+                set.insert(address);
+            })?;
+            return Ok(Self::Exact(set));
+        }
+
+        let bits_per_entry = (budget.saturating_mul(8) / count.max(1)).min(BLOOM_BITS_PER_ENTRY);
+        if bits_per_entry < BLOOM_MIN_BITS_PER_ENTRY {
+            return Err(format!("{} does not fit in the memory budget", path.display()).into());
+        }
+
+        // round down to a power of two so that bit indices can be masked
+        let bits_wanted = (count * bits_per_entry).max(64);
+        let len = 1u64 << (usize::BITS - 1 - bits_wanted.leading_zeros());
+        let mut bits = vec![0u64; (len / 64) as usize];
+        for_each_address(path, |address| {
+            for bit in bloom_bits(&address, len) {
+                bits[(bit / 64) as usize] |= 1 << (bit % 64);
+            }
+        })?;
+        Ok(Self::Bloom { bits, len })
+    }
+
+    /// Check whether an address is reserved. Large sets may report false
+    /// positives, but never false negatives.
+    pub fn contains(&self, address: &Address) -> bool {
+        match self {
+            Self::Exact(set) => set.contains(address),
+            Self::Bloom { bits, len } => bloom_bits(address, *len)
+                .all(|bit| bits[(bit / 64) as usize] & (1 << (bit % 64)) != 0),
+        }
+    }
+}
+
+/// Stream the addresses in a reserved addresses file.
+fn for_each_address(path: &Path, mut f: impl FnMut(Address)) -> Result<(), Box<dyn Error>> {
+    let file = File::open(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let address = line
+            .parse::<Address>()
+            .map_err(|_| format!("{}:{}: invalid address", path.display(), i + 1))?;
+        f(address);
+    }
+    Ok(())
+}
+
+/// Derive the bloom filter bit indices for an address via double hashing. The
+/// address is hashed first since reserved sets often share long prefixes.
+fn bloom_bits(address: &Address, len: u64) -> impl Iterator<Item = u64> {
+    let mut hash = [0u8; 32];
+    let mut keccak = Keccak::v256();
+    keccak.update(address.as_slice());
+    keccak.finalize(&mut hash);
+
+    let h1 = u64::from_le_bytes(hash[..8].try_into().unwrap());
+    let h2 = u64::from_le_bytes(hash[8..16].try_into().unwrap()) | 1;
+    (0..BLOOM_HASHES).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) & (len - 1))
+}
@@ -0,0 +1,754 @@
+use crate::{
+    add_address, db, leaderboard, mode, score_address_detailed, zero_bytes, Config, Reward,
+};
+use alloy_primitives::{hex, Address, U256};
+use rustc_hash::FxHashSet;
+use serde_json::{json, Value};
+use std::error::Error;
+use std::fmt::Display;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::warn;
+
+// how long to wait for the status of a run
+const STATUS_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A single line of a results file, i.e. `salt => address => reward`. The
+/// address is always held in EIP-55 checksummed form.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Found {
+    pub salt: String,
+    pub address: String,
+    pub reward: U256,
+}
+
+impl Found {
+    /// Parse a line in the format written by the miner, returning `None` for
+    /// anything that is not a well-formed result. Addresses written in any case
+    /// are accepted and checksummed.
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut parts = line.trim().split(" => ");
+        let salt = parts.next()?;
+        let address = parts.next()?;
+        let reward = parts.next()?.parse().ok()?;
+        if parts.next().is_some() || salt.len() != 66 || address.len() != 42 {
+            return None;
+        }
+        let address = address.parse::<Address>().ok()?;
+
+        Some(Self {
+            salt: salt.to_string(),
+            address: address.to_checksum(None),
+            reward,
+        })
+    }
+}
+
+impl std::fmt::Display for Found {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} => {} => {}", self.salt, self.address, self.reward)
+    }
+}
+
+/// A result as listed by [`top`]: its line of a results file, with its pattern
+/// score, the time it was found (in Unix seconds) and the job it was found
+/// for, where they are known.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Listed {
+    pub(crate) found: Found,
+    pub(crate) pattern_score: Option<u32>,
+    pub(crate) found_at: Option<u64>,
+    pub(crate) job_id: Option<String>,
+}
+
+impl Listed {
+    // a JSON lines result, as written with `--output-file`, and whether its
+    // address is reserved
+    fn parse_payload(line: &str) -> Option<(Self, bool)> {
+        let payload: Value = serde_json::from_str(line).ok()?;
+        let field = |key: &str| payload.get(key)?.as_str();
+        let found = Found::parse(&format!(
+            "{} => {} => {}",
+            field("salt")?,
+            field("address")?,
+            field("score")?
+        ))?;
+        let listed = Self {
+            found,
+            pattern_score: payload["pattern_score"]
+                .as_u64()
+                .and_then(|score| score.try_into().ok()),
+            found_at: payload["found_at"].as_u64(),
+            job_id: field("job_id").map(str::to_string),
+        };
+        Some((listed, payload["reserved"] == true))
+    }
+
+    // what results are ranked by: the pattern score if there is one, and
+    // then the reward
+    fn rank(&self) -> (Option<u32>, U256) {
+        (self.pattern_score, self.found.reward)
+    }
+}
+
+/// Totals gathered while reading one or more results files.
+#[derive(Debug, Default)]
+pub struct Summary {
+    pub read: usize,
+    pub malformed: usize,
+    pub duplicates: usize,
+}
+
+/// Read every result from the given files, dropping malformed lines and
+/// repeated salts (the first occurrence wins). Results are sorted by reward,
+/// highest first, with ties broken by salt so the order is canonical.
+pub fn read_all(paths: &[PathBuf]) -> Result<(Vec<Found>, Summary), Box<dyn Error>> {
+    read_sources(paths, &[])
+}
+
+/// Read every result from the given files and then from the given SQLite
+/// stores (as written with `--db`), as [`read_all`] does.
+fn read_sources(
+    paths: &[PathBuf],
+    dbs: &[PathBuf],
+) -> Result<(Vec<Found>, Summary), Box<dyn Error>> {
+    let mut summary = Summary::default();
+    let mut seen = FxHashSet::default();
+    let mut results = vec![];
+    let mut add = |found: Option<Found>| {
+        summary.read += 1;
+        let Some(found) = found else {
+            summary.malformed += 1;
+            return;
+        };
+        if !seen.insert(found.salt.to_lowercase()) {
+            summary.duplicates += 1;
+            return;
+        }
+        results.push(found);
+    };
+
+    for path in paths {
+        let file = File::open(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                add(Found::parse(&line));
+            }
+        }
+    }
+    for db in dbs {
+        db::read(db)?.into_iter().for_each(|found| add(Some(found)));
+    }
+
+    results.sort_by(|a, b| b.reward.cmp(&a.reward).then_with(|| a.salt.cmp(&b.salt)));
+
+    Ok((results, summary))
+}
+
+/// Merge the given results files and SQLite stores into a single
+/// deduplicated, sorted results file. The output is moved into place only once
+/// fully written, so it is safe to compact a file onto itself.
+pub fn compact(inputs: &[PathBuf], dbs: &[PathBuf], output: &Path) -> Result<(), Box<dyn Error>> {
+    let (results, summary) = read_sources(inputs, dbs)?;
+
+    write_atomic(output, &results)?;
+
+    println!(
+        "read {} results from {} file(s): {} malformed, {} duplicate, {} written to {}",
+        summary.read,
+        inputs.len() + dbs.len(),
+        summary.malformed,
+        summary.duplicates,
+        results.len(),
+        output.display()
+    );
+
+    Ok(())
+}
+
+/// Merge the given SQLite stores into the one at `output`, keeping every
+/// column of each result, and dropping salts that are already in it.
+pub fn merge_dbs(inputs: &[PathBuf], output: &Path) -> Result<(), Box<dyn Error>> {
+    let (read, added) = db::merge(inputs, output)?;
+
+    println!(
+        "read {read} results from {} database(s): {} duplicate, {added} added to {}",
+        inputs.len(),
+        read - added,
+        output.display()
+    );
+
+    Ok(())
+}
+
+/// Re-derive every result in a JSON lines results file (as written with
+/// `--output-file`) from its salt and factory, score it again by zero bytes
+/// and under `config.scoring` if given, and write the results to `output`,
+/// best first. Results whose address no longer follows from the salt (such as
+/// those mined for other init code) are reported as stale and dropped, as are
+/// lines that cannot be read and results below the minimum score.
+pub fn rescore(input: &Path, output: &Path, config: &Config) -> Result<(), Box<dyn Error>> {
+    let file = File::open(input).map_err(|e| format!("{}: {e}", input.display()))?;
+    let mut summary = Summary::default();
+    let (mut stale, mut below) = (0, 0);
+    let mut seen = FxHashSet::default();
+    let mut results = vec![];
+
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        summary.read += 1;
+
+        let Some((mut payload, salt, factory, address)) = parse_payload(&line) else {
+            warn!(line = i + 1, "not a well-formed result");
+            summary.malformed += 1;
+            continue;
+        };
+        // results mined with several init code hashes name theirs
+        let hash = match payload["init_code_hash"].as_str() {
+            Some(hash) => match hex::decode(hash).ok().and_then(|hash| hash.try_into().ok()) {
+                Some(hash) => hash,
+                None => {
+                    warn!(line = i + 1, "not a well-formed result");
+                    summary.malformed += 1;
+                    continue;
+                }
+            },
+            None => *mode::create2_hash(config),
+        };
+        let derived = mode::address(config, &factory, &salt, &hash);
+        if derived != address {
+            warn!(
+                line = i + 1,
+                "salt {} now derives {derived}, not {address}",
+                hex::encode_prefixed(salt)
+            );
+            stale += 1;
+            continue;
+        }
+        if !seen.insert(salt) {
+            summary.duplicates += 1;
+            continue;
+        }
+
+        let (leading, total) = zero_bytes(&address);
+        let reward = Reward::new().get(&(leading * 20 + total)).unwrap_or("0");
+        payload["score"] = json!(reward);
+        add_address(&mut payload, "address", &address);
+        let object = payload.as_object_mut().unwrap();
+        object.remove("pattern_score");
+        object.remove("score_breakdown");
+
+        let score = match &config.scoring {
+            Some(scoring) => {
+                let breakdown = score_address_detailed(scoring, &address);
+                if breakdown.total() < scoring.min_score {
+                    below += 1;
+                    continue;
+                }
+                payload["pattern_score"] = json!(breakdown.total());
+                payload["score_breakdown"] = json!(breakdown);
+                Some(breakdown.total())
+            }
+            None => None,
+        };
+        results.push((score, reward.parse::<U256>()?, salt, payload));
+    }
+
+    // best first, by pattern score and then by reward, with ties broken by
+    // salt so the order is canonical
+    results.sort_by(|a, b| (b.0, b.1).cmp(&(a.0, a.1)).then_with(|| a.2.cmp(&b.2)));
+    let payloads: Vec<Value> = results.into_iter().map(|result| result.3).collect();
+    write_atomic(output, &payloads)?;
+
+    println!(
+        "read {} results from {}: {} malformed, {} duplicate, {stale} stale, {below} below the minimum score, {} written to {}",
+        summary.read,
+        input.display(),
+        summary.malformed,
+        summary.duplicates,
+        payloads.len(),
+        output.display()
+    );
+
+    Ok(())
+}
+
+/// Parse a line of a JSON lines results file into the payload with its salt,
+/// factory and address.
+fn parse_payload(line: &str) -> Option<(Value, [u8; 32], Address, Address)> {
+    let payload: Value = serde_json::from_str(line).ok()?;
+    let field = |key: &str| payload.get(key)?.as_str();
+    let salt = hex::decode(field("salt")?).ok()?.try_into().ok()?;
+    let factory = field("factory")?.parse().ok()?;
+    let address = field("address")?.parse().ok()?;
+    Some((payload, salt, factory, address))
+}
+
+/// Print the `n` best results from the given results files (which may also be
+/// JSON lines, as written with `--output-file`) or from a `--db` store, by
+/// pattern score and then by reward, as a ranked table with the time each was
+/// found and its job where known, or write them to `output` in the results
+/// file format. With `job`, only the results tagged with that job are taken.
+/// Reserved addresses are left out.
+pub fn top(
+    inputs: &[PathBuf],
+    db: Option<&Path>,
+    job: Option<&str>,
+    n: usize,
+    output: Option<&Path>,
+) -> Result<(), Box<dyn Error>> {
+    let mut results = ranked(inputs, db, job)?;
+    results.truncate(n);
+
+    if let Some(output) = output {
+        let found: Vec<&Found> = results.iter().map(|listed| &listed.found).collect();
+        write_atomic(output, &found)?;
+        println!("wrote {} results to {}", results.len(), output.display());
+        return Ok(());
+    }
+
+    let score = |listed: &Listed| match listed.pattern_score {
+        Some(score) => score.to_string(),
+        None => listed.found.reward.to_string(),
+    };
+    let width = results.iter().map(|l| score(l).len()).max().unwrap_or(1);
+    for (i, listed) in results.iter().enumerate() {
+        let found_at = listed.found_at.map_or("-".to_string(), leaderboard::utc);
+        println!(
+            "{:>4}. {:>width$}  {}  {}  {found_at:<19}  {}",
+            i + 1,
+            score(listed),
+            listed.found.address,
+            listed.found.salt,
+            listed.job_id.as_deref().unwrap_or("-"),
+        );
+    }
+
+    Ok(())
+}
+
+// every result of the job (if given) in the given files or `--db` store, best
+// first
+fn ranked(
+    inputs: &[PathBuf],
+    db: Option<&Path>,
+    job: Option<&str>,
+) -> Result<Vec<Listed>, Box<dyn Error>> {
+    let mut results = match db {
+        Some(db) => db::list(db, job)?,
+        None => list(inputs)?
+            .into_iter()
+            .filter(|listed| job.is_none_or(|job| listed.job_id.as_deref() == Some(job)))
+            .collect(),
+    };
+    sort(&mut results);
+    Ok(results)
+}
+
+// by pattern score and then by reward, best first, with ties broken by salt so
+// the order is canonical
+fn sort(results: &mut [Listed]) {
+    results.sort_by(|a, b| {
+        b.rank()
+            .cmp(&a.rank())
+            .then_with(|| a.found.salt.cmp(&b.found.salt))
+    });
+}
+
+// every result in the given files, which may be results files or JSON lines,
+// dropping malformed lines, reserved addresses and repeated salts
+fn list(paths: &[PathBuf]) -> Result<Vec<Listed>, Box<dyn Error>> {
+    let mut seen = FxHashSet::default();
+    let mut results = vec![];
+    for path in paths {
+        let file = File::open(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            let line = line.trim();
+            let listed = match line.starts_with('{') {
+                true => Listed::parse_payload(line)
+                    .filter(|(_, reserved)| !reserved)
+                    .map(|(listed, _)| listed),
+                false => Found::parse(line).map(|found| Listed {
+                    found,
+                    pattern_score: None,
+                    found_at: None,
+                    job_id: None,
+                }),
+            };
+            if let Some(listed) = listed.filter(|l| seen.insert(l.found.salt.to_lowercase())) {
+                results.push(listed);
+            }
+        }
+    }
+    Ok(results)
+}
+
+/// Output formats for [`best`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum BestFormat {
+    /// Solidity constants, e.g. for a Foundry deployment script.
+    Solidity,
+    /// Shell variable assignments, e.g. for `source` or a `.env` file.
+    Env,
+    /// A single JSON object.
+    Json,
+}
+
+/// Print the best result from the given files or `--db` store (of `job`, if
+/// given), ranked as by [`top`], in a form that can be pasted straight into a
+/// deployment script. The best results of the runs whose status is served at
+/// the `status` URLs are taken into account as well.
+pub fn best(
+    inputs: &[PathBuf],
+    db: Option<&Path>,
+    job: Option<&str>,
+    status: &[String],
+    format: BestFormat,
+) -> Result<(), Box<dyn Error>> {
+    let mut results = ranked(inputs, db, job)?;
+    // a run that has ended no longer serves its status
+    for url in status {
+        match status_best(url, job) {
+            Ok(listed) => results.extend(listed),
+            Err(e) => warn!("could not get the best result of the run: {e}"),
+        }
+    }
+    sort(&mut results);
+    let Some(best) = results.first() else {
+        return Err("no results found".into());
+    };
+    let found = &best.found;
+
+    match format {
+        BestFormat::Solidity => {
+            if let Some(score) = best.pattern_score {
+                println!("// score: {score}");
+            }
+            println!("// reward: {}", found.reward);
+            println!("bytes32 constant SALT = {};", found.salt);
+            println!("address constant EXPECTED_ADDRESS = {};", found.address);
+        }
+        BestFormat::Env => {
+            println!("SALT={}", found.salt);
+            println!("EXPECTED_ADDRESS={}", found.address);
+            println!(
+                "EXPECTED_ADDRESS_LOWERCASE={}",
+                found.address.to_lowercase()
+            );
+            println!("REWARD={}", found.reward);
+            if let Some(score) = best.pattern_score {
+                println!("SCORE={score}");
+            }
+        }
+        BestFormat::Json => {
+            let mut object = json!({
+                "salt": found.salt,
+                "reward": found.reward.to_string(),
+            });
+            if let Some(score) = best.pattern_score {
+                object["pattern_score"] = json!(score);
+            }
+            if let Some(job) = &best.job_id {
+                object["job_id"] = json!(job);
+            }
+            add_address(&mut object, "address", &found.address.parse()?);
+            println!("{object}");
+        }
+    }
+
+    Ok(())
+}
+
+// the best result of the run whose status is served at `url`, if it has one
+// (and is for `job`, if given)
+fn status_best(url: &str, job: Option<&str>) -> Result<Option<Listed>, Box<dyn Error>> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(STATUS_TIMEOUT)
+        .build()?;
+    let status: Value = client
+        .get(url)
+        .send()
+        .and_then(|response| response.error_for_status())
+        .and_then(|response| response.json())
+        .map_err(|e| format!("{url}: {e}"))?;
+    let job_id = status["job_id"].as_str().map(str::to_string);
+    if job.is_some_and(|job| job_id.as_deref() != Some(job)) {
+        return Ok(None);
+    }
+    let Some(line) = status["best"].as_str() else {
+        return Ok(None);
+    };
+    let found = Found::parse(line).ok_or_else(|| format!("{url}: malformed best result"))?;
+
+    // the best score is the pattern score if the run is scoring
+    let pattern_score = match status["job"]["min_score"].is_null() {
+        true => None,
+        false => status["best_score"]
+            .as_str()
+            .and_then(|score| score.parse().ok()),
+    };
+    Ok(Some(Listed {
+        found,
+        pattern_score,
+        found_at: None,
+        job_id,
+    }))
+}
+
+/// Write results to `output`, one per line, via a temporary file that is moved
+/// into place.
+fn write_atomic<T: Display>(output: &Path, results: &[T]) -> Result<(), Box<dyn Error>> {
+    let mut tmp = output.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+
+    let mut writer = BufWriter::new(File::create(&tmp)?);
+    for found in results {
+        writeln!(writer, "{found}")?;
+    }
+    writer
+        .into_inner()
+        .map_err(|e| e.into_error())?
+        .sync_all()?;
+    fs::rename(&tmp, output)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::ResultDb;
+
+    fn temp(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "create2crunch-{}-results-{name}",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn write(name: &str, lines: &[String]) -> PathBuf {
+        let path = temp(name);
+        fs::write(&path, lines.join("\n") + "\n").unwrap();
+        path
+    }
+
+    fn salt(byte: u8) -> String {
+        hex::encode_prefixed([byte; 32])
+    }
+
+    fn address(byte: u8) -> String {
+        Address::repeat_byte(byte).to_checksum(None)
+    }
+
+    fn line(byte: u8, reward: u64) -> String {
+        format!("{} => {} => {reward}", salt(byte), address(byte))
+    }
+
+    #[test]
+    fn parses_lines() {
+        let found = Found::parse(&line(0xab, 7)).unwrap();
+        assert_eq!(found.to_string(), line(0xab, 7));
+
+        // any case is accepted, and checksummed
+        let lower = format!("  {} => {} => 7 ", salt(0xab), address(0xab).to_lowercase());
+        assert_eq!(Found::parse(&lower), Some(found));
+
+        for malformed in [
+            String::new(),
+            format!("{} => {}", salt(0xab), address(0xab)),
+            format!("{} => 7", salt(0xab)),
+            format!("{} => {} => seven", salt(0xab), address(0xab)),
+            format!("{} => {} => -7", salt(0xab), address(0xab)),
+            format!("{} => {} => 7 => 7", salt(0xab), address(0xab)),
+            format!("{} => {} => 7", &salt(0xab)[..64], address(0xab)),
+            format!("{} => {} => 7", salt(0xab), &address(0xab)[..40]),
+            format!("{} => 0x{} => 7", salt(0xab), "zz".repeat(20)),
+        ] {
+            assert_eq!(Found::parse(&malformed), None, "{malformed:?}");
+        }
+    }
+
+    #[test]
+    fn reads_files_and_dbs_once() {
+        let first = write(
+            "first.txt",
+            &[
+                line(1, 5),
+                "not a result".to_string(),
+                line(2, 9),
+                String::new(),
+                line(3, 5),
+            ],
+        );
+        // a repeat in another case, and one that the first file lacks
+        let second = write("second.txt", &[line(2, 1).to_uppercase(), line(4, 9)]);
+        let db_path = temp("results.sqlite");
+        let db = ResultDb::open(&db_path).unwrap();
+        for (byte, reward) in [(3, 1), (5, 5)] {
+            let payload = json!({
+                "salt": salt(byte),
+                "address": address(byte),
+                "factory": address(0),
+                "score": reward.to_string(),
+            });
+            db.record(&payload, None).unwrap();
+        }
+        drop(db);
+        let (files, dbs) = ([first, second], [db_path]);
+
+        let (results, summary) = read_sources(&files, &dbs).unwrap();
+        assert_eq!(
+            (summary.read, summary.malformed, summary.duplicates),
+            (8, 1, 2)
+        );
+        // by reward, highest first, and then by salt; the first occurrence of
+        // a salt wins
+        let expected = [line(2, 9), line(4, 9), line(1, 5), line(3, 5), line(5, 5)];
+        let lines: Vec<String> = results.iter().map(Found::to_string).collect();
+        assert_eq!(lines, expected);
+
+        // compacting gives the same order, and can be done in place
+        compact(&files, &dbs, &files[0]).unwrap();
+        assert_eq!(
+            fs::read_to_string(&files[0]).unwrap(),
+            expected.join("\n") + "\n"
+        );
+
+        for path in files.iter().chain(&dbs) {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    #[test]
+    fn ranks_by_pattern_score_and_then_reward() {
+        let payload = |byte: u8, reward: u64, score: Option<u32>, job: &str| {
+            let mut payload = json!({
+                "salt": salt(byte),
+                "address": address(byte).to_lowercase(),
+                "score": reward.to_string(),
+                "job_id": job,
+                "found_at": 1_700_000_000 + byte as u64,
+            });
+            if let Some(score) = score {
+                payload["pattern_score"] = json!(score);
+            }
+            payload.to_string()
+        };
+        let mut reserved: Value = serde_json::from_str(&payload(9, 99, Some(99), "a")).unwrap();
+        reserved["reserved"] = json!(true);
+        let input = write(
+            "ranked.jsonl",
+            &[
+                payload(1, 9, None, "a"),
+                payload(2, 1, Some(40), "a"),
+                payload(3, 5, Some(40), "b"),
+                payload(4, 1, Some(70), "a"),
+                line(5, 20),
+                line(1, 50),
+                reserved.to_string(),
+                "{ not json".to_string(),
+            ],
+        );
+
+        let order = |job| -> Vec<String> {
+            ranked(std::slice::from_ref(&input), None, job)
+                .unwrap()
+                .into_iter()
+                .map(|listed| listed.found.salt)
+                .collect()
+        };
+        assert_eq!(
+            order(None),
+            [salt(4), salt(3), salt(2), salt(5), salt(1)],
+            "scored results first, and the reserved, malformed and repeated ones left out"
+        );
+        assert_eq!(order(Some("a")), [salt(4), salt(2), salt(1)]);
+
+        let best = &ranked(std::slice::from_ref(&input), None, Some("b")).unwrap()[0];
+        assert_eq!(best.found.address, address(3));
+        assert_eq!(
+            (best.pattern_score, best.found_at, best.job_id.as_deref()),
+            (Some(40), Some(1_700_000_003), Some("b"))
+        );
+
+        // `top` writes the first of them as results lines
+        let output = temp("top.txt");
+        top(
+            std::slice::from_ref(&input),
+            None,
+            Some("a"),
+            2,
+            Some(&output),
+        )
+        .unwrap();
+        assert_eq!(
+            fs::read_to_string(&output).unwrap(),
+            format!("{}\n{}\n", line(4, 1), line(2, 1))
+        );
+
+        for path in [input, output] {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    #[test]
+    fn rescores_what_still_derives() {
+        let config = Config::default();
+        let factory = Address::repeat_byte(0x0f);
+        let payload = |byte: u8, address: Address| {
+            json!({
+                "salt": salt(byte),
+                "address": address.to_checksum(None),
+                "factory": factory.to_checksum(None),
+                "score": "123",
+                "pattern_score": 1,
+            })
+            .to_string()
+        };
+        let derive = |byte| mode::address(&config, &factory, &[byte; 32], &[0; 32]);
+        let input = write(
+            "rescore.jsonl",
+            &[
+                payload(2, derive(2)),
+                payload(1, derive(1)),
+                payload(3, Address::repeat_byte(3)),
+                payload(2, derive(2)),
+                "not json".to_string(),
+            ],
+        );
+        let output = temp("rescored.jsonl");
+
+        rescore(&input, &output, &config).unwrap();
+        let rescored: Vec<Value> = fs::read_to_string(&output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        // the stale, repeated and malformed lines are dropped, the reward is
+        // that of the derived address, and the pattern score is gone
+        assert_eq!(rescored.len(), 2);
+        for (payload, byte) in rescored.iter().zip([1, 2]) {
+            let address = derive(byte);
+            let (leading, total) = zero_bytes(&address);
+            let reward = Reward::new().get(&(leading * 20 + total)).unwrap_or("0");
+            assert_eq!(payload["salt"], salt(byte));
+            assert_eq!(payload["address"], address.to_checksum(None));
+            assert_eq!(payload["score"], reward);
+            assert!(payload.get("pattern_score").is_none());
+        }
+
+        for path in [input, output] {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
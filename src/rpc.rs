@@ -0,0 +1,120 @@
+use crate::miner::Solution;
+use crate::Config;
+use alloy_primitives::{hex, keccak256, Address};
+use reqwest::blocking::Client;
+use serde_json::{json, Value};
+use std::error::Error;
+use std::time::Duration;
+
+/// The view of the factory that found salts are checked against by default,
+/// as on the ImmutableCreate2Factory.
+pub const DEFAULT_RPC_VERIFY: &str = "findCreate2AddressViaHash(bytes32,bytes32)";
+
+// how long to wait for the node before giving up on a check
+const RPC_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Checks found salts against the factory itself before they are posted
+/// (`--rpc-url`), by calling one of its views with `eth_call`, so that a
+/// factory that derives addresses differently from the kernel is caught on the
+/// first result rather than at deployment time.
+pub(crate) struct Verifier {
+    client: Client,
+    url: String,
+    selector: [u8; 4],
+    // whether the view also takes the init code hash after the salt
+    with_hash: bool,
+    init_code_hash: [u8; 32],
+}
+
+/// Why a salt could not be confirmed.
+pub(crate) enum Mismatch {
+    /// The factory derives a different address from the salt.
+    Address(Address),
+    /// The node could not be asked, or gave an answer that makes no sense.
+    Failed(Box<dyn Error>),
+}
+
+impl Verifier {
+    pub(crate) fn new(url: String, config: &Config) -> Result<Self, Box<dyn Error>> {
+        let signature = &config.rpc_verify;
+        let with_hash = match arguments(signature) {
+            Some(1) => false,
+            Some(2) => true,
+            _ => return Err(format!("{signature} does not take a salt and init code hash").into()),
+        };
+        Ok(Self {
+            client: Client::builder().timeout(RPC_TIMEOUT).build()?,
+            url,
+            selector: keccak256(signature)[..4].try_into().unwrap(),
+            with_hash,
+            init_code_hash: config.init_code_hash,
+        })
+    }
+
+    /// Ask the factory what address the salt of a solution deploys to.
+    pub(crate) fn verify(&self, solution: &Solution) -> Result<(), Mismatch> {
+        let mut data = self.selector.to_vec();
+        data.extend_from_slice(solution.salt.as_slice());
+        if self.with_hash {
+            let hash = solution
+                .init_code_hash
+                .map_or(self.init_code_hash, |hash| hash.0);
+            data.extend_from_slice(&hash);
+        }
+
+        let address = self
+            .call(&solution.factory, &data)
+            .map_err(Mismatch::Failed)?;
+        match address == solution.address {
+            true => Ok(()),
+            false => Err(Mismatch::Address(address)),
+        }
+    }
+
+    fn call(&self, factory: &Address, data: &[u8]) -> Result<Address, Box<dyn Error>> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "eth_call",
+            "params": [
+                {"to": factory.to_string(), "data": hex::encode_prefixed(data)},
+                "latest",
+            ],
+        });
+        let response: Value = self
+            .client
+            .post(&self.url)
+            .json(&request)
+            .send()?
+            .error_for_status()?
+            .json()?;
+        if let Some(error) = response.get("error") {
+            return Err(format!("eth_call failed: {error}").into());
+        }
+
+        // an address is returned as the last 20 bytes of a 32-byte word
+        let result = response["result"]
+            .as_str()
+            .ok_or("eth_call returned no result")?;
+        let result = hex::decode(result)?;
+        if result.len() != 32 {
+            return Err(format!("eth_call returned {} bytes", result.len()).into());
+        }
+        Ok(Address::from_slice(&result[12..]))
+    }
+}
+
+/// The number of arguments that a function signature such as
+/// `computeAddress(bytes32,bytes32)` takes, if they are all `bytes32`.
+pub(crate) fn arguments(signature: &str) -> Option<usize> {
+    let (name, rest) = signature.split_once('(')?;
+    let arguments = rest.strip_suffix(')')?;
+    if name.is_empty() || arguments.is_empty() {
+        return None;
+    }
+    let arguments: Vec<&str> = arguments.split(',').collect();
+    arguments
+        .iter()
+        .all(|argument| *argument == "bytes32")
+        .then_some(arguments.len())
+}
@@ -0,0 +1,35 @@
+use crate::Config;
+use alloy_primitives::FixedBytes;
+use std::sync::OnceLock;
+
+// the head of every free salt searched by this process
+static FREE_HEAD: OnceLock<[u8; 20]> = OnceLock::new();
+
+/// What the first 20 bytes of each salt are.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum SaltMode {
+    /// The calling address, for factories that only let the caller use a
+    /// salt that starts with its address (protecting it from frontrunning).
+    #[default]
+    Caller,
+    /// Random bytes, for factories that take any salt.
+    Free,
+}
+
+/// The first 20 bytes of the salts searched for each caller: the callers
+/// themselves, or with free salts a single head that is random for each
+/// process (and zeroes in a deterministic search, so that it can be resumed).
+pub(crate) fn heads(config: &Config) -> Vec<[u8; 20]> {
+    match config.salt_mode {
+        SaltMode::Caller => config.calling_addresses.clone(),
+        SaltMode::Free if config.deterministic => vec![[0; 20]],
+        SaltMode::Free => vec![*FREE_HEAD.get_or_init(|| FixedBytes::<20>::random().0)],
+    }
+}
+
+/// Search the free salts that start with `head` rather than a random one, as
+/// when replaying a salt, returning whether it was in time to (before the
+/// head was asked for).
+pub(crate) fn replay_head(head: [u8; 20]) -> bool {
+    FREE_HEAD.set(head).is_ok()
+}
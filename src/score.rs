@@ -0,0 +1,229 @@
+use alloy_primitives::Address;
+use serde::Serialize;
+
+/// What addresses are mined for (`--scoring`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum ScoringMode {
+    /// Zero bytes, as in the original create2crunch: addresses are reported
+    /// by the leading and total zero byte thresholds and valued by the reward
+    /// table.
+    Zeros,
+    /// Nibble patterns, scored under the [`Scoring`] rules.
+    Nibbles,
+}
+
+/// Rules for scoring addresses by their hex nibbles, for mining patterns (such
+/// as the `0000...4444` style of vanity address) rather than zero bytes. The
+/// default values follow the rules of the Uniswap v4 address challenge, with a
+/// target nibble of 4.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Scoring {
+    /// The nibble (1 to 15) that the pattern bonuses look for.
+    pub target_nibble: u8,
+    /// Points for each leading zero nibble.
+    pub leading_weight: u32,
+    /// Points if the leading zeroes are followed by four target nibbles.
+    pub run_bonus: u32,
+    /// Further points if that run is followed by a different nibble.
+    pub run_end_bonus: u32,
+    /// Points if the address ends with four target nibbles.
+    pub tail_bonus: u32,
+    /// Points for each target nibble anywhere in the address.
+    pub nibble_weight: u32,
+    /// Whether an address scores nothing unless the first nibble after the
+    /// leading zeroes is the target.
+    pub require_target_first: bool,
+    /// Points for each uppercase letter in the EIP-55 checksummed form of the
+    /// address. These are only added on the host, which ranks by them, while
+    /// the device leaves them out of its minimum score filter.
+    pub uppercase_weight: u32,
+    /// Addresses scoring below this are not reported. If not zero, this takes
+    /// the place of the zero byte thresholds on the device.
+    pub min_score: u32,
+}
+
+impl Default for Scoring {
+    fn default() -> Self {
+        Self {
+            target_nibble: 4,
+            leading_weight: 10,
+            run_bonus: 40,
+            run_end_bonus: 20,
+            tail_bonus: 20,
+            nibble_weight: 1,
+            require_target_first: true,
+            uppercase_weight: 0,
+            min_score: 0,
+        }
+    }
+}
+
+/// The points that an address scores under each rule, as returned by
+/// [`score_address_detailed`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct ScoreBreakdown {
+    /// Points for the leading zero nibbles.
+    pub leading_zeroes: u32,
+    /// The bonus for four target nibbles straight after the leading zeroes.
+    pub run_bonus: u32,
+    /// The bonus for that run being followed by a different nibble.
+    pub run_end_bonus: u32,
+    /// The bonus for four target nibbles at the end of the address.
+    pub tail_bonus: u32,
+    /// Points for the target nibbles anywhere in the address.
+    pub nibbles: u32,
+    /// Points for the uppercase letters of the checksummed address.
+    pub uppercase: u32,
+    /// Whether the first nibble after the leading zeroes is not the target,
+    /// which leaves the address with no score if the rules require it.
+    pub target_not_first: bool,
+    /// Whether the address scores nothing, whatever the points above.
+    pub disqualified: bool,
+}
+
+impl ScoreBreakdown {
+    pub fn total(&self) -> u32 {
+        if self.disqualified {
+            return 0;
+        }
+        self.leading_zeroes
+            + self.run_bonus
+            + self.run_end_bonus
+            + self.tail_bonus
+            + self.nibbles
+            + self.uppercase
+    }
+}
+
+/// Score an address under the given rules, mirroring the kernel (apart from
+/// the points for uppercase letters, which take another hash to work out).
+pub fn score_address(scoring: &Scoring, address: &Address) -> u32 {
+    score_address_detailed(scoring, address).total()
+}
+
+/// Score an address under the given rules, with the points for each rule.
+pub fn score_address_detailed(scoring: &Scoring, address: &Address) -> ScoreBreakdown {
+    let nibbles: Vec<u8> = address.iter().flat_map(|b| [b >> 4, b & 0xf]).collect();
+    let target = scoring.target_nibble;
+    let mut breakdown = ScoreBreakdown::default();
+
+    let leading = nibbles.iter().take_while(|&&n| n == 0).count();
+    breakdown.leading_zeroes = leading as u32 * scoring.leading_weight;
+    breakdown.target_not_first = nibbles.get(leading).is_some_and(|&n| n != target);
+    breakdown.disqualified = scoring.require_target_first && breakdown.target_not_first;
+
+    let run = &nibbles[leading..(leading + 4).min(40)];
+    if run.len() == 4 && run.iter().all(|&n| n == target) {
+        breakdown.run_bonus = scoring.run_bonus;
+        if nibbles.get(leading + 4).is_some_and(|&n| n != target) {
+            breakdown.run_end_bonus = scoring.run_end_bonus;
+        }
+    }
+
+    if nibbles[36..].iter().all(|&n| n == target) {
+        breakdown.tail_bonus = scoring.tail_bonus;
+    }
+
+    let count = nibbles.iter().filter(|&&n| n == target).count();
+    breakdown.nibbles = count as u32 * scoring.nibble_weight;
+
+    if scoring.uppercase_weight != 0 {
+        let uppercase = address
+            .to_checksum(None)
+            .chars()
+            .filter(char::is_ascii_uppercase)
+            .count();
+        breakdown.uppercase = uppercase as u32 * scoring.uppercase_weight;
+    }
+    breakdown
+}
+
+/// Whether the EIP-55 checksummed form of an address starts with the given
+/// digits (after the `0x`), matching the case of each letter.
+pub(crate) fn matches_checksum(prefix: &str, address: &Address) -> bool {
+    address.to_checksum(None)[2..].starts_with(prefix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::keccak256;
+
+    fn parse(s: &str) -> Address {
+        s.parse().unwrap()
+    }
+
+    // the Uniswap v4 pool manager, mined under the default rules
+    #[test]
+    fn scores_a_known_address() {
+        let address = parse("0x000000000004444c5dc75cB358380D2e3dE08A90");
+        let breakdown = score_address_detailed(&Scoring::default(), &address);
+        assert_eq!(
+            breakdown,
+            ScoreBreakdown {
+                leading_zeroes: 110,
+                run_bonus: 40,
+                run_end_bonus: 20,
+                nibbles: 4,
+                ..ScoreBreakdown::default()
+            }
+        );
+        assert_eq!(score_address(&Scoring::default(), &address), 174);
+
+        let scoring = Scoring {
+            uppercase_weight: 2,
+            ..Scoring::default()
+        };
+        assert_eq!(score_address(&scoring, &address), 174 + 4 * 2);
+    }
+
+    // the address of example 0 of EIP-1014, derived on the host as the
+    // selftest derives the reference for each address from hashDigests
+    #[test]
+    fn scores_a_derived_address() {
+        let address = Address::ZERO.create2([0; 32], keccak256([0x00]));
+        assert_eq!(address, parse("0x4D1A2e2bB4F88F0250f26Ffff098B0b30B26BF38"));
+        assert_eq!(score_address(&Scoring::default(), &address), 2);
+    }
+
+    #[test]
+    fn scores_runs_and_tails() {
+        let scoring = Scoring::default();
+        // a run that goes on takes no run end bonus
+        let address = parse("0x0044444000000000000000000000000000004444");
+        let breakdown = score_address_detailed(&scoring, &address);
+        assert_eq!(breakdown.run_bonus, 40);
+        assert_eq!(breakdown.run_end_bonus, 0);
+        assert_eq!(breakdown.tail_bonus, 20);
+        assert_eq!(breakdown.total(), 20 + 40 + 20 + 9);
+
+        // the run must come straight after the leading zeroes
+        let address = parse("0x4044440000000000000000000000000000000000");
+        assert_eq!(score_address(&scoring, &address), 5);
+
+        assert_eq!(score_address(&scoring, &Address::ZERO), 400);
+    }
+
+    #[test]
+    fn requires_the_target_first() {
+        let address = parse("0x0000544440000000000000000000000000004444");
+        let breakdown = score_address_detailed(&Scoring::default(), &address);
+        assert!(breakdown.target_not_first);
+        assert!(breakdown.disqualified);
+        assert_eq!(breakdown.total(), 0);
+
+        let scoring = Scoring {
+            require_target_first: false,
+            ..Scoring::default()
+        };
+        assert_eq!(score_address(&scoring, &address), 40 + 20 + 8);
+    }
+
+    #[test]
+    fn matches_checksummed_prefixes() {
+        let address = parse("0x4D1A2e2bB4F88F0250f26Ffff098B0b30B26BF38");
+        assert!(matches_checksum("4D1A2e", &address));
+        assert!(!matches_checksum("4d1a2e", &address));
+        assert!(matches_checksum("", &address));
+    }
+}
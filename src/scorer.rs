@@ -0,0 +1,85 @@
+use alloy_primitives::Address;
+use std::error::Error;
+use std::path::Path;
+use std::sync::Mutex;
+use tracing::warn;
+use wasmtime::{Engine, Instance, Memory, Module, Store, TypedFunc};
+
+/// Scoring rules loaded from a WebAssembly module (`--scorer`), for campaigns
+/// whose rules the built-in [`Scoring`](crate::Scoring) cannot express. The
+/// device still filters candidates as configured (by the thresholds, a
+/// pattern or a minimum score), and the module then scores what it reports on
+/// the host in place of the built-in rules.
+///
+/// The module exports its `memory`, an `address() -> i32` function giving the
+/// offset of a 20-byte buffer that the host writes each address to, and a
+/// `score(address: i32) -> i32` function that is passed that offset and
+/// returns the score. Addresses with a negative score are dropped.
+pub struct Scorer {
+    // calls into the module one at a time, as the store cannot be shared
+    instance: Mutex<Loaded>,
+}
+
+struct Loaded {
+    store: Store<()>,
+    memory: Memory,
+    score: TypedFunc<i32, i32>,
+    address: usize,
+}
+
+impl Scorer {
+    /// Load a module from a `.wasm` file (or `.wat` text).
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let engine = Engine::default();
+        let module =
+            Module::from_file(&engine, path).map_err(|e| format!("{}: {e}", path.display()))?;
+        let mut store = Store::new(&engine, ());
+        let instance = Instance::new(&mut store, &module, &[])
+            .map_err(|e| format!("{}: {e}", path.display()))?;
+
+        let missing = |export: &str| format!("{} does not export {export}", path.display());
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| missing("its memory"))?;
+        let score = instance
+            .get_typed_func::<i32, i32>(&mut store, "score")
+            .map_err(|_| missing("score(i32) -> i32"))?;
+        let address = instance
+            .get_typed_func::<(), i32>(&mut store, "address")
+            .map_err(|_| missing("address() -> i32"))?
+            .call(&mut store, ())
+            .map_err(|e| format!("{}: {e}", path.display()))?;
+
+        Ok(Self {
+            instance: Mutex::new(Loaded {
+                store,
+                memory,
+                score,
+                address: address as usize,
+            }),
+        })
+    }
+
+    /// The score of an address, or `None` if the module drops it (or fails
+    /// on it).
+    pub(crate) fn score(&self, address: &Address) -> Option<u32> {
+        let loaded = &mut *self.instance.lock().unwrap();
+        let scored = loaded
+            .memory
+            .write(&mut loaded.store, loaded.address, address.as_slice())
+            .map_err(|e| e.to_string())
+            .and_then(|()| {
+                loaded
+                    .score
+                    .call(&mut loaded.store, loaded.address as i32)
+                    .map_err(|e| e.to_string())
+            });
+        match scored {
+            Ok(score) => u32::try_from(score).ok(),
+            Err(error) => {
+                warn!("the scorer failed on {address}: {error}");
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use crate::{pro_que, zero_bytes, Config, HISTOGRAM_BUCKETS};
+use alloy_primitives::{hex, Address, FixedBytes};
+use ocl::{Buffer, MemFlags};
+use rand::{thread_rng, Rng};
+use std::error::Error;
+
+/// The number of salts that are checked in each round by default.
+pub const DEFAULT_SELFTEST_COUNT: u32 = 4096;
+
+// number of independent dispatches, each with a fresh salt segment and nonce
+const ROUNDS: u32 = 4;
+
+// thresholds low enough that most dispatches contain a qualifying nonce
+const LEADING_ZEROES: u8 = 1;
+const TOTAL_ZEROES: u8 = 2;
+
+/// Check the OpenCL kernel on the given device (of `platform`, if given)
+/// against the host implementation. A random factory, caller and init code
+/// hash are baked into the kernel, then `count` salts per round are hashed on
+/// the device and every derived address (and its leading / total zero byte
+/// score) is compared with the result of hashing the same preimage with
+/// tiny-keccak on the host. The kernel's solution filter is exercised with low
+/// thresholds and must report exactly the nonces that qualify on the host. Any
+/// divergence is printed together with the inputs that produced it.
+pub fn selftest(platform: Option<u8>, device: u8, count: u32) -> Result<(), Box<dyn Error>> {
+    let config = Config {
+        factory_addresses: vec![FixedBytes::<20>::random().0],
+        calling_addresses: vec![FixedBytes::<20>::random().0],
+        init_code_hash: FixedBytes::<32>::random().0,
+        platform,
+        gpu_device: device,
+        leading_zeroes_threshold: LEADING_ZEROES,
+        total_zeroes_threshold: TOTAL_ZEROES,
+        // leave room for every work item so that no solution can be dropped
+        solutions_fraction: 1.0,
+        ..Default::default()
+    };
+    let factory = Address::from(config.factory_addresses[0]);
+
+    println!(
+        "self-testing device {device} with factory {factory}, caller {} and init code hash 0x{}",
+        Address::from(config.calling_addresses[0]),
+        hex::encode(config.init_code_hash)
+    );
+
+    let ocl_pq = pro_que(&config, count)?;
+    let mut rng = thread_rng();
+    let mut divergences = 0;
+
+    for round in 0..ROUNDS {
+        let message: [u8; 4] = rng.gen();
+        let nonce: u32 = rng.gen();
+        let offset = rng.gen_range(0..=u32::MAX - count);
+
+        let message_buffer = Buffer::builder()
+            .queue(ocl_pq.queue().clone())
+            .flags(MemFlags::new().read_only())
+            .len(4)
+            .copy_host_slice(&message)
+            .build()?;
+
+        // derive every address in the dispatch on the device
+        let mut addresses = vec![0u8; count as usize * 20];
+        let addresses_buffer = Buffer::<u8>::builder()
+            .queue(ocl_pq.queue().clone())
+            .flags(MemFlags::new().write_only())
+            .len(addresses.len())
+            .build()?;
+        let kern = ocl_pq
+            .kernel_builder("hashDigests")
+            .arg_named("message", &message_buffer)
+            .arg_named("nonce", nonce)
+            .arg_named("addresses", &addresses_buffer)
+            .build()?;
+        unsafe { kern.cmd().global_work_offset(offset as usize).enq()? };
+        addresses_buffer.read(&mut addresses).enq()?;
+
+        // run the regular search kernel over the same range
+        let mut solutions: Vec<u64> = vec![0; count as usize];
+        let solutions_buffer = Buffer::builder()
+            .queue(ocl_pq.queue().clone())
+            .flags(MemFlags::new().write_only())
+            .len(solutions.len())
+            .copy_host_slice(&solutions)
+            .build()?;
+        let mut solution_count: [u32; 1] = [0];
+        let solution_count_buffer = Buffer::builder()
+            .queue(ocl_pq.queue().clone())
+            .flags(MemFlags::new().read_write())
+            .len(1)
+            .copy_host_slice(&solution_count)
+            .build()?;
+        let mut solution_addresses: Vec<u8> = vec![0; count as usize * 20];
+        let solution_addresses_buffer = Buffer::builder()
+            .queue(ocl_pq.queue().clone())
+            .flags(MemFlags::new().write_only())
+            .len(solution_addresses.len())
+            .copy_host_slice(&solution_addresses)
+            .build()?;
+        let solution_hashes_buffer = Buffer::<u32>::builder()
+            .queue(ocl_pq.queue().clone())
+            .flags(MemFlags::new().write_only())
+            .len(count as usize)
+            .build()?;
+        let histogram_buffer = Buffer::<u32>::builder()
+            .queue(ocl_pq.queue().clone())
+            .flags(MemFlags::new().read_write())
+            .len(HISTOGRAM_BUCKETS)
+            .fill_val(0)
+            .build()?;
+        let kern = ocl_pq
+            .kernel_builder("hashMessage")
+            .arg_named("message", &message_buffer)
+            .arg_named("nonce", nonce)
+            .arg_named("solutions", &solutions_buffer)
+            .arg_named("solutionCount", &solution_count_buffer)
+            .arg_named("solutionAddresses", &solution_addresses_buffer)
+            .arg_named("solutionHashes", &solution_hashes_buffer)
+            .arg_named("histogram", &histogram_buffer)
+            .build()?;
+        unsafe { kern.cmd().global_work_offset(offset as usize).enq()? };
+        solution_count_buffer.read(&mut solution_count[..]).enq()?;
+        solutions_buffer.read(&mut solutions).enq()?;
+        solution_addresses_buffer
+            .read(&mut solution_addresses)
+            .enq()?;
+
+        let salt_for = |id: u32| {
+            let mut salt = [0u8; 32];
+            salt[..20].copy_from_slice(&config.calling_addresses[0]);
+            salt[20..24].copy_from_slice(&message);
+            salt[24..28].copy_from_slice(&id.to_le_bytes());
+            salt[28..].copy_from_slice(&nonce.to_le_bytes());
+            salt
+        };
+        let qualifies = |(leading, total): (usize, usize)| {
+            leading >= LEADING_ZEROES as usize || total >= TOTAL_ZEROES as usize
+        };
+
+        let mut expected = vec![];
+        for (i, bytes) in addresses.chunks_exact(20).enumerate() {
+            let id = offset + i as u32;
+            let salt = salt_for(id);
+            let host = factory.create2(salt, config.init_code_hash);
+            let derived = Address::from_slice(bytes);
+            if qualifies(zero_bytes(&host)) {
+                expected.push((nonce as u64) << 32 | id as u64);
+            }
+
+            if host != derived {
+                divergences += 1;
+                println!(
+                    "  DIVERGENCE salt 0x{}: host {host} {:?}, device {derived} {:?}",
+                    hex::encode(salt),
+                    zero_bytes(&host),
+                    zero_bytes(&derived)
+                );
+            }
+        }
+
+        // every returned address must match the one derived from its nonce
+        let returned = (solution_count[0] as usize).min(solutions.len());
+        for (solution, bytes) in solutions[..returned]
+            .iter()
+            .zip(solution_addresses.chunks_exact(20))
+        {
+            let salt = salt_for(*solution as u32);
+            let host = factory.create2(salt, config.init_code_hash);
+            let derived = Address::from_slice(bytes);
+            if host != derived {
+                divergences += 1;
+                println!(
+                    "  DIVERGENCE solution salt 0x{}: host {host}, device returned {derived}",
+                    hex::encode(salt)
+                );
+            }
+        }
+
+        // the device must report exactly the nonces the host considers solutions
+        let mut reported = solutions[..returned].to_vec();
+        reported.sort_unstable();
+        if solution_count[0] as usize != expected.len() || reported != expected {
+            divergences += 1;
+            println!(
+                "  DIVERGENCE message 0x{} nonce {nonce:#010x}: device reported {} solutions, host expected {}",
+                hex::encode(message),
+                solution_count[0],
+                expected.len()
+            );
+            for solution in reported.iter().filter(|s| !expected.contains(s)) {
+                let salt = salt_for(*solution as u32);
+                let host = factory.create2(salt, config.init_code_hash);
+                println!(
+                    "    unexpected salt 0x{}: host {host} {:?}",
+                    hex::encode(salt),
+                    zero_bytes(&host)
+                );
+            }
+            for solution in expected.iter().filter(|s| !reported.contains(s)) {
+                println!(
+                    "    missing salt 0x{}",
+                    hex::encode(salt_for(*solution as u32))
+                );
+            }
+        }
+
+        println!("round {}/{ROUNDS}: checked {count} salts", round + 1);
+    }
+
+    if divergences != 0 {
+        return Err(format!("{divergences} divergence(s) between device {device} and host").into());
+    }
+    println!("device {device} matches the host implementation");
+
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use tracing::{info, warn};
+
+// set by the first SIGINT or SIGTERM, which stops every run in the process
+static SIGNALLED: AtomicBool = AtomicBool::new(false);
+
+// the outcome of installing the signal handler, which can only be done once
+static HANDLER: OnceLock<Result<(), String>> = OnceLock::new();
+
+/// Set once the run has been asked to stop, by SIGINT or SIGTERM, from the
+/// dashboard or (in job mode) because the job has changed, so that work in
+/// flight can be finished and recorded before exiting. A second signal exits
+/// at once.
+#[derive(Clone, Default)]
+pub(crate) struct Shutdown(Arc<AtomicBool>);
+
+impl Shutdown {
+    /// Start listening for SIGINT and SIGTERM (or Ctrl-C and Ctrl-Break on
+    /// Windows) if not already listening, and return the flag for a new run.
+    pub(crate) fn install() -> Result<Self, String> {
+        HANDLER
+            .get_or_init(|| {
+                ctrlc::set_handler(|| {
+                    if SIGNALLED.swap(true, Ordering::Relaxed) {
+                        std::process::exit(130);
+                    }
+                    warn!("stopping after the work in flight; interrupt again to exit at once");
+                })
+                .map_err(|e| e.to_string())
+            })
+            .clone()?;
+        Ok(Self::default())
+    }
+
+    /// Whether a signal has been received, which stops the process rather
+    /// than just the current run.
+    pub(crate) fn signalled() -> bool {
+        SIGNALLED.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn request(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Ask the run to stop, logging why unless it has already been asked to.
+    pub(crate) fn request_because(&self, reason: &str) {
+        if !self.0.swap(true, Ordering::Relaxed) && !Self::signalled() {
+            info!("stopping: {reason}");
+        }
+    }
+
+    pub(crate) fn requested(&self) -> bool {
+        self.0.load(Ordering::Relaxed) || Self::signalled()
+    }
+}
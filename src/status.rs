@@ -0,0 +1,216 @@
+use crate::control::{Control, Tuning};
+use crate::{heartbeat, probability, Config};
+use alloy_primitives::{hex, Address};
+use serde_json::{json, Value};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{debug, info};
+
+// how often the status of a run is brought up to date
+const PUBLISH_INTERVAL: Duration = Duration::from_secs(1);
+
+// how long a client gets to send its request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+// the latest status of the process, served from /status
+static STATUS: Mutex<Option<Value>> = Mutex::new(None);
+
+// the parameters of the current run, changed through /control
+static CONTROL: Mutex<Option<Arc<Control>>> = Mutex::new(None);
+
+// the largest request body accepted
+const MAX_BODY: usize = 64 * 1024;
+
+// the outcome of starting the server, which can only be done once
+static SERVER: OnceLock<Result<(), String>> = OnceLock::new();
+
+/// A small HTTP server (`--status-addr`) for orchestrators to supervise the
+/// miner with: `/healthz` answers as long as the process is alive, and
+/// `/status` returns the latest status of the run as JSON, and posting a JSON
+/// object of parameters to `/control` changes them for the rest of the run
+/// (see [`Control::apply`]). The server lives as long as the process, so in
+/// job mode it carries on across jobs.
+pub(crate) struct StatusServer {
+    last: Mutex<Option<Instant>>,
+}
+
+impl StatusServer {
+    /// Start serving on `addr` if not already serving, and return a handle
+    /// for a new run to publish its status through and take changes to its
+    /// parameters from.
+    pub(crate) fn start(addr: &str, control: Arc<Control>) -> Result<Self, String> {
+        SERVER
+            .get_or_init(|| {
+                let listener =
+                    TcpListener::bind(addr).map_err(|e| format!("could not bind {addr}: {e}"))?;
+                info!(
+                    "serving status on http://{}",
+                    listener.local_addr().unwrap()
+                );
+                thread::spawn(move || {
+                    for stream in listener.incoming().flatten() {
+                        thread::spawn(move || {
+                            if let Err(error) = respond(stream) {
+                                debug!("could not answer a status request: {error}");
+                            }
+                        });
+                    }
+                });
+                Ok(())
+            })
+            .clone()?;
+        *CONTROL.lock().unwrap() = Some(control);
+        Ok(Self {
+            last: Mutex::new(None),
+        })
+    }
+
+    /// Whether the status is due to be brought up to date, in which case it
+    /// is taken to be published.
+    pub(crate) fn due(&self) -> bool {
+        let mut last = self.last.lock().unwrap();
+        if last.is_some_and(|last| last.elapsed() < PUBLISH_INTERVAL) {
+            return false;
+        }
+        *last = Some(Instant::now());
+        true
+    }
+
+    pub(crate) fn publish(&self, status: Value) {
+        *STATUS.lock().unwrap() = Some(status);
+    }
+}
+
+impl Drop for StatusServer {
+    /// Mark the run as over, keeping its last status until the next one.
+    fn drop(&mut self) {
+        *CONTROL.lock().unwrap() = None;
+        if let Some(status) = STATUS.lock().unwrap().as_mut() {
+            status["running"] = json!(false);
+        }
+    }
+}
+
+/// The status of a run, extending the heartbeat report with the parameters
+/// being mined for, the best result so far (as the line written to the results
+/// file), an estimate of the time to the next better (or target) score and
+/// when a result was last queued for submission.
+pub(crate) fn report(
+    config: &Config,
+    tuning: &Tuning,
+    uptime: f64,
+    devices: &[(u8, u128)],
+    found: u64,
+    best: Option<&(u128, String)>,
+    last_submission: Option<SystemTime>,
+) -> Value {
+    let rank = best.map(|(rank, _)| *rank);
+    let mut status = heartbeat::report(config, uptime, devices, found, rank);
+    status["running"] = json!(true);
+    status["job"] = json!({
+        "factories": config
+            .factory_addresses
+            .iter()
+            .map(|factory| Address::from(*factory).to_checksum(None))
+            .collect::<Vec<_>>(),
+        "callers": config
+            .calling_addresses
+            .iter()
+            .map(|caller| Address::from(*caller).to_checksum(None))
+            .collect::<Vec<_>>(),
+        "init_code_hash": hex::encode_prefixed(config.init_code_hash),
+        "leading_zeroes_threshold": tuning.leading_zeroes_threshold,
+        "total_zeroes_threshold": tuning.total_zeroes_threshold,
+        "min_score": config.scoring.map(|_| tuning.min_score),
+        "only_improvements": tuning.only_improvements,
+        "stop_at_score": tuning.stop_at_score.map(|score| score.to_string()),
+    });
+    status["best"] = json!(best.map(|(_, line)| line));
+    let attempts = devices.iter().map(|(_, attempts)| attempts).sum();
+    status["estimate"] = match probability::estimate(config, tuning, rank, attempts, uptime) {
+        Some(estimate) => json!({
+            "score": estimate.rank.to_string(),
+            "probability": estimate.probability,
+            "expected_secs": estimate.expected_secs(),
+            "summary": estimate.to_string(),
+        }),
+        None => Value::Null,
+    };
+    status["last_submission"] = json!(last_submission
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|since| since.as_secs()));
+    status
+}
+
+/// Answer a single request, closing the connection afterwards.
+fn respond(stream: TcpStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+
+    // skip the headers other than the length of the body
+    let mut header = String::new();
+    let mut length = 0;
+    while reader.read_line(&mut header)? > 2 {
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse().unwrap_or(0);
+            }
+        }
+        header.clear();
+    }
+
+    let mut parts = request.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let path = path.split('?').next().unwrap_or(path);
+    let (status, content_type, body) = match (method, path) {
+        ("GET", "/healthz") => ("200 OK", "text/plain", "ok\n".to_string()),
+        ("GET", "/status") => {
+            let status = STATUS.lock().unwrap().clone();
+            let status = status.unwrap_or_else(|| json!({ "running": false }));
+            ("200 OK", "application/json", format!("{status}\n"))
+        }
+        ("POST", "/control") if length > MAX_BODY => (
+            "413 Payload Too Large",
+            "text/plain",
+            "request too large\n".to_string(),
+        ),
+        ("POST", "/control") => {
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body)?;
+            let control = CONTROL.lock().unwrap().clone();
+            let applied = match control {
+                Some(control) => serde_json::from_slice(&body)
+                    .map_err(|e| format!("invalid JSON: {e}"))
+                    .and_then(|changes| control.apply(&changes))
+                    .map_err(|error| ("400 Bad Request", error)),
+                None => Err(("409 Conflict", "no run is in progress".to_string())),
+            };
+            match applied {
+                Ok(tuning) => (
+                    "200 OK",
+                    "application/json",
+                    format!("{}\n", tuning.report()),
+                ),
+                Err((status, error)) => (status, "text/plain", format!("{error}\n")),
+            }
+        }
+        ("GET", _) | ("POST", _) => ("404 Not Found", "text/plain", "not found\n".to_string()),
+        _ => (
+            "405 Method Not Allowed",
+            "text/plain",
+            "method not allowed\n".to_string(),
+        ),
+    };
+
+    write!(
+        &stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
@@ -0,0 +1,673 @@
+use crate::websocket::Streamer;
+use alloy_primitives::{hex, keccak256};
+use reqwest::blocking::Client;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::StatusCode;
+use serde_json::Value;
+use std::error::Error;
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime};
+use tracing::{debug, info, warn};
+
+// the first delay before retrying spooled submissions, doubled after every
+// pass that leaves some of them behind
+const RETRY_MIN_DELAY: Duration = Duration::from_secs(5);
+
+// the longest delay between retries
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(600);
+
+// results queued for each endpoint when there is no memory budget
+const DEFAULT_QUEUE_LEN: usize = 10_000;
+
+// a generous estimate of the memory taken by one queued result
+const RESULT_BYTES: usize = 2048;
+
+/// Posts results to each endpoint from a reporter thread of its own, so that
+/// a slow endpoint never holds up mining (or the other endpoints). The given
+/// headers (such as an `Authorization` token) are sent with every request.
+/// With a batch size above one, results are collected for up to the batch
+/// interval and posted as a JSON array.
+///
+/// Submissions that fail with a network error or a server-side status are
+/// appended to a JSONL spool file, which a background thread retries with
+/// exponential backoff until they go through. The spool outlives the process,
+/// so anything left in it is retried on the next run. With several endpoints,
+/// each one is spooled to a file of its own.
+///
+/// With a WebSocket endpoint, results are streamed over it instead while it is
+/// connected, and only posted to the other endpoints while it is not.
+///
+/// The results waiting for each endpoint are held in a bounded queue (taking
+/// together at most a quarter of `--max-memory`, if given), and any that do
+/// not fit while an endpoint falls behind go straight to its spool. The spool
+/// is retried a queue's worth of results at a time, so it never has to fit in
+/// memory either.
+pub(crate) struct Submitter {
+    reporters: Vec<Reporter>,
+    streamer: Option<Streamer>,
+    last_submitted: Mutex<Option<SystemTime>>,
+}
+
+/// The reporter thread of one endpoint.
+struct Reporter {
+    queue: Queue,
+    thread: JoinHandle<()>,
+    last_status: Arc<Mutex<Option<String>>>,
+}
+
+/// The queue of results waiting to be posted to one endpoint, which spills
+/// over into its spool once full.
+#[derive(Clone)]
+pub(crate) struct Queue {
+    results: mpsc::SyncSender<Value>,
+    poster: Arc<Poster>,
+    full: Arc<AtomicBool>,
+}
+
+impl Queue {
+    /// Queue a result, or spool it for a retry if the queue is full.
+    pub(crate) fn push(&self, payload: Value) {
+        match self.results.try_send(payload) {
+            Ok(()) => self.full.store(false, Ordering::Relaxed),
+            Err(mpsc::TrySendError::Full(payload)) => {
+                if !self.full.swap(true, Ordering::Relaxed) {
+                    warn!(
+                        "{} is falling behind, spooling results until it catches up",
+                        self.poster.endpoint
+                    );
+                }
+                if let Err(error) = self.poster.spool.push(&payload) {
+                    warn!("could not spool result: {error}");
+                }
+            }
+            // the reporter only stops once every sender is dropped
+            Err(mpsc::TrySendError::Disconnected(_)) => unreachable!(),
+        }
+    }
+}
+
+impl Submitter {
+    pub(crate) fn new(
+        endpoints: &[String],
+        spool: &Path,
+        batch_size: usize,
+        batch_interval: Duration,
+        headers: &[(String, String)],
+        ws_endpoint: Option<&str>,
+        max_memory: Option<usize>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let client = client(headers)?;
+        let queue_len = queue_len(max_memory, endpoints.len() + ws_endpoint.is_some() as usize);
+
+        let reporters = endpoints
+            .iter()
+            .map(|endpoint| {
+                let spool = match endpoints.len() {
+                    1 => spool.to_path_buf(),
+                    _ => endpoint_spool(spool, endpoint),
+                };
+                let poster = Arc::new(Poster {
+                    client: client.clone(),
+                    endpoint: endpoint.clone(),
+                    batch_size,
+                    chunk_len: queue_len.max(batch_size),
+                    spool: Spool {
+                        path: spool,
+                        lock: Mutex::new(()),
+                    },
+                });
+                let last_status = Arc::new(Mutex::new(None));
+
+                let retry = poster.clone();
+                thread::spawn(move || retry.retry_spooled());
+
+                let (results, received) = mpsc::sync_channel(queue_len);
+                let queue = Queue {
+                    results,
+                    poster: poster.clone(),
+                    full: Arc::new(AtomicBool::new(false)),
+                };
+                let status = last_status.clone();
+                let thread =
+                    thread::spawn(move || poster.report(&received, batch_interval, &status));
+
+                Reporter {
+                    queue,
+                    thread,
+                    last_status,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let streamer = ws_endpoint
+            .map(|url| {
+                let fallback = reporters.iter().map(|r| r.queue.clone()).collect();
+                Streamer::new(url.to_string(), headers, fallback, queue_len)
+            })
+            .transpose()?;
+
+        Ok(Self {
+            reporters,
+            streamer,
+            last_submitted: Mutex::new(None),
+        })
+    }
+
+    /// Queue a result for the WebSocket endpoint if it is connected, or else
+    /// for every other endpoint.
+    pub(crate) fn submit(&self, payload: Value) {
+        match &self.streamer {
+            Some(streamer) if streamer.connected() => streamer.send(payload),
+            _ => {
+                for reporter in &self.reporters {
+                    reporter.queue.push(payload.clone());
+                }
+            }
+        }
+        *self.last_submitted.lock().unwrap() = Some(SystemTime::now());
+    }
+
+    /// When a result was last queued for the endpoints.
+    pub(crate) fn last_submitted(&self) -> Option<SystemTime> {
+        *self.last_submitted.lock().unwrap()
+    }
+
+    /// The outcome of the most recent post, if any, to each endpoint (which
+    /// is named if there are several).
+    pub(crate) fn last_status(&self) -> Option<String> {
+        let statuses: Vec<String> = self
+            .reporters
+            .iter()
+            .filter_map(|reporter| {
+                let status = reporter.last_status.lock().unwrap().clone()?;
+                Some(match self.reporters.len() {
+                    1 => status,
+                    _ => format!("{} {status}", reporter.queue.poster.endpoint),
+                })
+            })
+            .collect();
+        (!statuses.is_empty()).then(|| statuses.join("; "))
+    }
+
+    /// Wait for the queued results to be posted (or spooled).
+    pub(crate) fn finish(self) {
+        // the streamer may still hand results over to the reporters
+        if let Some(streamer) = self.streamer {
+            streamer.finish();
+        }
+        for reporter in self.reporters {
+            drop(reporter.queue);
+            let _ = reporter.thread.join();
+        }
+    }
+}
+
+/// How many results can be queued for each of `queues` endpoints: as many as
+/// fit in a quarter of the memory budget between them, or else a fixed number.
+fn queue_len(max_memory: Option<usize>, queues: usize) -> usize {
+    match max_memory {
+        Some(bytes) => (bytes / 4 / RESULT_BYTES / queues.max(1)).max(1),
+        None => DEFAULT_QUEUE_LEN,
+    }
+}
+
+/// An HTTP client that sends the given headers with every request.
+pub(crate) fn client(headers: &[(String, String)]) -> Result<Client, Box<dyn Error>> {
+    let mut header_map = HeaderMap::new();
+    for (name, value) in headers {
+        header_map.append(
+            HeaderName::from_bytes(name.as_bytes())?,
+            HeaderValue::from_str(value)?,
+        );
+    }
+    Ok(Client::builder().default_headers(header_map).build()?)
+}
+
+/// The spool file of one of several endpoints: the given path with a hash of
+/// the endpoint added to the file name, so that it stays the same across runs.
+fn endpoint_spool(spool: &Path, endpoint: &str) -> PathBuf {
+    let tag = hex::encode(&keccak256(endpoint)[..4]);
+    let stem = spool.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match spool.extension() {
+        Some(extension) => format!("{stem}-{tag}.{}", extension.to_string_lossy()),
+        None => format!("{stem}-{tag}"),
+    };
+    spool.with_file_name(name)
+}
+
+/// The endpoint and spool shared by the reporter and retry threads.
+struct Poster {
+    client: Client,
+    endpoint: String,
+    batch_size: usize,
+    // how many spooled results are read into memory at a time
+    chunk_len: usize,
+    spool: Spool,
+}
+
+impl Poster {
+    /// Post results as they arrive, in batches if so configured, until the
+    /// channel is closed.
+    fn report(
+        &self,
+        received: &mpsc::Receiver<Value>,
+        batch_interval: Duration,
+        last_status: &Mutex<Option<String>>,
+    ) {
+        while let Ok(first) = received.recv() {
+            let deadline = Instant::now() + batch_interval;
+            let mut batch = vec![first];
+            while batch.len() < self.batch_size {
+                let timeout = deadline.saturating_duration_since(Instant::now());
+                match received.recv_timeout(timeout) {
+                    Ok(payload) => batch.push(payload),
+                    Err(_) => break,
+                }
+            }
+
+            let subject = match &batch[..] {
+                [payload] => payload["address"].as_str().unwrap_or("result").to_string(),
+                batch => format!("{} results", batch.len()),
+            };
+            let outcome = match self.post(&batch) {
+                Ok(status) => status.to_string(),
+                Err(error) => {
+                    warn!(
+                        "could not submit {subject} to {}, spooling for a retry: {error}",
+                        self.endpoint
+                    );
+                    for payload in &batch {
+                        if let Err(error) = self.spool.push(payload) {
+                            warn!("could not spool result: {error}");
+                        }
+                    }
+                    format!("{error} (will retry)")
+                }
+            };
+            *last_status.lock().unwrap() = Some(format!("{subject}: {outcome}"));
+        }
+    }
+
+    /// Post results, as a single object or else as an array if batching,
+    /// failing on errors that may go away on a retry. Other client errors
+    /// (such as a rejected payload) are logged and dropped.
+    fn post(&self, batch: &[Value]) -> Result<StatusCode, String> {
+        let request = match (self.batch_size, batch) {
+            (1, [payload]) => self.client.post(&self.endpoint).json(payload),
+            _ => self.client.post(&self.endpoint).json(batch),
+        };
+        let status = request.send().map_err(|e| e.to_string())?.status();
+        if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS {
+            return Err(format!("endpoint returned {status}"));
+        }
+        if !status.is_success() {
+            warn!(
+                "{} rejected {} results with {status}",
+                self.endpoint,
+                batch.len()
+            );
+        }
+        Ok(status)
+    }
+
+    /// Retry the spooled submissions forever, backing off while they fail.
+    fn retry_spooled(&self) {
+        let mut delay = RETRY_MIN_DELAY;
+        loop {
+            thread::sleep(delay);
+
+            let (submitted, left) = match self.retry_pass() {
+                Ok(pass) => pass,
+                Err(error) => {
+                    warn!("could not retry {}: {error}", self.spool.path.display());
+                    continue;
+                }
+            };
+
+            if left == 0 {
+                if submitted > 0 {
+                    info!("submitted {submitted} spooled results");
+                }
+                delay = RETRY_MIN_DELAY;
+            } else {
+                delay = (delay * 2).min(RETRY_MAX_DELAY);
+                debug!(submitted, left, "retrying spooled results in {delay:?}");
+            }
+        }
+    }
+
+    /// Post the spooled submissions in order, in batches of the configured
+    /// size and reading at most `chunk_len` of them at a time, until one
+    /// fails. Those that went through (or can no longer be parsed) are then
+    /// removed from the spool. Returns how many were submitted and how many
+    /// are left.
+    fn retry_pass(&self) -> io::Result<(usize, usize)> {
+        let mut submitted = 0;
+        let mut malformed = 0;
+        // the end of the spooled lines dealt with so far
+        let mut done = 0;
+        'pass: loop {
+            let lines = self.spool.read(done, self.chunk_len)?;
+            let Some(&(_, last)) = lines.last() else {
+                break;
+            };
+
+            let mut batch = Vec::with_capacity(self.batch_size);
+            let mut skipped = 0;
+            for (line, end) in &lines {
+                match serde_json::from_str::<Value>(line) {
+                    Ok(payload) => batch.push(payload),
+                    Err(_) => skipped += 1,
+                }
+                let full = batch.len() == self.batch_size || *end == last;
+                if batch.is_empty() || !full {
+                    continue;
+                }
+                // the remaining results are retried on the next pass, in
+                // order, rather than hammering an endpoint that is down
+                if self.post(&batch).is_err() {
+                    break 'pass;
+                }
+                submitted += batch.len();
+                malformed += skipped;
+                skipped = 0;
+                batch.clear();
+                done = *end;
+            }
+            // the chunk may end in lines that cannot be parsed
+            malformed += skipped;
+            done = last;
+        }
+
+        if malformed > 0 {
+            warn!("dropping {malformed} malformed spooled results");
+        }
+        let left = self.spool.remove(done)?;
+        Ok((submitted, left))
+    }
+}
+
+/// A JSONL file of submissions waiting to be retried.
+struct Spool {
+    path: PathBuf,
+    // serializes access from the reporter and retry threads
+    lock: Mutex<()>,
+}
+
+impl Spool {
+    fn push(&self, payload: &Value) -> io::Result<()> {
+        let _guard = self.lock.lock().unwrap();
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{payload}")
+    }
+
+    /// Read up to `limit` lines from the given offset, each with the offset
+    /// just past it.
+    fn read(&self, offset: u64, limit: usize) -> io::Result<Vec<(String, u64)>> {
+        let _guard = self.lock.lock().unwrap();
+        let Some(mut file) = self.open()? else {
+            return Ok(vec![]);
+        };
+        file.seek(SeekFrom::Start(offset))?;
+
+        let mut lines = Vec::new();
+        let mut end = offset;
+        let mut line = String::new();
+        while lines.len() < limit {
+            line.clear();
+            match file.read_line(&mut line)? {
+                0 => break,
+                n => end += n as u64,
+            }
+            if !line.trim().is_empty() {
+                lines.push((line.trim_end().to_string(), end));
+            }
+        }
+        Ok(lines)
+    }
+
+    /// Remove everything before the given offset (keeping whatever has been
+    /// appended since it was read) and return how many lines are left.
+    fn remove(&self, offset: u64) -> io::Result<usize> {
+        let _guard = self.lock.lock().unwrap();
+        let Some(mut file) = self.open()? else {
+            return Ok(0);
+        };
+        file.seek(SeekFrom::Start(offset))?;
+
+        // copy the rest a line at a time (unless it is the whole file),
+        // counting the lines
+        let tmp = self.path.with_extension("tmp");
+        let mut rest = match offset {
+            0 => None,
+            _ => Some(BufWriter::new(fs::File::create(&tmp)?)),
+        };
+        let mut left = 0;
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            if file.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+            if !line.trim_ascii().is_empty() {
+                if let Some(rest) = &mut rest {
+                    rest.write_all(&line)?;
+                }
+                left += 1;
+            }
+        }
+
+        if let Some(rest) = rest {
+            rest.into_inner().map_err(|e| e.into_error())?;
+            match left {
+                0 => fs::remove_file(&tmp)?,
+                _ => fs::rename(&tmp, &self.path)?,
+            }
+        }
+        if left == 0 {
+            fs::remove_file(&self.path)?;
+        }
+        Ok(left)
+    }
+
+    /// The spool file, if there is one.
+    fn open(&self) -> io::Result<Option<BufReader<fs::File>>> {
+        match fs::File::open(&self.path) {
+            Ok(file) => Ok(Some(BufReader::new(file))),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::io::Read;
+    use std::net::TcpListener;
+
+    fn spool(name: &str) -> Spool {
+        let path =
+            std::env::temp_dir().join(format!("create2crunch-{}-{name}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+        Spool {
+            path,
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn lines(spool: &Spool) -> Vec<String> {
+        spool
+            .read(0, usize::MAX)
+            .unwrap()
+            .into_iter()
+            .map(|(line, _)| line)
+            .collect()
+    }
+
+    /// An endpoint that answers each request with the next of the given
+    /// statuses, and the bodies it was sent.
+    fn endpoint(statuses: &[u16]) -> (String, JoinHandle<Vec<Value>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let statuses = statuses.to_vec();
+        let thread = thread::spawn(move || {
+            let mut bodies = Vec::new();
+            for status in statuses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut stream = BufReader::new(stream);
+                let mut length = 0;
+                loop {
+                    let mut header = String::new();
+                    stream.read_line(&mut header).unwrap();
+                    let header = header.trim_end().to_ascii_lowercase();
+                    if header.is_empty() {
+                        break;
+                    }
+                    if let Some(value) = header.strip_prefix("content-length:") {
+                        length = value.trim().parse().unwrap();
+                    }
+                }
+                let mut body = vec![0; length];
+                stream.read_exact(&mut body).unwrap();
+                bodies.push(serde_json::from_slice(&body).unwrap());
+                write!(
+                    stream.get_mut(),
+                    "HTTP/1.1 {status} STATUS\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                )
+                .unwrap();
+            }
+            bodies
+        });
+        (url, thread)
+    }
+
+    fn poster(endpoint: String, batch_size: usize, chunk_len: usize, spool: Spool) -> Poster {
+        Poster {
+            client: Client::new(),
+            endpoint,
+            batch_size,
+            chunk_len,
+            spool,
+        }
+    }
+
+    #[test]
+    fn endpoint_spools() {
+        let spool = Path::new("spool/failed.jsonl");
+        let a = endpoint_spool(spool, "https://a.example/results");
+        let b = endpoint_spool(spool, "https://b.example/results");
+        assert_eq!(a, endpoint_spool(spool, "https://a.example/results"));
+        assert_ne!(a, b);
+        assert_eq!(a.parent(), Some(Path::new("spool")));
+        let name = a.file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with("failed-") && name.ends_with(".jsonl"));
+        assert_eq!(name.len(), "failed-.jsonl".len() + 8);
+
+        let bare = endpoint_spool(Path::new("failed"), "https://a.example/results");
+        assert_eq!(bare.to_str().unwrap(), &name[..name.len() - 6]);
+    }
+
+    #[test]
+    fn removes_before_an_offset() {
+        let spool = spool("remove");
+        assert!(spool.read(0, 10).unwrap().is_empty());
+        assert_eq!(spool.remove(0).unwrap(), 0);
+
+        for i in 0..5 {
+            spool.push(&json!({ "i": i })).unwrap();
+        }
+        let read = spool.read(0, 2).unwrap();
+        assert_eq!(read.len(), 2);
+        // appended after reading, and kept
+        spool.push(&json!({ "i": 5 })).unwrap();
+
+        assert_eq!(spool.remove(read[1].1).unwrap(), 4);
+        assert_eq!(
+            lines(&spool),
+            [r#"{"i":2}"#, r#"{"i":3}"#, r#"{"i":4}"#, r#"{"i":5}"#]
+        );
+        // from the start nothing is removed
+        assert_eq!(spool.remove(0).unwrap(), 4);
+        assert_eq!(lines(&spool).len(), 4);
+
+        let end = spool.read(0, 10).unwrap().last().unwrap().1;
+        assert_eq!(spool.remove(end).unwrap(), 0);
+        assert!(!spool.path.exists());
+    }
+
+    #[test]
+    fn posts_by_status() {
+        let (url, server) = endpoint(&[200, 400, 429, 503]);
+        let poster = poster(url, 1, 1, spool("post"));
+        let payload = [json!({ "address": "0x01" })];
+
+        assert_eq!(poster.post(&payload), Ok(StatusCode::OK));
+        // rejected, and not worth a retry
+        assert_eq!(poster.post(&payload), Ok(StatusCode::BAD_REQUEST));
+        assert!(poster.post(&payload).is_err());
+        assert!(poster.post(&payload).is_err());
+        assert_eq!(server.join().unwrap(), vec![payload[0].clone(); 4]);
+    }
+
+    #[test]
+    fn posts_batches_as_arrays() {
+        let (url, server) = endpoint(&[200]);
+        let poster = poster(url, 2, 2, spool("batch"));
+        let batch = [json!({ "i": 0 }), json!({ "i": 1 })];
+        assert_eq!(poster.post(&batch), Ok(StatusCode::OK));
+        assert_eq!(server.join().unwrap(), [json!(batch)]);
+    }
+
+    #[test]
+    fn retries_until_a_failure() {
+        let (url, server) = endpoint(&[200, 200, 500]);
+        let poster = poster(url, 2, 3, spool("retry"));
+        for i in 0..4 {
+            poster.spool.push(&json!({ "i": i })).unwrap();
+        }
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(&poster.spool.path)
+            .unwrap();
+        writeln!(file, "not json").unwrap();
+        for i in 4..8 {
+            poster.spool.push(&json!({ "i": i })).unwrap();
+        }
+
+        // the first chunk of three goes out as a pair and a single, and the
+        // next pair (around the malformed line) fails and stops the pass
+        assert_eq!(poster.retry_pass().unwrap(), (3, 6));
+        assert_eq!(
+            server.join().unwrap(),
+            [
+                json!([{ "i": 0 }, { "i": 1 }]),
+                json!([{ "i": 2 }]),
+                json!([{ "i": 3 }, { "i": 4 }]),
+            ]
+        );
+        assert_eq!(
+            lines(&poster.spool)[..3],
+            [r#"{"i":3}"#, "not json", r#"{"i":4}"#]
+        );
+
+        // once the endpoint is back the rest go through, less the malformed line
+        let (url, server) = endpoint(&[200, 200, 200]);
+        let poster = Poster {
+            endpoint: url,
+            ..poster
+        };
+        assert_eq!(poster.retry_pass().unwrap(), (5, 0));
+        assert_eq!(server.join().unwrap().len(), 3);
+        assert!(!poster.spool.path.exists());
+    }
+}
@@ -0,0 +1,139 @@
+use crate::WorkerStatus;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::Stylize;
+use ratatui::text::Line;
+use ratatui::widgets::{Block, List, Paragraph};
+use ratatui::DefaultTerminal;
+use separator::Separatable;
+use std::collections::VecDeque;
+use std::io;
+use std::time::Duration;
+
+/// What the dashboard shows of a GPU run.
+pub(crate) struct Snapshot<'a> {
+    pub(crate) runtime: f64,
+    pub(crate) workers: &'a [WorkerStatus],
+    pub(crate) found: u64,
+    pub(crate) best: Option<&'a str>,
+    pub(crate) estimate: Option<String>,
+    pub(crate) submission: Option<&'a str>,
+    pub(crate) warnings: Vec<String>,
+    pub(crate) recent: &'a VecDeque<String>,
+}
+
+/// A full-screen dashboard (`--tui`) drawn on the alternate screen in place of
+/// the plain status output. The terminal is restored when it is dropped.
+pub(crate) struct Dashboard {
+    terminal: DefaultTerminal,
+}
+
+impl Dashboard {
+    pub(crate) fn new() -> io::Result<Self> {
+        Ok(Self {
+            terminal: ratatui::try_init()?,
+        })
+    }
+
+    /// Whether q, Esc or Ctrl-C has been pressed; the terminal is in raw mode,
+    /// so Ctrl-C does not interrupt the process by itself.
+    pub(crate) fn quit_requested(&self) -> io::Result<bool> {
+        while event::poll(Duration::ZERO)? {
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            let ctrl_c = key.code == KeyCode::Char('c') && key.modifiers == KeyModifiers::CONTROL;
+            if key.kind == KeyEventKind::Press
+                && (ctrl_c || matches!(key.code, KeyCode::Char('q') | KeyCode::Esc))
+            {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    pub(crate) fn draw(&mut self, snapshot: &Snapshot<'_>) -> io::Result<()> {
+        let runtime = snapshot.runtime as u64;
+        let rate = |hashes: u128| hashes as f64 / 1_000_000.0 / snapshot.runtime.max(1.0);
+        let hashes: u128 = snapshot.workers.iter().map(|w| w.hashes).sum();
+        let dispatches: u64 = snapshot.workers.iter().map(|w| w.dispatches).sum();
+
+        let mut lines = vec![
+            Line::from(format!(
+                "runtime {}:{:02}:{:02}   dispatches {}   attempts {}",
+                runtime / 3600,
+                runtime / 60 % 60,
+                runtime % 60,
+                dispatches.separated_string(),
+                hashes.separated_string(),
+            )),
+            Line::from(format!(
+                "rate {:.2} million attempts per second   found {}",
+                rate(hashes),
+                snapshot.found,
+            ))
+            .bold(),
+            Line::from(format!("best {}", snapshot.best.unwrap_or("-"))),
+            Line::from(format!(
+                "estimate {}",
+                snapshot.estimate.as_deref().unwrap_or("-")
+            )),
+            Line::from(format!(
+                "last submission {}",
+                snapshot.submission.unwrap_or("-")
+            )),
+        ];
+        for status in snapshot.workers {
+            if status.device == 255 {
+                let line = format!("CPU ({:.2} million per second)", rate(status.hashes));
+                lines.push(Line::from(line));
+                continue;
+            }
+            lines.push(Line::from(format!(
+                "device {} {}({:.2} million per second, work size {:#x}{}): {}",
+                status.device,
+                match status.name.as_str() {
+                    "" => String::new(),
+                    name => format!("{name} "),
+                },
+                rate(status.hashes),
+                status.work_size,
+                status
+                    .sample
+                    .map_or(String::new(), |sample| format!(", {sample}")),
+                status.search_space,
+            )));
+        }
+        for warning in &snapshot.warnings {
+            lines.push(Line::from(warning.as_str()).yellow());
+        }
+
+        self.terminal.draw(|frame| {
+            let [summary, recent, footer] = Layout::vertical([
+                Constraint::Length(lines.len() as u16 + 2),
+                Constraint::Min(3),
+                Constraint::Length(1),
+            ])
+            .areas(frame.area());
+
+            frame.render_widget(
+                Paragraph::new(lines).block(Block::bordered().title(" create2crunch ")),
+                summary,
+            );
+            frame.render_widget(
+                List::new(snapshot.recent.iter().rev().map(String::as_str))
+                    .block(Block::bordered().title(" recently found ")),
+                recent,
+            );
+            frame.render_widget(Line::from("press q to quit").dim(), footer);
+        })?;
+
+        Ok(())
+    }
+}
+
+impl Drop for Dashboard {
+    fn drop(&mut self) {
+        ratatui::restore();
+    }
+}
@@ -0,0 +1,335 @@
+use crate::control::Tuning;
+use crate::{
+    mode, solution_slots, Backend, Config, Dispatch, Mode, Scoring, CONTROL_CHARACTER,
+    HISTOGRAM_BUCKETS, HISTOGRAM_MIN_NIBBLES,
+};
+use std::sync::mpsc;
+use tracing::info;
+use wgpu::util::DeviceExt;
+
+static KERNEL_SRC: &str = include_str!("./kernels/keccak256.wgsl");
+
+// work items per workgroup
+const WORKGROUP_SIZE: u32 = 256;
+
+// workgroups per row of a dispatch; larger dispatches add rows, as a single
+// dimension is limited to 65535 workgroups
+const WORKGROUPS_PER_ROW: u32 = 1 << 15;
+
+/// Dispatches work through wgpu, which runs on Metal, Vulkan or DX12 where
+/// there is no usable OpenCL platform (e.g. on Apple Silicon). The salt layout
+/// and solutions buffer follow the OpenCL kernel.
+pub(crate) struct Wgpu {
+    name: String,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+    bind_group: wgpu::BindGroup,
+    params: wgpu::Buffer,
+    preimage: wgpu::Buffer,
+    solutions: wgpu::Buffer,
+    solution_count: wgpu::Buffer,
+    solution_addresses: wgpu::Buffer,
+    histogram: wgpu::Buffer,
+    readback: wgpu::Buffer,
+    // the padded preimage of each target, with the message (other than the
+    // salt prefix) and nonce empty
+    preimages: Vec<[u8; 200]>,
+    prefix_len: usize,
+    slots: usize,
+    leading_zeroes: u32,
+    total_zeroes: u32,
+    histogram_enabled: bool,
+    scoring: Option<Scoring>,
+}
+
+impl Wgpu {
+    /// Set up the adapter with the given index for dispatches of up to
+    /// `work_size`, searching the given factories and callers.
+    pub(crate) fn new(
+        config: &Config,
+        tuning: &Tuning,
+        device: u8,
+        targets: &[(usize, [u8; 20], [u8; 20])],
+        work_size: u32,
+    ) -> ocl::Result<Self> {
+        let instance = wgpu::Instance::new(&Default::default());
+        let adapter = instance
+            .enumerate_adapters(wgpu::Backends::all())
+            .into_iter()
+            .nth(device as usize)
+            .ok_or_else(|| format!("no OpenCL platform or wgpu adapter {device} found"))?;
+        let name = adapter.get_info().name;
+        info!("no OpenCL platform found, using {name} through wgpu");
+
+        if !config.extra_init_code_hashes.is_empty() {
+            return Err("several init code hashes can only be mined with OpenCL".into());
+        }
+
+        let (device, queue) = pollster::block_on(adapter.request_device(&Default::default()))
+            .map_err(|e| e.to_string())?;
+
+        let mut src = format!(
+            "const HISTOGRAM_BUCKETS: u32 = {HISTOGRAM_BUCKETS}u;\n\
+             const HISTOGRAM_MIN_NIBBLES: u32 = {HISTOGRAM_MIN_NIBBLES}u;\n\
+             const WORKGROUP_SIZE: u32 = {WORKGROUP_SIZE}u;\n\
+             const CREATE3: bool = {};\n\
+             const PATTERN: bool = {};\n",
+            config.mode == Mode::Create3,
+            config.pattern.is_some()
+        );
+        let words = config
+            .pattern
+            .map_or([(0, 0); 5], |pattern| pattern.words());
+        for (i, (mask, value)) in words.iter().enumerate() {
+            src += &format!(
+                "const PATTERN_MASK_{i}: u32 = 0x{mask:08x}u;\n\
+                 const PATTERN_VALUE_{i}: u32 = 0x{value:08x}u;\n"
+            );
+        }
+        src += KERNEL_SRC;
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("keccak256"),
+            source: wgpu::ShaderSource::Wgsl(src.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("hashMessage"),
+            layout: None,
+            module: &module,
+            entry_point: Some("hashMessage"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        // the number of solutions that can be returned from a single dispatch
+        let slots = solution_slots(config, work_size);
+
+        let storage = wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC;
+        let buffer = |label, size: usize, usage| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: size as u64,
+                usage: usage | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            })
+        };
+        let params = buffer("params", 64, wgpu::BufferUsages::UNIFORM);
+        let preimage = buffer("preimage", 200, wgpu::BufferUsages::STORAGE);
+        let solutions = buffer("solutions", slots * 8, storage);
+        let solution_count = buffer("solutionCount", 4, storage);
+        let solution_addresses = buffer("solutionAddresses", slots * 20, storage);
+        let histogram = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("histogram"),
+            contents: &[0; HISTOGRAM_BUCKETS * 4],
+            usage: storage | wgpu::BufferUsages::COPY_DST,
+        });
+
+        // the count, histogram, solutions and addresses are copied back in one go
+        let readback = buffer(
+            "readback",
+            4 + HISTOGRAM_BUCKETS * 4 + slots * 28,
+            wgpu::BufferUsages::MAP_READ,
+        );
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &[
+                &params,
+                &preimage,
+                &solutions,
+                &solution_count,
+                &solution_addresses,
+                &histogram,
+            ]
+            .iter()
+            .enumerate()
+            .map(|(binding, buffer)| wgpu::BindGroupEntry {
+                binding: binding as u32,
+                resource: buffer.as_entire_binding(),
+            })
+            .collect::<Vec<_>>(),
+        });
+
+        // 0xff ++ factory ++ caller ++ message ++ nonce ++ init code hash, then
+        // the padding
+        let preimages = targets
+            .iter()
+            .map(|(_, factory, caller)| {
+                let mut preimage = [0; 200];
+                preimage[0] = CONTROL_CHARACTER;
+                preimage[1..21].copy_from_slice(factory);
+                preimage[21..41].copy_from_slice(caller);
+                preimage[41..41 + config.salt_prefix.len()].copy_from_slice(&config.salt_prefix);
+                preimage[53..85].copy_from_slice(mode::create2_hash(config));
+                preimage[85] = 0x01;
+                preimage[135] = 0x80;
+                preimage
+            })
+            .collect();
+
+        let mut wgpu = Self {
+            name,
+            device,
+            queue,
+            pipeline,
+            bind_group,
+            params,
+            preimage,
+            solutions,
+            solution_count,
+            solution_addresses,
+            histogram,
+            readback,
+            preimages,
+            prefix_len: config.salt_prefix.len(),
+            slots,
+            leading_zeroes: 0,
+            total_zeroes: 0,
+            histogram_enabled: config.histogram,
+            scoring: config.scoring,
+        };
+        wgpu.retune(tuning);
+        Ok(wgpu)
+    }
+}
+
+impl Backend for Wgpu {
+    fn dispatch(
+        &mut self,
+        target: usize,
+        message: &[u8; 4],
+        nonce: u32,
+        work_size: u32,
+    ) -> ocl::Result<Dispatch> {
+        // pass the preimage with the message in place, and the parameters
+        let mut preimage = self.preimages[target];
+        preimage[41 + self.prefix_len..45].copy_from_slice(&message[self.prefix_len..]);
+        self.queue.write_buffer(&self.preimage, 0, &preimage);
+
+        let scoring = self.scoring.unwrap_or_default();
+        let params = [
+            nonce,
+            self.leading_zeroes,
+            self.total_zeroes,
+            self.slots as u32,
+            self.histogram_enabled as u32,
+            self.scoring.is_some() as u32,
+            scoring.target_nibble as u32,
+            scoring.leading_weight,
+            scoring.run_bonus,
+            scoring.run_end_bonus,
+            scoring.tail_bonus,
+            scoring.nibble_weight,
+            scoring.min_score,
+            scoring.require_target_first as u32,
+        ];
+        let params: Vec<u8> = params.iter().flat_map(|p| p.to_le_bytes()).collect();
+        self.queue.write_buffer(&self.params, 0, &params);
+
+        // reset the solution count and the histogram
+        self.queue.write_buffer(&self.solution_count, 0, &[0; 4]);
+        if self.histogram_enabled {
+            let histogram = [0; HISTOGRAM_BUCKETS * 4];
+            self.queue.write_buffer(&self.histogram, 0, &histogram);
+        }
+
+        // run the kernel, then copy the results back for reading; the last
+        // row may run a few more work items than asked for
+        let workgroups = work_size.div_ceil(WORKGROUP_SIZE);
+        let columns = workgroups.min(WORKGROUPS_PER_ROW);
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        {
+            let mut pass = encoder.begin_compute_pass(&Default::default());
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &self.bind_group, &[]);
+            pass.dispatch_workgroups(columns, workgroups.div_ceil(columns), 1);
+        }
+        let histogram_offset = 4;
+        let solutions_offset = histogram_offset + HISTOGRAM_BUCKETS as u64 * 4;
+        let addresses_offset = solutions_offset + self.slots as u64 * 8;
+        encoder.copy_buffer_to_buffer(&self.solution_count, 0, &self.readback, 0, 4);
+        encoder.copy_buffer_to_buffer(
+            &self.histogram,
+            0,
+            &self.readback,
+            histogram_offset,
+            HISTOGRAM_BUCKETS as u64 * 4,
+        );
+        encoder.copy_buffer_to_buffer(
+            &self.solutions,
+            0,
+            &self.readback,
+            solutions_offset,
+            self.slots as u64 * 8,
+        );
+        encoder.copy_buffer_to_buffer(
+            &self.solution_addresses,
+            0,
+            &self.readback,
+            addresses_offset,
+            self.slots as u64 * 20,
+        );
+        self.queue.submit([encoder.finish()]);
+
+        // wait for the dispatch and the copies to complete
+        let slice = self.readback.slice(..);
+        let (sender, receiver) = mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        self.device
+            .poll(wgpu::PollType::Wait)
+            .map_err(|e| e.to_string())?;
+        receiver
+            .recv()
+            .map_err(|e| e.to_string())?
+            .map_err(|e| e.to_string())?;
+
+        let dispatch = {
+            let data = slice.get_mapped_range();
+            let word =
+                |offset: usize| u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
+
+            let count = word(0);
+            let mut histogram = [0u32; HISTOGRAM_BUCKETS];
+            for (i, bucket) in histogram.iter_mut().enumerate() {
+                *bucket = word(histogram_offset as usize + i * 4);
+            }
+
+            let returned = (count as usize).min(self.slots);
+            let solutions = (0..returned)
+                .map(|slot| {
+                    let solution = solutions_offset as usize + slot * 8;
+                    let solution =
+                        u64::from_le_bytes(data[solution..solution + 8].try_into().unwrap());
+                    let address = addresses_offset as usize + slot * 20;
+                    (solution, data[address..address + 20].try_into().unwrap(), 0)
+                })
+                .collect();
+
+            Dispatch {
+                count,
+                solutions,
+                histogram,
+            }
+        };
+        self.readback.unmap();
+
+        Ok(dispatch)
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    // the thresholds and minimum score are passed with every dispatch
+    fn retune(&mut self, tuning: &Tuning) -> bool {
+        self.leading_zeroes = tuning.leading_zeroes_threshold as u32;
+        self.total_zeroes = tuning.total_zeroes_threshold as u32;
+        if let Some(scoring) = &mut self.scoring {
+            scoring.min_score = tuning.min_score;
+        }
+        true
+    }
+}
@@ -0,0 +1,221 @@
+use crate::submit::Queue;
+use serde_json::Value;
+use std::io::ErrorKind;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use tungstenite::client::IntoClientRequest;
+use tungstenite::http::{HeaderName, HeaderValue};
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Message, WebSocket};
+
+// the first delay before reconnecting, doubled after every failed attempt
+const RECONNECT_MIN_DELAY: Duration = Duration::from_secs(1);
+
+// the longest delay between attempts to reconnect
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(60);
+
+// how long to wait for a connection to be established
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+// how long to wait for results before reading from the socket, which answers
+// pings and notices a closed connection
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+type Socket = WebSocket<MaybeTlsStream<TcpStream>>;
+
+/// Streams results to a WebSocket endpoint (`--ws-endpoint`) over a persistent
+/// connection, each as a JSON text message, reconnecting with exponential
+/// backoff whenever the connection drops. Results are only handed to it while
+/// it is connected; any that it then fails to send, or that do not fit in its
+/// bounded queue, go to the fallback queues (of the HTTP reporters) instead.
+pub(crate) struct Streamer {
+    results: mpsc::SyncSender<Value>,
+    fallback: Vec<Queue>,
+    connected: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+impl Streamer {
+    pub(crate) fn new(
+        url: String,
+        headers: &[(String, String)],
+        fallback: Vec<Queue>,
+        queue_len: usize,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        // check the URL and headers up front rather than on every reconnect
+        let mut request = url.as_str().into_client_request()?;
+        for (name, value) in headers {
+            request.headers_mut().append(
+                HeaderName::from_bytes(name.as_bytes())?,
+                HeaderValue::from_str(value)?,
+            );
+        }
+
+        let connected = Arc::new(AtomicBool::new(false));
+        let (results, received) = mpsc::sync_channel(queue_len);
+        let thread = {
+            let connected = connected.clone();
+            let fallback = fallback.clone();
+            thread::spawn(move || stream(&url, request, &received, &connected, &fallback))
+        };
+
+        Ok(Self {
+            results,
+            fallback,
+            connected,
+            thread,
+        })
+    }
+
+    pub(crate) fn connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
+    /// Queue a result for the connection, or hand it to the HTTP reporters if
+    /// the queue is full.
+    pub(crate) fn send(&self, payload: Value) {
+        match self.results.try_send(payload) {
+            Ok(()) => {}
+            Err(mpsc::TrySendError::Full(payload)) => hand_over(payload, &self.fallback),
+            // the thread only stops once the sender is dropped
+            Err(mpsc::TrySendError::Disconnected(_)) => unreachable!(),
+        }
+    }
+
+    /// Send whatever is still queued and close the connection.
+    pub(crate) fn finish(self) {
+        drop(self.results);
+        drop(self.fallback);
+        let _ = self.thread.join();
+    }
+}
+
+/// Keep a connection to the endpoint open and send results over it until the
+/// channel is closed.
+fn stream(
+    url: &str,
+    request: tungstenite::handshake::client::Request,
+    received: &mpsc::Receiver<Value>,
+    connected: &AtomicBool,
+    fallback: &[Queue],
+) {
+    let mut socket: Option<Socket> = None;
+    let mut delay = RECONNECT_MIN_DELAY;
+    let mut next_attempt = Instant::now();
+
+    loop {
+        if socket.is_none() && Instant::now() >= next_attempt {
+            match connect(request.clone()) {
+                Ok(connection) => {
+                    info!("streaming results to {url}");
+                    socket = Some(connection);
+                    connected.store(true, Ordering::Relaxed);
+                    delay = RECONNECT_MIN_DELAY;
+                }
+                Err(error) => {
+                    warn!("could not connect to {url}, retrying in {delay:?}: {error}");
+                    next_attempt = Instant::now() + delay;
+                    delay = (delay * 2).min(RECONNECT_MAX_DELAY);
+                }
+            }
+        }
+
+        let outcome = match received.recv_timeout(POLL_INTERVAL) {
+            Ok(payload) => match socket.as_mut() {
+                Some(connection) => {
+                    let sent = connection
+                        .send(Message::text(payload.to_string()))
+                        .map_err(|e| e.to_string());
+                    if sent.is_err() {
+                        hand_over(payload, fallback);
+                    }
+                    sent
+                }
+                None => {
+                    hand_over(payload, fallback);
+                    Ok(())
+                }
+            },
+            Err(mpsc::RecvTimeoutError::Timeout) => match socket.as_mut() {
+                Some(connection) => poll(connection),
+                None => Ok(()),
+            },
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                if let Some(mut connection) = socket.take() {
+                    let _ = connection.close(None);
+                    let _ = connection.flush();
+                }
+                return;
+            }
+        };
+
+        if let Err(error) = outcome {
+            warn!("lost the connection to {url}, falling back to HTTP: {error}");
+            socket = None;
+            connected.store(false, Ordering::Relaxed);
+            next_attempt = Instant::now() + delay;
+
+            // anything queued while the connection was up goes over HTTP
+            for payload in received.try_iter() {
+                hand_over(payload, fallback);
+            }
+        }
+    }
+}
+
+/// Open a connection, leaving reads on it to time out quickly so that they can
+/// be interleaved with sending results.
+fn connect(request: tungstenite::handshake::client::Request) -> Result<Socket, String> {
+    let uri = request.uri();
+    let host = uri.host().ok_or("missing host")?;
+    let port = uri
+        .port_u16()
+        .unwrap_or(if uri.scheme_str() == Some("wss") {
+            443
+        } else {
+            80
+        });
+    let address = (host, port)
+        .to_socket_addrs()
+        .map_err(|e| e.to_string())?
+        .next()
+        .ok_or("could not resolve host")?;
+
+    let tcp = TcpStream::connect_timeout(&address, CONNECT_TIMEOUT).map_err(|e| e.to_string())?;
+    tcp.set_read_timeout(Some(CONNECT_TIMEOUT))
+        .map_err(|e| e.to_string())?;
+    let handle = tcp.try_clone().map_err(|e| e.to_string())?;
+    let (socket, _) = tungstenite::client_tls(request, tcp).map_err(|e| e.to_string())?;
+    handle
+        .set_read_timeout(Some(Duration::from_millis(1)))
+        .map_err(|e| e.to_string())?;
+    Ok(socket)
+}
+
+/// Read whatever the endpoint has sent (answering pings), failing if the
+/// connection has been closed.
+fn poll(connection: &mut Socket) -> Result<(), String> {
+    loop {
+        match connection.read() {
+            Ok(Message::Close(_)) => return Err("the endpoint closed the connection".into()),
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(error))
+                if matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+            {
+                return connection.flush().map_err(|e| e.to_string());
+            }
+            Err(error) => return Err(error.to_string()),
+        }
+    }
+}
+
+/// Pass a result that could not be streamed on to the HTTP reporters.
+fn hand_over(payload: Value, fallback: &[Queue]) {
+    for queue in fallback {
+        queue.push(payload.clone());
+    }
+}